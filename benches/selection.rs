@@ -0,0 +1,29 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{EvalConfig, eval_with_rng, parse};
+use std::hint::black_box;
+
+fn large_pool_selection(c: &mut Criterion) {
+    let config = EvalConfig {
+        max_rolls: 100_000,
+        ..EvalConfig::default()
+    };
+    for expr in [
+        "5000d6kh2500",
+        "5000d6p<3kl100",
+        "(1d6, 1d6, 1d6, 1d6, 1d6, 1d6)kh3",
+    ] {
+        let ast = parse(&expr).unwrap();
+        c.bench_function(expr, |b| {
+            let mut seed = 0;
+            b.iter(|| {
+                seed += 1;
+                let rng = StdRng::seed_from_u64(seed);
+                black_box(eval_with_rng(&ast, config.clone(), rng).unwrap())
+            })
+        });
+    }
+}
+
+criterion_group!(benches, large_pool_selection);
+criterion_main!(benches);
@@ -3,7 +3,7 @@
 
 use rollatorium::roll;
 
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 
 fn main() {
     println!("Rollatorium REPL. Type a dice expression and press Enter. Ctrl-C to exit.");
@@ -22,9 +22,18 @@ fn main() {
                 match roll(&trimmed) {
                     Ok(result) => {
                         println!("Result: {}", result.total);
-                        println!("Details: {:?}", result);
+                        if io::stdout().is_terminal() {
+                            println!("Details: {}", result.to_ansi());
+                        } else {
+                            println!("Details: {}", result);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        if let Some(diagnostic) = e.caret_diagnostic(trimmed) {
+                            eprintln!("{}", diagnostic);
+                        }
                     }
-                    Err(e) => eprintln!("Error: {}", e),
                 }
             }
             Err(error) => {
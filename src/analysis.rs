@@ -0,0 +1,837 @@
+// ---------- Analysis ----------
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::Result;
+use crate::ast::{
+    BinaryOperator, DiceSize, Node, SelectorKind, SetOperation, SetOperator, UnaryOperator,
+};
+use crate::error::RollatoriumError::Eval;
+use crate::eval::{EPSILON, EvalConfig, Evaluator, selector_matches};
+
+/// Distinct totals beyond which exact enumeration gives up and samples instead.
+const MAX_OUTCOMES: usize = 10_000;
+/// Pairings or dice pools one exact enumeration step may visit.
+const MAX_ENUMERATION: usize = 1_000_000;
+/// Trials drawn when an expression can't be enumerated exactly.
+const SAMPLE_TRIALS: usize = 10_000;
+/// Seed for sampling, so repeated analyses of one expression agree.
+const SAMPLE_SEED: u64 = 0x5EED_D1CE;
+
+/// Returns how lucky `total` is for `expr`: the probability of rolling a total
+/// less than or equal to it. The distribution is computed exactly where the
+/// expression can be enumerated (plain and keep/drop dice, sets without
+/// operations, arithmetic) and sampled otherwise, e.g. for explosions.
+pub fn luck(expr: &Node, total: f64, config: EvalConfig) -> Result<f64> {
+    Ok(Outcomes::of(expr, &config)?.cumulative(total))
+}
+
+/// Returns the expected net successes of a success-counting pool such as
+/// `6d10cs>=8`: the dice count times the chance a die counts as a success,
+/// less the chance it counts as a failure under `cf`. Only constant pools
+/// whose operations are all value-selector `cs`/`cf` are supported.
+pub fn expected_successes(expr: &Node, config: EvalConfig) -> Result<f64> {
+    let unsupported = || {
+        Eval(format!(
+            "expected_successes needs a dice pool with cs/cf operations, found '{}'",
+            expr
+        ))
+    };
+    let pool = DicePool::of(expr).ok_or_else(unsupported)?;
+    let counts_only = pool.operations.iter().all(|operation| {
+        matches!(
+            operation.operator,
+            SetOperator::CountSuccess | SetOperator::CountFailure
+        )
+    });
+    if !counts_only {
+        return Err(unsupported());
+    }
+
+    let quantity = pool.quantity(&config, "expected_successes")?;
+    let faces = pool.faces(&config)?;
+    let mut expected = 0.0;
+    for operation in pool.operations {
+        let sign = match operation.operator {
+            SetOperator::CountSuccess => 1.0,
+            _ => -1.0,
+        };
+        let mut targets = Vec::with_capacity(operation.selectors.len());
+        for selector in &operation.selectors {
+            targets.push((
+                selector.kind,
+                constant(
+                    &selector.target,
+                    &config,
+                    "selector target",
+                    "expected_successes",
+                )?,
+            ));
+        }
+        for &(face, probability) in &faces.points {
+            let mut counted = false;
+            for &(kind, target) in &targets {
+                counted |= selector_matches(kind, face, target, config.selector_epsilon)
+                    .ok_or_else(|| {
+                        Eval("Positional selectors have no per-die success chance".into())
+                    })?;
+            }
+            if counted {
+                expected += sign * probability;
+            }
+        }
+    }
+    Ok(quantity * expected)
+}
+
+/// Summary statistics of an expression's totals, computed together from one
+/// distribution by [`profile`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExprProfile {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub variance: f64,
+    /// The most likely total; the lowest one on a tie.
+    pub mode: f64,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+    /// Each total with its probability, lowest total first.
+    pub histogram: Vec<(f64, f64)>,
+    /// Whether the distribution was enumerated exactly rather than sampled.
+    pub exact: bool,
+}
+
+/// Profiles the totals of `expr` from a single distribution: exact where the
+/// expression can be enumerated, as for [`luck`], and sampled otherwise.
+/// Percentiles are the lowest total whose cumulative probability reaches them.
+pub fn profile(expr: &Node, config: EvalConfig) -> Result<ExprProfile> {
+    let (outcomes, exact) = match Outcomes::exact(expr, &config) {
+        Some(outcomes) => (outcomes, true),
+        None => (Outcomes::sampled(expr, &config, SAMPLE_TRIALS)?, false),
+    };
+    let points = outcomes.points;
+    let mean: f64 = points.iter().map(|(total, p)| total * p).sum();
+    let variance = points
+        .iter()
+        .map(|(total, p)| p * (total - mean).powi(2))
+        .sum();
+    let mode = points
+        .iter()
+        .fold(
+            points[0],
+            |best, &point| if point.1 > best.1 { point } else { best },
+        )
+        .0;
+    let percentile = |fraction: f64| {
+        let mut cumulative = 0.0;
+        for &(total, p) in &points {
+            cumulative += p;
+            if cumulative >= fraction - 1e-9 {
+                return total;
+            }
+        }
+        points[points.len() - 1].0
+    };
+    let (p10, p50, p90) = (percentile(0.1), percentile(0.5), percentile(0.9));
+    Ok(ExprProfile {
+        min: points[0].0,
+        max: points[points.len() - 1].0,
+        mean,
+        variance,
+        mode,
+        p10,
+        p50,
+        p90,
+        histogram: points,
+        exact,
+    })
+}
+
+/// Returns how far the `mi`/`ma` operations of a pool such as `2d6mi3` move
+/// its expected total away from the same pool unclamped; positive when
+/// clamping raises the mean. The shift is exact over the die's faces, so only
+/// constant pools whose operations are all plain `mi`/`ma` are supported.
+pub fn clamp_mean_shift(expr: &Node, config: EvalConfig) -> Result<f64> {
+    let unsupported = || {
+        Eval(format!(
+            "clamp_mean_shift needs a dice pool with mi/ma operations, found '{}'",
+            expr
+        ))
+    };
+    let pool = DicePool::of(expr).ok_or_else(unsupported)?;
+    let clamps_only = pool.operations.iter().all(|operation| {
+        matches!(
+            operation.operator,
+            SetOperator::Minimum | SetOperator::Maximum
+        ) && matches!(
+            operation.selectors.as_slice(),
+            [selector] if selector.kind == SelectorKind::Literal
+        )
+    });
+    if !clamps_only {
+        return Err(unsupported());
+    }
+
+    let quantity = pool.quantity(&config, "clamp_mean_shift")?;
+    let faces = pool.faces(&config)?;
+    let mut thresholds = Vec::with_capacity(pool.operations.len());
+    for operation in pool.operations {
+        thresholds.push((
+            operation.operator,
+            constant(
+                &operation.selectors[0].target,
+                &config,
+                "clamp threshold",
+                "clamp_mean_shift",
+            )?,
+        ));
+    }
+    let mut shift = 0.0;
+    for &(face, probability) in &faces.points {
+        let clamped =
+            thresholds
+                .iter()
+                .fold(face, |value, &(operator, threshold)| match operator {
+                    SetOperator::Minimum => value.max(threshold),
+                    _ => value.min(threshold),
+                });
+        shift += probability * (clamped - face);
+    }
+    Ok(quantity * shift)
+}
+
+/// Returns the lowest and highest totals `expr` can roll, worked out from the
+/// syntax tree without rolling. Keeping or dropping the highest or lowest few
+/// dice narrows a pool to that many dice, and explosions and `ra` are bounded
+/// by the default `max_rolls`, as no evaluation rolls more dice than that, so
+/// the bounds are not always attained.
+pub fn bounds(expr: &Node) -> Result<(f64, f64)> {
+    expr_bounds(expr, &EvalConfig::default())
+}
+
+fn expr_bounds(expr: &Node, config: &EvalConfig) -> Result<(f64, f64)> {
+    let unsupported = || Eval(format!("Cannot bound '{}' without rolling", expr));
+    match expr {
+        Node::Literal(value) => Ok((*value, *value)),
+        Node::Unary { operator, operand } => {
+            let (low, high) = expr_bounds(operand, config)?;
+            Ok(match operator {
+                UnaryOperator::Plus => (low, high),
+                UnaryOperator::Minus => (-high, -low),
+            })
+        }
+        Node::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            let (a, b) = expr_bounds(left, config)?;
+            let (c, d) = expr_bounds(right, config)?;
+            match operator {
+                BinaryOperator::Add => Ok((a + c, b + d)),
+                BinaryOperator::Subtract => Ok((a - d, b - c)),
+                // Each of these is monotonic in both operands (division only
+                // while the divisor keeps its sign), so the extremes are corners.
+                BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::IntDivide
+                | BinaryOperator::CeilDivide
+                    if *operator == BinaryOperator::Multiply || c > 0.0 || d < 0.0 =>
+                {
+                    let corners = [
+                        operator.apply(a, c),
+                        operator.apply(a, d),
+                        operator.apply(b, c),
+                        operator.apply(b, d),
+                    ];
+                    if corners.iter().any(|corner| corner.is_nan()) {
+                        return Err(unsupported());
+                    }
+                    Ok((
+                        corners.into_iter().fold(f64::INFINITY, f64::min),
+                        corners.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                    ))
+                }
+                BinaryOperator::Equal
+                | BinaryOperator::NotEqual
+                | BinaryOperator::Greater
+                | BinaryOperator::GreaterEqual
+                | BinaryOperator::Less
+                | BinaryOperator::LessEqual => Ok((0.0, 1.0)),
+                _ => Err(unsupported()),
+            }
+        }
+        Node::Dice { num, size } => pool_bounds(expr, num.as_deref(), size, &[], config),
+        Node::DiceWithOps { dice, operations } => match dice.as_ref() {
+            Node::Dice { num, size } => pool_bounds(expr, num.as_deref(), size, operations, config),
+            _ => Err(unsupported()),
+        },
+        Node::Set {
+            elements,
+            operations,
+        } => {
+            let selects_only = operations.iter().all(|operation| {
+                matches!(operation.operator, SetOperator::Keep | SetOperator::Drop)
+            });
+            if !selects_only {
+                return Err(unsupported());
+            }
+            let (mut low, mut high) = (0.0, 0.0);
+            for element in elements {
+                let (element_low, element_high) = expr_bounds(element, config)?;
+                // Any element may be dropped, leaving it out of the total.
+                if operations.is_empty() {
+                    low += element_low;
+                    high += element_high;
+                } else {
+                    low += element_low.min(0.0);
+                    high += element_high.max(0.0);
+                }
+            }
+            Ok((low, high))
+        }
+        Node::Repeat { count, expr } => {
+            let (low, high) = expr_bounds(expr, config)?;
+            Ok((*count as f64 * low, *count as f64 * high))
+        }
+        Node::Annotated { expr, .. } => expr_bounds(expr, config),
+        _ => Err(unsupported()),
+    }
+}
+
+/// Bounds a dice pool by the range of dice it can end up counting and the
+/// range of values each of them can show.
+fn pool_bounds(
+    pool: &Node,
+    quantity: Option<&Node>,
+    size: &DiceSize,
+    operations: &[SetOperation],
+    config: &EvalConfig,
+) -> Result<(f64, f64)> {
+    let unsupported = || Eval(format!("Cannot bound '{}' without rolling", pool));
+    let (mut count_low, mut count_high) = match quantity {
+        Some(node) => expr_bounds(node, config)?,
+        None => (1.0, 1.0),
+    };
+    count_low = count_low.max(0.0);
+    let (mut face_low, mut face_high) = face_bounds(size, config)?;
+    let max_rolls = config.max_rolls as f64;
+    // The dice counted before the current run of keeps, which later keeps in
+    // the run add to their union from.
+    let mut keep_base = None;
+    for operation in operations {
+        if operation.after_parens
+            || !matches!(operation.operator, SetOperator::Keep | SetOperator::Drop)
+        {
+            keep_base = None;
+        }
+        match operation.operator {
+            SetOperator::Keep | SetOperator::Drop => match operation.selectors.as_slice() {
+                [selector]
+                    if matches!(
+                        selector.kind,
+                        SelectorKind::Highest | SelectorKind::Lowest | SelectorKind::Middle
+                    ) =>
+                {
+                    let (n_low, n_high) = expr_bounds(&selector.target, config)?;
+                    if operation.operator == SetOperator::Keep {
+                        match keep_base {
+                            None => {
+                                keep_base = Some((count_low, count_high));
+                                count_low = count_low.min(n_low.max(0.0));
+                                count_high = count_high.min(n_high.max(0.0));
+                            }
+                            Some((base_low, base_high)) => {
+                                count_low = count_low.max(base_low.min(n_low.max(0.0)));
+                                count_high = base_high.min(count_high + n_high.max(0.0));
+                            }
+                        }
+                    } else {
+                        count_low = (count_low - n_high).max(0.0);
+                        count_high = (count_high - n_low).max(0.0);
+                    }
+                }
+                // Value selectors can leave any number of the dice.
+                _ => {
+                    if operation.operator == SetOperator::Keep {
+                        match keep_base {
+                            None => keep_base = Some((count_low, count_high)),
+                            Some((_, base_high)) => count_high = base_high,
+                        }
+                    }
+                    count_low = 0.0;
+                }
+            },
+            SetOperator::WeightedKeep => count_low = 0.0,
+            SetOperator::Explode | SetOperator::RerollAdd => count_high = max_rolls,
+            SetOperator::Penetrate => {
+                count_high = max_rolls;
+                face_low -= 1.0;
+            }
+            // Every roll a compounding die adds still counts toward `max_rolls`.
+            SetOperator::ExplodeCompound => {
+                face_low = face_low.min(max_rolls * face_low);
+                face_high = face_high.max(max_rolls * face_high);
+            }
+            SetOperator::Minimum | SetOperator::Maximum => match operation.selectors.as_slice() {
+                [selector] if selector.kind == SelectorKind::Literal => {
+                    let (t_low, t_high) = expr_bounds(&selector.target, config)?;
+                    if operation.operator == SetOperator::Minimum {
+                        face_low = face_low.max(t_low);
+                        face_high = face_high.max(t_high);
+                    } else {
+                        face_low = face_low.min(t_low);
+                        face_high = face_high.min(t_high);
+                    }
+                }
+                _ => return Err(unsupported()),
+            },
+            SetOperator::Reroll
+            | SetOperator::RerollOnce
+            | SetOperator::RerollBetter
+            | SetOperator::RerollWorse
+            | SetOperator::BonusExplode
+            | SetOperator::SortAscending
+            | SetOperator::SortDescending
+            | SetOperator::Spread
+            | SetOperator::Straight
+            | SetOperator::CountSuccess
+            | SetOperator::CountFailure => {}
+            // Each added roll counts one less, down to `max_rolls` of them.
+            SetOperator::ExplodePenetrate => {
+                face_low = face_low.min(max_rolls * (face_low - 1.0));
+                face_high = face_high.max(max_rolls * face_high);
+            }
+            SetOperator::StepExplode => return Err(unsupported()),
+        }
+    }
+
+    let has = |operator| {
+        operations
+            .iter()
+            .any(|operation| operation.operator == operator)
+    };
+    if has(SetOperator::Spread) {
+        Ok((0.0, (face_high - face_low).max(0.0)))
+    } else if has(SetOperator::Straight) {
+        Ok((0.0, count_high))
+    } else if has(SetOperator::CountSuccess) || has(SetOperator::CountFailure) {
+        let low = if has(SetOperator::CountFailure) {
+            -count_high
+        } else {
+            0.0
+        };
+        let high = if has(SetOperator::CountSuccess) {
+            count_high
+        } else {
+            0.0
+        };
+        Ok((low, high))
+    } else {
+        Ok((
+            (count_low * face_low).min(count_high * face_low),
+            (count_low * face_high).max(count_high * face_high),
+        ))
+    }
+}
+
+/// The lowest and highest value a single die of `size` can show.
+fn face_bounds(size: &DiceSize, config: &EvalConfig) -> Result<(f64, f64)> {
+    match size {
+        DiceSize::Value(sides) => {
+            let (_, high) = expr_bounds(sides, config)?;
+            Ok((1.0, high.max(1.0)))
+        }
+        DiceSize::Percent => Ok((0.0, 90.0)),
+        DiceSize::Percentile => Ok((1.0, 100.0)),
+        DiceSize::Fudge => Ok((-1.0, 1.0)),
+        DiceSize::ExprTable(entries) => {
+            let (mut low, mut high) = (f64::INFINITY, f64::NEG_INFINITY);
+            for entry in entries {
+                let (entry_low, entry_high) = expr_bounds(entry, config)?;
+                low = low.min(entry_low);
+                high = high.max(entry_high);
+            }
+            Ok((low, high))
+        }
+        DiceSize::Custom(name) => {
+            let faces = config
+                .dice
+                .faces(name)
+                .ok_or_else(|| Eval(format!("Unknown custom die 'd{}'", name)))?;
+            Ok((
+                faces.iter().copied().fold(f64::INFINITY, f64::min),
+                faces.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            ))
+        }
+    }
+}
+
+impl Node {
+    /// Returns whether some roll of the expression totals `target`. The answer
+    /// is exact where the distribution can be enumerated, as for [`luck`];
+    /// otherwise only targets outside the expression's bounds are ruled out,
+    /// and anything within them, or any expression without bounds, is reported
+    /// as producible.
+    pub fn can_produce(&self, target: f64, config: EvalConfig) -> Result<bool> {
+        let epsilon = config.selector_epsilon;
+        if let Some(outcomes) = Outcomes::exact(self, &config) {
+            return Ok(outcomes
+                .points
+                .iter()
+                .any(|(total, _)| (total - target).abs() <= epsilon));
+        }
+        // A seeded roll reports evaluation errors, as sampling would for `luck`.
+        let mut rng = StdRng::seed_from_u64(SAMPLE_SEED);
+        Evaluator::new(&mut rng, config.clone()).eval(self)?;
+        Ok(match expr_bounds(self, &config) {
+            Ok((low, high)) => low - epsilon <= target && target <= high + epsilon,
+            Err(_) => true,
+        })
+    }
+}
+
+/// A single dice pool with operations, optionally annotated, as the per-die
+/// analyses accept.
+struct DicePool<'a> {
+    dice: &'a Node,
+    num: Option<&'a Node>,
+    size: &'a DiceSize,
+    operations: &'a [SetOperation],
+}
+
+impl<'a> DicePool<'a> {
+    fn of(expr: &'a Node) -> Option<Self> {
+        let pool = match expr {
+            Node::Annotated { expr, .. } => expr.as_ref(),
+            other => other,
+        };
+        let Node::DiceWithOps { dice, operations } = pool else {
+            return None;
+        };
+        let Node::Dice { num, size } = dice.as_ref() else {
+            return None;
+        };
+        Some(Self {
+            dice,
+            num: num.as_deref(),
+            size,
+            operations,
+        })
+    }
+
+    fn quantity(&self, config: &EvalConfig, analysis: &str) -> Result<f64> {
+        match self.num {
+            Some(node) => constant(node, config, "dice quantity", analysis),
+            None => Ok(1.0),
+        }
+    }
+
+    fn faces(&self, config: &EvalConfig) -> Result<Outcomes> {
+        Outcomes::die_faces(self.size, config)
+            .ok_or_else(|| Eval(format!("Cannot enumerate the faces of '{}'", self.dice)))
+    }
+}
+
+/// Evaluates `node` as a constant, naming `context` when it involves dice.
+fn constant(node: &Node, config: &EvalConfig, context: &str, analysis: &str) -> Result<f64> {
+    Outcomes::exact(node, config)
+        .and_then(|outcomes| outcomes.as_point())
+        .ok_or_else(|| Eval(format!("{} must be a constant for {}", context, analysis)))
+}
+
+/// A discrete distribution of totals, sorted by total, whose probabilities sum to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Outcomes {
+    points: Vec<(f64, f64)>,
+}
+
+impl Outcomes {
+    /// The exact distribution when it can be enumerated, else a sampled one.
+    pub(crate) fn of(expr: &Node, config: &EvalConfig) -> Result<Self> {
+        match Self::exact(expr, config) {
+            Some(outcomes) => Ok(outcomes),
+            None => Self::sampled(expr, config, SAMPLE_TRIALS),
+        }
+    }
+
+    /// Enumerates the distribution of `expr`, or `None` when it is too large or
+    /// uses operations that can't be enumerated.
+    pub(crate) fn exact(expr: &Node, config: &EvalConfig) -> Option<Self> {
+        match expr {
+            Node::Literal(value) => Some(Self::point(*value)),
+            Node::Unary { operator, operand } => {
+                let operand = Self::exact(operand, config)?;
+                Some(match operator {
+                    UnaryOperator::Plus => operand,
+                    UnaryOperator::Minus => operand.map(|total| -total)?,
+                })
+            }
+            Node::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = Self::exact(left, config)?;
+                let right = Self::exact(right, config)?;
+                // Leave `//`, `/^` or `%` by zero to the evaluator to report.
+                if matches!(
+                    operator,
+                    BinaryOperator::IntDivide | BinaryOperator::CeilDivide | BinaryOperator::Modulo
+                ) && right.points.iter().any(|(total, _)| total.abs() < EPSILON)
+                {
+                    return None;
+                }
+                left.combine(&right, |l, r| operator.apply(l, r))
+            }
+            Node::Dice { num, size } => Self::exact_dice(num.as_deref(), size, &[], config),
+            Node::DiceWithOps { dice, operations } => match dice.as_ref() {
+                Node::Dice { num, size } => {
+                    let all_selections = operations.iter().all(|operation| {
+                        matches!(
+                            operation.operator,
+                            SetOperator::Keep
+                                | SetOperator::Drop
+                                | SetOperator::SortAscending
+                                | SetOperator::SortDescending
+                        )
+                    });
+                    if !all_selections {
+                        return None;
+                    }
+                    Self::exact_dice(num.as_deref(), size, operations, config)
+                }
+                _ => None,
+            },
+            Node::Set {
+                elements,
+                operations,
+            } if operations.is_empty() => {
+                elements.iter().try_fold(Self::point(0.0), |sum, element| {
+                    sum.combine(&Self::exact(element, config)?, |l, r| l + r)
+                })
+            }
+            Node::Annotated { expr, .. } => Self::exact(expr, config),
+            _ => None,
+        }
+    }
+
+    /// Estimates the distribution of `expr` from `trials` seeded evaluations.
+    pub(crate) fn sampled(expr: &Node, config: &EvalConfig, trials: usize) -> Result<Self> {
+        let mut rng = StdRng::seed_from_u64(SAMPLE_SEED);
+        let mut points = Vec::with_capacity(trials);
+        for _ in 0..trials {
+            let total = Evaluator::new(&mut rng, config.clone()).eval(expr)?.total;
+            points.push((total, 1.0));
+        }
+        Ok(Self::from_weights(points))
+    }
+
+    /// The probability of a total less than or equal to `total`.
+    pub(crate) fn cumulative(&self, total: f64) -> f64 {
+        let probability: f64 = self
+            .points
+            .iter()
+            .take_while(|(value, _)| *value <= total)
+            .map(|(_, probability)| probability)
+            .sum();
+        probability.min(1.0)
+    }
+
+    fn point(total: f64) -> Self {
+        Outcomes {
+            points: vec![(total, 1.0)],
+        }
+    }
+
+    /// Builds a distribution from `(total, weight)` pairs, merging equal totals
+    /// and normalizing the weights.
+    fn from_weights(mut points: Vec<(f64, f64)>) -> Self {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut merged: Vec<(f64, f64)> = Vec::with_capacity(points.len());
+        for (total, weight) in points {
+            match merged.last_mut() {
+                Some(last) if last.0 == total => last.1 += weight,
+                _ => merged.push((total, weight)),
+            }
+        }
+        let sum: f64 = merged.iter().map(|(_, weight)| weight).sum();
+        for point in &mut merged {
+            point.1 /= sum;
+        }
+        Outcomes { points: merged }
+    }
+
+    fn within_limits(self) -> Option<Self> {
+        (self.points.len() <= MAX_OUTCOMES).then_some(self)
+    }
+
+    fn map(&self, f: impl Fn(f64) -> f64) -> Option<Self> {
+        let points = self
+            .points
+            .iter()
+            .map(|&(total, p)| (f(total), p))
+            .collect();
+        Self::from_weights(points).within_limits()
+    }
+
+    fn combine(&self, other: &Self, f: impl Fn(f64, f64) -> f64) -> Option<Self> {
+        if self.points.len().checked_mul(other.points.len())? > MAX_ENUMERATION {
+            return None;
+        }
+        let mut points = Vec::with_capacity(self.points.len() * other.points.len());
+        for &(left, p) in &self.points {
+            for &(right, q) in &other.points {
+                points.push((f(left, right), p * q));
+            }
+        }
+        Self::from_weights(points).within_limits()
+    }
+
+    fn as_point(&self) -> Option<f64> {
+        match self.points.as_slice() {
+            [(total, _)] => Some(*total),
+            _ => None,
+        }
+    }
+
+    /// The distribution of a single die of `size`.
+    fn die_faces(size: &DiceSize, config: &EvalConfig) -> Option<Self> {
+        match size {
+            DiceSize::Percent => Some(Self::from_weights(
+                (0..10).map(|n| (n as f64 * 10.0, 1.0)).collect(),
+            )),
+            DiceSize::Percentile => Some(Self::from_weights(
+                (1..=100).map(|n| (n as f64, 1.0)).collect(),
+            )),
+            DiceSize::Fudge => Some(Self::from_weights(vec![
+                (-1.0, 1.0),
+                (0.0, 1.0),
+                (1.0, 1.0),
+            ])),
+            DiceSize::Value(node) => {
+                let sides = Self::exact(node, config)?.as_point()?;
+                if sides < 1.0 || sides.fract() != 0.0 || sides > MAX_OUTCOMES as f64 {
+                    return None;
+                }
+                Some(Self::from_weights(
+                    (1..=sides as usize).map(|n| (n as f64, 1.0)).collect(),
+                ))
+            }
+            DiceSize::ExprTable(entries) => {
+                let mut points = Vec::new();
+                for entry in entries {
+                    points.extend(Self::exact(entry, config)?.points);
+                }
+                Self::from_weights(points).within_limits()
+            }
+            DiceSize::Custom(name) => Some(Self::from_weights(
+                config
+                    .dice
+                    .faces(name)?
+                    .iter()
+                    .map(|&face| (face, 1.0))
+                    .collect(),
+            )),
+        }
+    }
+
+    fn exact_dice(
+        quantity: Option<&Node>,
+        size: &DiceSize,
+        operations: &[SetOperation],
+        config: &EvalConfig,
+    ) -> Option<Self> {
+        let quantity = match quantity {
+            Some(node) => Self::exact(node, config)?.as_point()?,
+            None => 1.0,
+        };
+        if quantity < 0.0 || quantity.fract() != 0.0 {
+            return None;
+        }
+        let quantity = quantity as usize;
+        // Pools the evaluator would reject are left to sampling to report.
+        if quantity > config.max_dice || quantity > config.max_rolls {
+            return None;
+        }
+
+        let faces = Self::die_faces(size, config)?;
+
+        if operations.is_empty() {
+            return (0..quantity)
+                .try_fold(Self::point(0.0), |sum, _| sum.combine(&faces, |l, r| l + r));
+        }
+
+        // Keep/drop totals depend on the whole pool, so walk every multiset of
+        // faces once, weighted by how many ordered rolls produce it.
+        for operation in operations {
+            for selector in &operation.selectors {
+                Self::exact(&selector.target, config)?.as_point()?;
+            }
+        }
+        if pool_count(quantity, faces.points.len())? > MAX_ENUMERATION {
+            return None;
+        }
+        let mut evaluator = Evaluator::new(StdRng::seed_from_u64(SAMPLE_SEED), config.clone());
+        let mut points = Vec::new();
+        let mut pool = Vec::with_capacity(quantity);
+        let mut visit = |pool: &[f64], weight: f64| -> Option<()> {
+            let total = evaluator.kept_total(pool, operations).ok()?;
+            points.push((total, weight));
+            Some(())
+        };
+        walk_pools(&faces.points, quantity, 1.0, &mut pool, &mut visit)?;
+        Self::from_weights(points).within_limits()
+    }
+}
+
+/// Number of distinct pools of `quantity` dice over `faces` faces, if it fits.
+fn pool_count(quantity: usize, faces: usize) -> Option<usize> {
+    let mut count: usize = 1;
+    for step in 1..faces {
+        count = count.checked_mul(quantity + step)? / step;
+        if count > MAX_ENUMERATION {
+            return Some(count);
+        }
+    }
+    Some(count)
+}
+
+/// Visits every multiset of `remaining` faces with its probability, choosing
+/// how many dice show each face in turn.
+fn walk_pools(
+    faces: &[(f64, f64)],
+    remaining: usize,
+    weight: f64,
+    pool: &mut Vec<f64>,
+    visit: &mut impl FnMut(&[f64], f64) -> Option<()>,
+) -> Option<()> {
+    let Some((&(face, probability), rest)) = faces.split_first() else {
+        return if remaining == 0 {
+            visit(pool, weight)
+        } else {
+            Some(())
+        };
+    };
+    if rest.is_empty() {
+        pool.extend(std::iter::repeat_n(face, remaining));
+        let result = visit(pool, weight * probability.powi(remaining as i32));
+        pool.truncate(pool.len() - remaining);
+        return result;
+    }
+    // `ways` is remaining-choose-count, the orderings of this face's dice.
+    let mut ways = 1.0;
+    for count in 0..=remaining {
+        if count > 0 {
+            ways = ways * (remaining - count + 1) as f64 / count as f64;
+            pool.push(face);
+        }
+        let chosen = weight * ways * probability.powi(count as i32);
+        walk_pools(rest, remaining - count, chosen, pool, visit)?;
+    }
+    pool.truncate(pool.len() - remaining);
+    Some(())
+}
@@ -9,6 +9,8 @@
 /// supporting types make it possible to extend the parser without having to
 /// redesign the tree structure later on.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Node {
     /// A numeric literal.
     Literal(f64),
@@ -38,6 +40,18 @@ pub enum Node {
         dice: Box<Node>,
         operations: Vec<SetOperation>,
     },
+    /// A function call such as `until(1d20 >= 15, 10)`.
+    Call { name: String, args: Vec<Node> },
+    /// An inclusive range such as `1..20`, only valid as a function argument.
+    Range { low: Box<Node>, high: Box<Node> },
+    /// A comparison against an implied value such as `<10`, only valid as a
+    /// function argument, as in `reroll_pool(4d6, <10, 3)`.
+    Condition(Selector),
+    /// A pool aggregate such as `mean`, only valid as a selector target where it
+    /// resolves against the pool being selected from.
+    Aggregate(Aggregate),
+    /// An expression rolled `count` times, written `3x1d6` or `1d6 x3`.
+    Repeat { count: u32, expr: Box<Node> },
     /// An annotated expression, e.g. `4d6 [strength]`.
     Annotated {
         expr: Box<Node>,
@@ -45,15 +59,57 @@ pub enum Node {
     },
 }
 
-/// The size of a die (e.g. 6 for d6 or percent for d%).
+/// Builds `{count}d{sides}kh{keep}`, the pool advantage rules roll: `2d20kh1`
+/// is advantage and `3d20kh1` elven accuracy.
+pub fn keep_highest(count: u32, sides: u32, keep: u32) -> Node {
+    keep_pool(count, sides, keep, SelectorKind::Highest)
+}
+
+/// Builds `{count}d{sides}kl{keep}`, as `2d20kl1` rolls disadvantage.
+pub fn keep_lowest(count: u32, sides: u32, keep: u32) -> Node {
+    keep_pool(count, sides, keep, SelectorKind::Lowest)
+}
+
+fn keep_pool(count: u32, sides: u32, keep: u32, kind: SelectorKind) -> Node {
+    Node::DiceWithOps {
+        dice: Box::new(Node::Dice {
+            num: Some(Box::new(Node::Literal(count.into()))),
+            size: DiceSize::Value(Box::new(Node::Literal(sides.into()))),
+        }),
+        operations: vec![SetOperation {
+            operator: SetOperator::Keep,
+            selectors: vec![Selector {
+                kind,
+                target: Box::new(Node::Literal(keep.into())),
+            }],
+            after_parens: false,
+        }],
+    }
+}
+
+/// The size of a die (e.g. 6 for d6, `(1d6)`, or percent for d%).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum DiceSize {
     Value(Box<Node>),
+    /// A percentile die showing 0, 10, ..., 90. Selectors compare against
+    /// these scaled values, so `d%k>30` keeps 40 and up.
     Percent,
+    /// A percentile die, `d%%`, showing 1 to 100 like a `d100`, for systems
+    /// that read `d%` as a flat roll rather than a tens die.
+    Percentile,
+    /// A Fudge or Fate die, `dF`, showing -1, 0 or +1.
+    Fudge,
+    /// A table die such as `d[1d4, 2d6]`, which rolls one of its entries.
+    ExprTable(Vec<Node>),
+    /// A die registered by name in the config's `DiceRegistry`, as in `2dRune`.
+    Custom(String),
 }
 
 /// Unary operators supported by the language.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnaryOperator {
     Plus,
     Minus,
@@ -61,13 +117,18 @@ pub enum UnaryOperator {
 
 /// Binary operators supported by the language.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOperator {
     Add,
     Subtract,
     Multiply,
     Divide,
     IntDivide,
+    /// `/^`, dividing and rounding up, as in "half your level, rounded up".
+    CeilDivide,
     Modulo,
+    /// `^` or `**`, binding right to left so `2^3^2` is `2^(3^2)`.
+    Power,
     Equal,
     NotEqual,
     Greater,
@@ -76,18 +137,107 @@ pub enum BinaryOperator {
     LessEqual,
 }
 
+impl BinaryOperator {
+    pub const ALL: [BinaryOperator; 14] = [
+        BinaryOperator::Add,
+        BinaryOperator::Subtract,
+        BinaryOperator::Multiply,
+        BinaryOperator::Divide,
+        BinaryOperator::IntDivide,
+        BinaryOperator::CeilDivide,
+        BinaryOperator::Modulo,
+        BinaryOperator::Power,
+        BinaryOperator::Equal,
+        BinaryOperator::NotEqual,
+        BinaryOperator::Greater,
+        BinaryOperator::GreaterEqual,
+        BinaryOperator::Less,
+        BinaryOperator::LessEqual,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::IntDivide => "//",
+            BinaryOperator::CeilDivide => "/^",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Power => "^",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+        }
+    }
+
+    /// Applies the operator to two totals; comparisons yield 1 or 0.
+    pub(crate) fn apply(self, left: f64, right: f64) -> f64 {
+        match self {
+            BinaryOperator::Add => left + right,
+            BinaryOperator::Subtract => left - right,
+            BinaryOperator::Multiply => left * right,
+            BinaryOperator::Divide => left / right,
+            BinaryOperator::IntDivide => (left / right).trunc(),
+            BinaryOperator::CeilDivide => (left / right).ceil(),
+            BinaryOperator::Modulo => left % right,
+            BinaryOperator::Power => left.powf(right),
+            BinaryOperator::Equal => (left == right) as i32 as f64,
+            BinaryOperator::NotEqual => (left != right) as i32 as f64,
+            BinaryOperator::Greater => (left > right) as i32 as f64,
+            BinaryOperator::GreaterEqual => (left >= right) as i32 as f64,
+            BinaryOperator::Less => (left < right) as i32 as f64,
+            BinaryOperator::LessEqual => (left <= right) as i32 as f64,
+        }
+    }
+
+    /// Binding strength; higher binds tighter.
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual
+            | BinaryOperator::Less
+            | BinaryOperator::LessEqual => 1,
+            BinaryOperator::Add | BinaryOperator::Subtract => 2,
+            BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::IntDivide
+            | BinaryOperator::CeilDivide
+            | BinaryOperator::Modulo => 3,
+            BinaryOperator::Power => 4,
+        }
+    }
+
+    /// Whether a chain of the operator groups from the right, as `^` does.
+    pub fn is_right_associative(self) -> bool {
+        self == BinaryOperator::Power
+    }
+}
+
 /// A selector targets a subset of a dice pool (e.g. highest, lowest).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Selector {
     pub kind: SelectorKind,
     pub target: Box<Node>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SelectorKind {
     Literal,
     Highest,
     Lowest,
+    /// The middle few by value, as in `5d6km1`. The rest split evenly
+    /// between the ends; when they can't, the extra one comes off the high
+    /// end, so `4d6km1` picks the second lowest. Equal values keep their roll
+    /// order.
+    Middle,
     GreaterThan,
     GreaterThanOrEqual,
     LessThan,
@@ -96,33 +246,217 @@ pub enum SelectorKind {
     NotEqual,
 }
 
+/// Aggregates a selector target can refer to, e.g. `k>mean` or `rr<avg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Aggregate {
+    /// Also written `avg`.
+    Mean,
+    Median,
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mean" | "avg" => Some(Aggregate::Mean),
+            "median" => Some(Aggregate::Median),
+            "sum" => Some(Aggregate::Sum),
+            "min" => Some(Aggregate::Min),
+            "max" => Some(Aggregate::Max),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aggregate::Mean => "mean",
+            Aggregate::Median => "median",
+            Aggregate::Sum => "sum",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+        }
+    }
+}
+
+impl SelectorKind {
+    pub const ALL: [SelectorKind; 10] = [
+        SelectorKind::Literal,
+        SelectorKind::Highest,
+        SelectorKind::Lowest,
+        SelectorKind::Middle,
+        SelectorKind::GreaterThan,
+        SelectorKind::GreaterThanOrEqual,
+        SelectorKind::LessThan,
+        SelectorKind::LessThanOrEqual,
+        SelectorKind::EqualTo,
+        SelectorKind::NotEqual,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            SelectorKind::Literal => "",
+            SelectorKind::Highest => "h",
+            SelectorKind::Lowest => "l",
+            SelectorKind::Middle => "m",
+            SelectorKind::GreaterThan => ">",
+            SelectorKind::GreaterThanOrEqual => ">=",
+            SelectorKind::LessThan => "<",
+            SelectorKind::LessThanOrEqual => "<=",
+            SelectorKind::EqualTo => "==",
+            SelectorKind::NotEqual => "!=",
+        }
+    }
+}
+
 /// The different set operations that can be applied to a dice pool.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SetOperator {
+    /// Keeps the selection. Several selectors in one keep, as in `4d6kh1l1`,
+    /// keep every die any of them picks. Chained keeps work the same way:
+    /// each selects from what was kept before the first of them, and dice
+    /// pools and sets alike keep the union of their selections. Parentheses
+    /// end the chain, so `(4d6kh2)kl1` keeps the lower of the two highest.
     Keep,
     Drop,
     Reroll,
     RerollOnce,
     RerollAdd,
+    /// Rerolls each selected die once, or every die when bare as in `1d20rb`,
+    /// and keeps the higher of the two rolls.
+    RerollBetter,
+    /// Like `rb`, but keeps the lower of the two rolls.
+    RerollWorse,
     Explode,
+    StepExplode,
+    /// Rolls triggered dice into a separate bonus pool instead of the total.
+    BonusExplode,
     ExplodeCompound,
+    /// Compounds like `!!`, but each explosion adds one less than it rolls,
+    /// as in `1d6!!p`.
     ExplodePenetrate,
     Penetrate,
     Minimum,
     Maximum,
+    /// Totals the pool as its highest kept die less its lowest.
+    Spread,
+    /// Totals the pool as the length of its longest run of consecutive kept
+    /// faces, as in `6d6straight`.
+    Straight,
+    /// Keeps each kept die with probability `value / sides`, as in `6d6wkeep`.
+    WeightedKeep,
     CountSuccess,
     CountFailure,
+    /// Lists the pool's dice lowest first, as in `10d6sa`; the total and which
+    /// dice are kept are unchanged.
+    SortAscending,
+    /// Lists the pool's dice highest first, as in `10d6sd`.
+    SortDescending,
+}
+
+impl SetOperator {
+    pub const ALL: [SetOperator; 22] = [
+        SetOperator::Keep,
+        SetOperator::Drop,
+        SetOperator::Reroll,
+        SetOperator::RerollOnce,
+        SetOperator::RerollAdd,
+        SetOperator::RerollBetter,
+        SetOperator::RerollWorse,
+        SetOperator::Explode,
+        SetOperator::StepExplode,
+        SetOperator::BonusExplode,
+        SetOperator::ExplodeCompound,
+        SetOperator::ExplodePenetrate,
+        SetOperator::Penetrate,
+        SetOperator::Minimum,
+        SetOperator::Maximum,
+        SetOperator::Spread,
+        SetOperator::Straight,
+        SetOperator::WeightedKeep,
+        SetOperator::CountSuccess,
+        SetOperator::CountFailure,
+        SetOperator::SortAscending,
+        SetOperator::SortDescending,
+    ];
+
+    /// Every spelling of the operator, canonical `symbol` first.
+    pub fn lexemes(self) -> &'static [&'static str] {
+        match self {
+            SetOperator::Keep => &["k"],
+            SetOperator::Drop => &["p"],
+            SetOperator::Reroll => &["rr"],
+            SetOperator::RerollOnce => &["ro"],
+            SetOperator::RerollAdd => &["ra"],
+            SetOperator::RerollBetter => &["rb"],
+            SetOperator::RerollWorse => &["rw"],
+            SetOperator::Explode => &["e", "!"],
+            SetOperator::StepExplode => &["stepe"],
+            SetOperator::BonusExplode => &["eb"],
+            SetOperator::ExplodeCompound => &["!!"],
+            SetOperator::ExplodePenetrate => &["!!p"],
+            SetOperator::Penetrate => &["!p"],
+            SetOperator::Minimum => &["mi"],
+            SetOperator::Maximum => &["ma"],
+            SetOperator::Spread => &["spread"],
+            SetOperator::Straight => &["straight"],
+            SetOperator::WeightedKeep => &["wkeep"],
+            SetOperator::CountSuccess => &["cs"],
+            SetOperator::CountFailure => &["cf"],
+            SetOperator::SortAscending => &["sa"],
+            SetOperator::SortDescending => &["sd"],
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        self.lexemes()[0]
+    }
 }
 
 /// A modifier applied to a dice set, potentially using a selector.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetOperation {
     pub operator: SetOperator,
     pub selectors: Vec<Selector>,
+    /// Set on the first operation after a parenthesised pool that already has
+    /// operations, as the `kl2` in `(10d6kh5)kl2`. A keep here starts a fresh
+    /// run, choosing among the dice the parentheses kept.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub after_parens: bool,
 }
 
 /// Represents a textual annotation applied to a node.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Annotation {
     pub text: String,
+    /// Display multiplier from a leading `xN`, e.g. `[x3 fire]`; 1 otherwise.
+    pub repeat: u32,
+}
+
+impl Annotation {
+    /// Builds an annotation from its raw text, splitting off a leading `xN`
+    /// repeat marker when present.
+    pub fn from_text(raw: &str) -> Self {
+        let (head, rest) = raw.split_once(char::is_whitespace).unwrap_or((raw, ""));
+        let repeat = head
+            .strip_prefix(['x', 'X'])
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|digits| digits.parse::<u32>().ok())
+            .filter(|&repeat| repeat > 0);
+        match repeat {
+            Some(repeat) => Annotation {
+                text: rest.trim_start().to_string(),
+                repeat,
+            },
+            None => Annotation {
+                text: raw.to_string(),
+                repeat: 1,
+            },
+        }
+    }
 }
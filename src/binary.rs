@@ -0,0 +1,20 @@
+// ---------- Binary ----------
+
+use crate::Result;
+use crate::error::RollatoriumError::Serialization;
+use crate::eval::EvalResult;
+
+impl EvalResult {
+    /// Encodes the full result tree as MessagePack, with struct fields named
+    /// so tagged enums decode. Non-finite totals are stored as-is; the
+    /// encoding is self-describing but its exact bytes are not guaranteed to
+    /// stay stable between releases.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec_named(self).map_err(|err| Serialization(err.to_string()))
+    }
+
+    /// Decodes a result previously encoded with [`EvalResult::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes).map_err(|err| Serialization(err.to_string()))
+    }
+}
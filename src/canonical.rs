@@ -0,0 +1,227 @@
+use std::hash::Hasher;
+
+use crate::ast::{BinaryOperator, DiceSize, Node, SetOperation, UnaryOperator};
+
+impl Node {
+    /// A hash of the expression that is stable across runs and builds, for
+    /// caching results per logical expression. Chains of `+`, `*`, `==` and
+    /// `!=` hash the same whatever the order of their operands, so `2 + 3` and
+    /// `3 + 2` share a hash. Annotations are ignored as they never change a
+    /// result.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.hash_canonical(&mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_canonical(&self, hasher: &mut StableHasher) {
+        match self {
+            Node::Literal(value) => {
+                hasher.write_u8(0);
+                hash_f64(*value, hasher);
+            }
+            Node::Unary { operator, operand } => {
+                hasher.write_u8(1);
+                hasher.write_u8(match operator {
+                    UnaryOperator::Plus => b'+',
+                    UnaryOperator::Minus => b'-',
+                });
+                operand.hash_canonical(hasher);
+            }
+            Node::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                hasher.write_u8(2);
+                hash_str(operator.symbol(), hasher);
+                if is_commutative(*operator) {
+                    let mut operands = Vec::new();
+                    collect_operands(*operator, left, &mut operands);
+                    collect_operands(*operator, right, &mut operands);
+                    let mut hashes: Vec<u64> =
+                        operands.into_iter().map(Node::canonical_hash).collect();
+                    hashes.sort_unstable();
+                    hasher.write_usize(hashes.len());
+                    for hash in hashes {
+                        hasher.write_u64(hash);
+                    }
+                } else {
+                    left.hash_canonical(hasher);
+                    right.hash_canonical(hasher);
+                }
+            }
+            Node::Dice { num, size } => {
+                hasher.write_u8(3);
+                hash_dice(num.as_deref(), size, hasher);
+            }
+            Node::Set {
+                elements,
+                operations,
+            } => {
+                hasher.write_u8(4);
+                hash_nodes(elements, hasher);
+                hash_operations(operations, hasher);
+            }
+            Node::DiceWithOps { dice, operations } => {
+                hasher.write_u8(5);
+                dice.hash_canonical(hasher);
+                hash_operations(operations, hasher);
+            }
+            Node::Call { name, args } => {
+                hasher.write_u8(6);
+                hash_str(name, hasher);
+                hash_nodes(args, hasher);
+            }
+            Node::Range { low, high } => {
+                hasher.write_u8(7);
+                low.hash_canonical(hasher);
+                high.hash_canonical(hasher);
+            }
+            Node::Condition(selector) => {
+                hasher.write_u8(10);
+                hash_str(selector.kind.symbol(), hasher);
+                selector.target.hash_canonical(hasher);
+            }
+            Node::Aggregate(aggregate) => {
+                hasher.write_u8(8);
+                hash_str(aggregate.name(), hasher);
+            }
+            Node::Repeat { count, expr } => {
+                hasher.write_u8(9);
+                hasher.write_u32(*count);
+                expr.hash_canonical(hasher);
+            }
+            Node::Annotated { expr, .. } => expr.hash_canonical(hasher),
+        }
+    }
+}
+
+fn is_commutative(operator: BinaryOperator) -> bool {
+    matches!(
+        operator,
+        BinaryOperator::Add
+            | BinaryOperator::Multiply
+            | BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+    )
+}
+
+/// Flattens a chain of `operator` into its operands. Only `+` and `*` chains
+/// are associative; a comparison's operands are its two sides.
+fn collect_operands<'a>(operator: BinaryOperator, node: &'a Node, operands: &mut Vec<&'a Node>) {
+    let node = match node {
+        Node::Annotated { expr, .. } => expr,
+        node => node,
+    };
+    match node {
+        Node::Binary {
+            operator: inner,
+            left,
+            right,
+        } if *inner == operator
+            && matches!(operator, BinaryOperator::Add | BinaryOperator::Multiply) =>
+        {
+            collect_operands(operator, left, operands);
+            collect_operands(operator, right, operands);
+        }
+        node => operands.push(node),
+    }
+}
+
+fn hash_dice(num: Option<&Node>, size: &DiceSize, hasher: &mut StableHasher) {
+    match num {
+        Some(num) => num.hash_canonical(hasher),
+        // `d6` rolls the same single die as `1d6`.
+        None => Node::Literal(1.0).hash_canonical(hasher),
+    }
+    match size {
+        DiceSize::Value(sides) => {
+            hasher.write_u8(0);
+            sides.hash_canonical(hasher);
+        }
+        DiceSize::Percent => hasher.write_u8(1),
+        DiceSize::Fudge => hasher.write_u8(4),
+        DiceSize::Percentile => hasher.write_u8(5),
+        DiceSize::ExprTable(entries) => {
+            hasher.write_u8(2);
+            hash_nodes(entries, hasher);
+        }
+        DiceSize::Custom(name) => {
+            hasher.write_u8(3);
+            hash_str(name, hasher);
+        }
+    }
+}
+
+fn hash_nodes(nodes: &[Node], hasher: &mut StableHasher) {
+    hasher.write_usize(nodes.len());
+    for node in nodes {
+        node.hash_canonical(hasher);
+    }
+}
+
+fn hash_operations(operations: &[SetOperation], hasher: &mut StableHasher) {
+    hasher.write_usize(operations.len());
+    for operation in operations {
+        hash_str(operation.operator.symbol(), hasher);
+        hasher.write_u8(operation.after_parens.into());
+        hasher.write_usize(operation.selectors.len());
+        for selector in &operation.selectors {
+            hash_str(selector.kind.symbol(), hasher);
+            selector.target.hash_canonical(hasher);
+        }
+    }
+}
+
+fn hash_str(text: &str, hasher: &mut StableHasher) {
+    hasher.write_usize(text.len());
+    hasher.write(text.as_bytes());
+}
+
+/// Hashes the bit pattern, with every NaN and both zeros folded together.
+fn hash_f64(value: f64, hasher: &mut StableHasher) {
+    let value = if value.is_nan() {
+        f64::NAN
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    };
+    hasher.write_u64(value.to_bits());
+}
+
+/// 64-bit FNV-1a, fixed so hashes can be stored and compared across builds,
+/// unlike `DefaultHasher`. Integers are written little-endian.
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
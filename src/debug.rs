@@ -0,0 +1,163 @@
+use crate::ast::{DiceSize, Node, SetOperation};
+
+/// Renders `node` as an indented ASCII tree, one node per line with each
+/// child labelled by its role, to show how an expression was grouped. The
+/// tree for `1 + 2 == 2` makes it plain that `==` binds loosest:
+///
+/// ```text
+/// Binary(Equal)
+/// |-- left: Binary(Add)
+/// |   |-- left: Literal(1)
+/// |   `-- right: Literal(2)
+/// `-- right: Literal(2)
+/// ```
+pub fn render_tree(node: &Node) -> String {
+    let mut out = String::new();
+    write_tree(&mut out, &tree_of(node), "");
+    out
+}
+
+struct Tree {
+    label: String,
+    children: Vec<Tree>,
+}
+
+impl Tree {
+    fn leaf(label: String) -> Self {
+        Tree {
+            label,
+            children: Vec::new(),
+        }
+    }
+
+    /// `tree` with its label prefixed by the role it plays in its parent.
+    fn child(role: &str, mut tree: Tree) -> Self {
+        tree.label = format!("{}: {}", role, tree.label);
+        tree
+    }
+}
+
+fn tree_of(node: &Node) -> Tree {
+    let (label, children) = match node {
+        Node::Literal(value) => (format!("Literal({})", value), Vec::new()),
+        Node::Unary { operator, operand } => (
+            format!("Unary({:?})", operator),
+            vec![Tree::child("operand", tree_of(operand))],
+        ),
+        Node::Binary {
+            operator,
+            left,
+            right,
+        } => (
+            format!("Binary({:?})", operator),
+            vec![
+                Tree::child("left", tree_of(left)),
+                Tree::child("right", tree_of(right)),
+            ],
+        ),
+        Node::Dice { num, size } => {
+            let mut children: Vec<_> = num
+                .iter()
+                .map(|num| Tree::child("count", tree_of(num)))
+                .collect();
+            children.push(Tree::child("sides", size_tree(size)));
+            ("Dice".to_string(), children)
+        }
+        Node::DiceWithOps { dice, operations } => {
+            let mut children = vec![Tree::child("dice", tree_of(dice))];
+            children.extend(operations.iter().map(operation_tree));
+            ("DiceWithOps".to_string(), children)
+        }
+        Node::Set {
+            elements,
+            operations,
+        } => {
+            let mut children: Vec<_> = elements
+                .iter()
+                .map(|element| Tree::child("element", tree_of(element)))
+                .collect();
+            children.extend(operations.iter().map(operation_tree));
+            ("Set".to_string(), children)
+        }
+        Node::Call { name, args } => (
+            format!("Call({})", name),
+            args.iter()
+                .map(|arg| Tree::child("arg", tree_of(arg)))
+                .collect(),
+        ),
+        Node::Range { low, high } => (
+            "Range".to_string(),
+            vec![
+                Tree::child("low", tree_of(low)),
+                Tree::child("high", tree_of(high)),
+            ],
+        ),
+        Node::Condition(selector) => (
+            format!("Condition({:?})", selector.kind),
+            vec![Tree::child("target", tree_of(&selector.target))],
+        ),
+        Node::Aggregate(aggregate) => (format!("Aggregate({})", aggregate.name()), Vec::new()),
+        Node::Repeat { count, expr } => (
+            format!("Repeat({})", count),
+            vec![Tree::child("expr", tree_of(expr))],
+        ),
+        Node::Annotated { expr, annotations } => {
+            let annotations: Vec<_> = annotations
+                .iter()
+                .map(|annotation| format!("[{}]", annotation))
+                .collect();
+            (
+                format!("Annotated({})", annotations.join(" ")),
+                vec![Tree::child("expr", tree_of(expr))],
+            )
+        }
+    };
+    Tree { label, children }
+}
+
+fn size_tree(size: &DiceSize) -> Tree {
+    match size {
+        DiceSize::Value(sides) => tree_of(sides),
+        DiceSize::Percent => Tree::leaf("Percent".to_string()),
+        DiceSize::Percentile => Tree::leaf("Percentile".to_string()),
+        DiceSize::Fudge => Tree::leaf("Fudge".to_string()),
+        DiceSize::ExprTable(entries) => Tree {
+            label: "Table".to_string(),
+            children: entries
+                .iter()
+                .map(|entry| Tree::child("entry", tree_of(entry)))
+                .collect(),
+        },
+        DiceSize::Custom(name) => Tree::leaf(format!("Custom({})", name)),
+    }
+}
+
+fn operation_tree(operation: &SetOperation) -> Tree {
+    Tree {
+        label: format!("operation: {:?}", operation.operator),
+        children: operation
+            .selectors
+            .iter()
+            .map(|selector| {
+                Tree::child(
+                    &format!("selector({:?})", selector.kind),
+                    tree_of(&selector.target),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Writes `tree` and its children, each child line starting with `prefix`
+/// and the branch that leads to it.
+fn write_tree(out: &mut String, tree: &Tree, prefix: &str) {
+    out.push_str(&tree.label);
+    out.push('\n');
+    for (idx, child) in tree.children.iter().enumerate() {
+        let last = idx + 1 == tree.children.len();
+        out.push_str(prefix);
+        out.push_str(if last { "`-- " } else { "|-- " });
+        let prefix = format!("{}{}", prefix, if last { "    " } else { "|   " });
+        write_tree(out, child, &prefix);
+    }
+}
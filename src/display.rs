@@ -0,0 +1,485 @@
+// ---------- Display ----------
+
+use std::fmt::{self, Write};
+
+use crate::ast::{Annotation, BinaryOperator, DiceSize, Node, SetOperation, UnaryOperator};
+use crate::eval::{DiceRoll, DieOrigin, DieResult, EvalResult, SetRoll, Value};
+
+/// Range bounds are parsed as comparisons, so only nested ranges need parentheses.
+const RANGE_BOUND_PRECEDENCE: u8 = 1;
+/// Repetition binds loosest, so it is parenthesized wherever it is nested.
+const REPEAT_PRECEDENCE: u8 = 0;
+const UNARY_PRECEDENCE: u8 = 5;
+const ATOM_PRECEDENCE: u8 = 6;
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD: &str = "\x1b[1m";
+const ANSI_DIM: &str = "\x1b[2m";
+const ANSI_UNDERLINE: &str = "\x1b[4m";
+const ANSI_INVERSE: &str = "\x1b[7m";
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_GREEN: &str = "\x1b[32m";
+
+/// Renders a node back into expression syntax, adding parentheses only where
+/// precedence requires them.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Literal(value) => write!(f, "{}", value),
+            Node::Unary { operator, operand } => {
+                f.write_str(unary_symbol(*operator))?;
+                write_node(f, operand, UNARY_PRECEDENCE)
+            }
+            Node::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let (left_min, right_min) = operand_precedences(*operator);
+                write_node(f, left, left_min)?;
+                write!(f, " {} ", operator.symbol())?;
+                write_node(f, right, right_min)
+            }
+            Node::Dice { num, size } => {
+                match num.as_deref() {
+                    Some(num @ Node::Literal(_)) => write!(f, "{}", num)?,
+                    Some(num) => write!(f, "({})", num)?,
+                    None => {}
+                }
+                f.write_char('d')?;
+                write_sides(f, size)
+            }
+            Node::DiceWithOps { dice, operations } => {
+                open_parens(f, operations)?;
+                write!(f, "{}", dice)?;
+                match dice.as_ref() {
+                    Node::Dice { size, .. } => write_dice_operations(f, size, operations),
+                    _ => write_operations(f, operations),
+                }
+            }
+            Node::Set {
+                elements,
+                operations,
+            } => {
+                f.write_char('(')?;
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                if elements.len() == 1 {
+                    f.write_char(',')?;
+                }
+                f.write_char(')')?;
+                write_operations(f, operations)
+            }
+            Node::Call { name, args } => {
+                write!(f, "{}(", name)?;
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_char(')')
+            }
+            Node::Range { low, high } => {
+                write_node(f, low, RANGE_BOUND_PRECEDENCE)?;
+                f.write_str("..")?;
+                write_node(f, high, RANGE_BOUND_PRECEDENCE)
+            }
+            Node::Condition(selector) => {
+                f.write_str(selector.kind.symbol())?;
+                write_node(f, &selector.target, ATOM_PRECEDENCE)
+            }
+            Node::Aggregate(aggregate) => f.write_str(aggregate.name()),
+            Node::Repeat { count, expr } => {
+                write!(f, "{}x", count)?;
+                write_node(f, expr, REPEAT_PRECEDENCE + 1)
+            }
+            Node::Annotated { expr, annotations } => {
+                write_node(f, expr, ATOM_PRECEDENCE)?;
+                for annotation in annotations {
+                    write!(f, " [{}]", annotation)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.repeat, self.text.is_empty()) {
+            (1, _) => f.write_str(&self.text),
+            (repeat, true) => write!(f, "x{}", repeat),
+            (repeat, false) => write!(f, "x{} {}", repeat, self.text),
+        }
+    }
+}
+
+/// The precedence each operand of `operator` needs to go unparenthesized: the
+/// side a chain groups from may hold the same operator, the other side must
+/// bind tighter.
+fn operand_precedences(operator: BinaryOperator) -> (u8, u8) {
+    let precedence = operator.precedence();
+    if operator.is_right_associative() {
+        (precedence + 1, precedence)
+    } else {
+        (precedence, precedence + 1)
+    }
+}
+
+fn node_precedence(node: &Node) -> u8 {
+    match node {
+        Node::Binary { operator, .. } => operator.precedence(),
+        Node::Unary { .. } => UNARY_PRECEDENCE,
+        Node::Repeat { .. } => REPEAT_PRECEDENCE,
+        _ => ATOM_PRECEDENCE,
+    }
+}
+
+fn write_node(f: &mut fmt::Formatter<'_>, node: &Node, min_precedence: u8) -> fmt::Result {
+    if node_precedence(node) < min_precedence {
+        write!(f, "({})", node)
+    } else {
+        write!(f, "{}", node)
+    }
+}
+
+/// Writes a pool's operations; a custom die name (or the `F` of `dF`) runs to
+/// the next non-alphanumeric character, so a space sets its operations apart.
+/// Operations after parentheses close the ones `open_parens` wrote.
+fn write_dice_operations(
+    f: &mut fmt::Formatter<'_>,
+    size: &DiceSize,
+    operations: &[SetOperation],
+) -> fmt::Result {
+    if matches!(size, DiceSize::Custom(_) | DiceSize::Fudge) && !operations.is_empty() {
+        f.write_char(' ')?;
+    }
+    for operation in operations {
+        if operation.after_parens {
+            f.write_char(')')?;
+        }
+        write_operations(f, std::slice::from_ref(operation))?;
+    }
+    Ok(())
+}
+
+/// Opens a parenthesis in front of a pool for each of its operations that
+/// follows one, as in `((10d6kh5)kl2)kh1`.
+fn open_parens(f: &mut fmt::Formatter<'_>, operations: &[SetOperation]) -> fmt::Result {
+    for _ in operations.iter().filter(|operation| operation.after_parens) {
+        f.write_char('(')?;
+    }
+    Ok(())
+}
+
+fn write_sides(f: &mut fmt::Formatter<'_>, size: &DiceSize) -> fmt::Result {
+    match size {
+        DiceSize::Percent => f.write_char('%'),
+        DiceSize::Percentile => f.write_str("%%"),
+        DiceSize::Fudge => f.write_char('F'),
+        DiceSize::Value(node) if matches!(**node, Node::Literal(_)) => write!(f, "{}", node),
+        DiceSize::Value(node) => write!(f, "({})", node),
+        DiceSize::ExprTable(entries) => {
+            f.write_char('[')?;
+            for (idx, entry) in entries.iter().enumerate() {
+                if idx > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", entry)?;
+            }
+            f.write_char(']')
+        }
+        DiceSize::Custom(name) => f.write_str(name),
+    }
+}
+
+fn write_operations(f: &mut fmt::Formatter<'_>, operations: &[SetOperation]) -> fmt::Result {
+    for operation in operations {
+        f.write_str(operation.operator.symbol())?;
+        for selector in &operation.selectors {
+            f.write_str(selector.kind.symbol())?;
+            write_node(f, &selector.target, ATOM_PRECEDENCE)?;
+        }
+    }
+    Ok(())
+}
+
+fn unary_symbol(operator: UnaryOperator) -> &'static str {
+    match operator {
+        UnaryOperator::Plus => "+",
+        UnaryOperator::Minus => "-",
+    }
+}
+
+impl EvalResult {
+    /// Renders the breakdown followed by the total, styled with ANSI escape
+    /// codes: dropped dice are dimmed, natural maximums green, natural ones red
+    /// and dice added by explosions bold. Dice counted by `cs` are underlined
+    /// and dice counted by `cf` shown in inverse.
+    pub fn to_ansi(&self) -> String {
+        format!(
+            "{} = {}",
+            Breakdown {
+                value: &self.value,
+                ansi: true,
+            },
+            self.total
+        )
+    }
+}
+
+/// Renders the breakdown followed by the total, e.g.
+/// `4d6kh3 (5, 3, ~~2~~, 6) = 14`, with dropped dice struck through.
+impl fmt::Display for EvalResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.value, self.total)
+    }
+}
+
+/// Renders the breakdown of a value without its total, e.g.
+/// `4d6kh3 (5, 3, ~~2~~, 6) + 2`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Breakdown {
+            value: self,
+            ansi: false,
+        }
+        .fmt(f)
+    }
+}
+
+/// Renders the evaluated tree of a result, e.g. `4d6kh3 (5, 3, 2, 6) + 2`.
+struct Breakdown<'a> {
+    value: &'a Value,
+    ansi: bool,
+}
+
+impl fmt::Display for Breakdown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_value(f, self.value, 0)
+    }
+}
+
+impl Breakdown<'_> {
+    fn write_result(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        result: &EvalResult,
+        min_precedence: u8,
+    ) -> fmt::Result {
+        self.write_value(f, &result.value, min_precedence)
+    }
+
+    fn write_value(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        value: &Value,
+        min_precedence: u8,
+    ) -> fmt::Result {
+        let parenthesize = value_precedence(value) < min_precedence;
+        if parenthesize {
+            f.write_char('(')?;
+        }
+        match value {
+            Value::Literal(value) => write!(f, "{}", value)?,
+            Value::Unary { operator, operand } => {
+                f.write_str(unary_symbol(*operator))?;
+                self.write_result(f, operand, UNARY_PRECEDENCE)?;
+            }
+            Value::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let (left_min, right_min) = operand_precedences(*operator);
+                self.write_result(f, left, left_min)?;
+                write!(f, " {} ", operator.symbol())?;
+                self.write_result(f, right, right_min)?;
+            }
+            Value::Dice(roll) => self.write_dice(f, roll)?,
+            Value::Set(set) => self.write_set(f, set)?,
+            Value::Range { low, high } => {
+                self.write_result(f, low, RANGE_BOUND_PRECEDENCE)?;
+                f.write_str("..")?;
+                self.write_result(f, high, RANGE_BOUND_PRECEDENCE)?;
+            }
+            Value::Call { name, args } => {
+                write!(f, "{}(", name)?;
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    self.write_result(f, arg, 0)?;
+                }
+                f.write_char(')')?;
+            }
+            Value::Repeat { results } => {
+                write!(f, "{}x(", results.len())?;
+                for (idx, repetition) in results.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    self.write_result(f, repetition, 0)?;
+                }
+                f.write_char(')')?;
+            }
+            Value::Annotated { expr, annotations } => {
+                self.write_result(f, expr, ATOM_PRECEDENCE)?;
+                for annotation in annotations {
+                    write!(f, " [{}]", annotation)?;
+                }
+            }
+        }
+        if parenthesize {
+            f.write_char(')')?;
+        }
+        Ok(())
+    }
+
+    fn write_dice(&self, f: &mut fmt::Formatter<'_>, roll: &DiceRoll) -> fmt::Result {
+        open_parens(f, &roll.operations)?;
+        write!(f, "{}d", roll.quantity)?;
+        write_sides(f, &roll.sides)?;
+        write_dice_operations(f, &roll.sides, &roll.operations)?;
+        f.write_str(" (")?;
+        for (idx, die) in roll.dice.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            self.write_die(f, roll, die)?;
+        }
+        f.write_char(')')?;
+        if !roll.bonus.is_empty() {
+            f.write_str(" bonus (")?;
+            for (idx, die) in roll.bonus.iter().enumerate() {
+                if idx > 0 {
+                    f.write_str(", ")?;
+                }
+                self.write_die(f, roll, die)?;
+            }
+            f.write_char(')')?;
+        }
+        Ok(())
+    }
+
+    fn write_die(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        roll: &DiceRoll,
+        die: &DieResult,
+    ) -> fmt::Result {
+        if let Some(entry) = &die.entry {
+            return self.write_element(f, entry, die.dropped);
+        }
+        if !self.ansi {
+            return if die.dropped {
+                write!(f, "~~{}~~", die.value)
+            } else {
+                write!(f, "{}", die.value)
+            };
+        }
+
+        let mut styles = Vec::new();
+        if die.dropped {
+            styles.push(ANSI_DIM);
+        }
+        if matches!(
+            die.origin,
+            DieOrigin::Explosion | DieOrigin::StepExplosion { .. }
+        ) {
+            styles.push(ANSI_BOLD);
+        }
+        if die.kept && die.success {
+            styles.push(ANSI_UNDERLINE);
+        }
+        if die.kept && die.failure {
+            styles.push(ANSI_INVERSE);
+        }
+        match natural_roll(roll, die) {
+            Some(Natural::Max) => styles.push(ANSI_GREEN),
+            Some(Natural::One) => styles.push(ANSI_RED),
+            None => {}
+        }
+        if styles.is_empty() {
+            write!(f, "{}", die.value)
+        } else {
+            write!(f, "{}{}{}", styles.concat(), die.value, ANSI_RESET)
+        }
+    }
+
+    /// Writes a nested result (a set element or a table die's entry), marking
+    /// it as dropped when it no longer counts.
+    fn write_element(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        result: &EvalResult,
+        dropped: bool,
+    ) -> fmt::Result {
+        match (dropped, self.ansi) {
+            (false, _) => self.write_result(f, result, 0),
+            (true, false) => {
+                f.write_str("~~")?;
+                self.write_result(f, result, 0)?;
+                f.write_str("~~")
+            }
+            (true, true) => {
+                // Nested styles would reset the dimming early, so the
+                // dropped element is rendered plainly inside it.
+                write!(f, "{}{}{}", ANSI_DIM, result.value, ANSI_RESET)
+            }
+        }
+    }
+
+    fn write_set(&self, f: &mut fmt::Formatter<'_>, set: &SetRoll) -> fmt::Result {
+        f.write_char('(')?;
+        for (idx, element) in set.elements.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            self.write_element(f, &element.value, element.dropped)?;
+        }
+        if set.elements.len() == 1 {
+            f.write_char(',')?;
+        }
+        f.write_char(')')?;
+        write_operations(f, &set.operations)
+    }
+}
+
+fn value_precedence(value: &Value) -> u8 {
+    match value {
+        Value::Binary { operator, .. } => operator.precedence(),
+        Value::Unary { .. } => UNARY_PRECEDENCE,
+        Value::Repeat { .. } => REPEAT_PRECEDENCE,
+        _ => ATOM_PRECEDENCE,
+    }
+}
+
+enum Natural {
+    Max,
+    One,
+}
+
+/// Classifies a numbered die's first roll as a natural maximum or a natural one.
+fn natural_roll(roll: &DiceRoll, die: &DieResult) -> Option<Natural> {
+    if !matches!(roll.sides, DiceSize::Value(_)) {
+        return None;
+    }
+    let size = match die.origin {
+        DieOrigin::StepExplosion { size } => size,
+        _ => roll.size,
+    };
+    let first = *die.rolls.first()?;
+    if first == size as f64 {
+        Some(Natural::Max)
+    } else if first == 1.0 {
+        Some(Natural::One)
+    } else {
+        None
+    }
+}
@@ -1,11 +1,69 @@
+use std::ops::Range;
+
 use thiserror::Error;
 
+use crate::ast::BinaryOperator;
+
 #[derive(Debug, Error)]
 pub enum RollatoriumError {
-    #[error("Lexer error: {0}")]
-    Lexer(String),
-    #[error("Parser error: {0}")]
-    Parser(String),
+    /// Input that isn't made of known tokens. `span` is the character range of
+    /// the offending text.
+    #[error("Lexer error: {message}")]
+    Lexer { message: String, span: Range<usize> },
+    /// Tokens that don't form an expression. `span` is the character range of
+    /// the token the parser stopped at; at the end of the input it is empty.
+    #[error("Parser error: {message}")]
+    Parser { message: String, span: Range<usize> },
     #[error("Evaluation error: {0}")]
     Eval(String),
+    /// `//`, `/^` or `%` with a zero divisor, or `0 / 0`, in the expression
+    /// `expr`.
+    #[error("Evaluation error: {} by zero in '{expr}'", division_name(*operator))]
+    DivideByZero {
+        operator: BinaryOperator,
+        expr: String,
+    },
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+}
+
+impl RollatoriumError {
+    /// The character range of the input a lexer or parser error points at.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            RollatoriumError::Lexer { span, .. } | RollatoriumError::Parser { span, .. } => {
+                Some(span.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Renders `input` with a line of carets under the span of a lexer or
+    /// parser error, as in
+    ///
+    /// ```text
+    /// 1d6 + * 2
+    ///       ^
+    /// ```
+    ///
+    /// An error at the end of the input gets a single caret just past it.
+    pub fn caret_diagnostic(&self, input: &str) -> Option<String> {
+        let span = self.span()?;
+        let width = span.end.saturating_sub(span.start).max(1);
+        Some(format!(
+            "{}\n{}{}",
+            input,
+            " ".repeat(span.start),
+            "^".repeat(width)
+        ))
+    }
+}
+
+fn division_name(operator: BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Divide => "Division",
+        BinaryOperator::Modulo => "Modulo",
+        BinaryOperator::CeilDivide => "Ceiling division",
+        _ => "Integer division",
+    }
 }
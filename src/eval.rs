@@ -1,36 +1,294 @@
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hasher;
+use std::time::{Duration, Instant};
 
-use rand::RngCore;
 use rand::distr::{Distribution, Uniform};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
 
 use crate::Result;
 use crate::ast::{
-    Annotation, BinaryOperator, DiceSize, Node, Selector, SelectorKind, SetOperation, SetOperator,
-    UnaryOperator,
+    Aggregate, Annotation, BinaryOperator, DiceSize, Node, Selector, SelectorKind, SetOperation,
+    SetOperator, UnaryOperator,
 };
-use crate::error::RollatoriumError::Eval;
+use crate::canonical::StableHasher;
+use crate::error::RollatoriumError::{self, Eval};
+use crate::registry::DiceRegistry;
 
-const EPSILON: f64 = 1e-9;
+pub(crate) const EPSILON: f64 = 1e-9;
+/// The functions `eval_call` knows, by name.
+pub(crate) const FUNCTIONS: &[&str] = &[
+    "until",
+    "clamp",
+    "margin",
+    "if",
+    "gate",
+    "chance",
+    "needs",
+    "reroll_pool",
+    "min",
+    "max",
+    "abs",
+    "floor",
+    "ceil",
+    "round",
+];
+
+/// Largest integer magnitude an `f64` represents exactly (2^53).
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
 
 #[derive(Debug, Clone)]
 pub struct EvalConfig {
     pub max_rolls: usize,
+    /// Die sizes a step explosion (`stepe`) climbs through, smallest first.
+    pub explode_ladder: Vec<u32>,
+    /// Largest dice quantity a single pool may request.
+    pub max_dice: usize,
+    /// Largest number of sides a numbered die may have, as in `1d1000000`.
+    pub max_die_size: u32,
+    /// Clamp quantities above `max_dice` (emitting a diagnostic) instead of
+    /// failing the evaluation.
+    pub clamp_quantity_to_max: bool,
+    /// Emit a diagnostic when integer arithmetic leaves the range `f64`
+    /// represents exactly.
+    pub warn_precision_loss: bool,
+    /// Custom dice that `d<Name>` rolls.
+    pub dice: DiceRegistry,
+    /// How close a value must be to a target to match a literal, `==` or `!=`
+    /// selector. Integer faces match exactly at any small tolerance; widen it
+    /// for fractional custom faces whose sums drift, as `0.1 + 0.2` does from
+    /// `0.3`. Ordering selectors such as `>` compare exactly.
+    pub selector_epsilon: f64,
+    /// Leave operations that changed nothing, such as `kh5` on four dice, out
+    /// of a pool's recorded `operations` so breakdowns only show what
+    /// mattered. Totals are unaffected.
+    pub prune_noop_operations: bool,
+    /// Roll only the branch `if` takes, and a `gate` value only when its
+    /// check passes. Off by default, so both branches roll and an expression
+    /// always consumes the same number of dice, keeping seeded replays aligned
+    /// whichever way the condition falls; turning it on rolls fewer dice at
+    /// the cost of that predictability.
+    pub lazy_branches: bool,
+    /// Stop rolling at `max_rolls` and flag the pools cut short as
+    /// [`truncated`](DiceRoll::truncated) instead of failing the evaluation.
+    pub truncate_on_limit: bool,
+    /// Record which selectors picked each die a pool's keeps and drops
+    /// selected, in [`DiceRoll::selector_matches`], for debugging operations
+    /// such as `k>5h1` that join several selectors.
+    pub record_selector_matches: bool,
+    /// How the final total is rounded, for rules such as "round all
+    /// fractions down". Only the overall total changes; the breakdown keeps
+    /// every intermediate value as rolled.
+    pub total_rounding: RoundingRule,
+    /// How many times `rr` may reroll any one die before it keeps its last
+    /// value, so `1d20rr<20` in a big pool can't spend the whole `max_rolls`
+    /// budget on a single die. `None` rerolls until the selector stops
+    /// matching.
+    pub max_rerolls_per_die: Option<usize>,
+    /// How many dice any one chain of `!` explosions may add before it stops
+    /// and keeps what it rolled, so one lucky `1d2e1` can't spend the whole
+    /// `max_rolls` budget. `None` explodes until a die stops matching.
+    pub max_explosion_depth: Option<usize>,
+}
+
+/// How [`EvalConfig::total_rounding`] rounds a fractional total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingRule {
+    /// Leave the total as computed.
+    #[default]
+    None,
+    /// Toward negative infinity, so 3.5 becomes 3.
+    Down,
+    /// Toward positive infinity, so 3.5 becomes 4.
+    Up,
+    /// To the nearest integer, halves away from zero.
+    Nearest,
+    /// To the nearest integer, halves to the even neighbour, so 2.5 becomes 2.
+    HalfEven,
+}
+
+impl RoundingRule {
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            RoundingRule::None => value,
+            RoundingRule::Down => value.floor(),
+            RoundingRule::Up => value.ceil(),
+            RoundingRule::Nearest => value.round(),
+            RoundingRule::HalfEven => value.round_ties_even(),
+        }
+    }
 }
 
 impl Default for EvalConfig {
     fn default() -> Self {
-        Self { max_rolls: 1000 }
+        Self {
+            max_rolls: 1000,
+            explode_ladder: vec![4, 6, 8, 10, 12, 20],
+            max_dice: 10_000,
+            max_die_size: 1_000_000,
+            clamp_quantity_to_max: false,
+            warn_precision_loss: false,
+            dice: DiceRegistry::default(),
+            selector_epsilon: EPSILON,
+            lazy_branches: false,
+            prune_noop_operations: false,
+            truncate_on_limit: false,
+            record_selector_matches: false,
+            total_rounding: RoundingRule::None,
+            max_rerolls_per_die: None,
+            max_explosion_depth: None,
+        }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvalResult {
     pub total: f64,
     pub value: Value,
 }
 
+impl EvalResult {
+    /// Whether any pool stopped short at `max_rolls` under `truncate_on_limit`.
+    pub fn truncated(&self) -> bool {
+        let mut pools = Vec::new();
+        collect_pools(self, &mut pools);
+        pools.iter().any(|roll| roll.truncated)
+    }
+
+    /// Whether a roll of a single numbered die, such as `1d20 + 5`, landed
+    /// on its natural maximum or a natural one, judged by the first roll of
+    /// the die originally rolled so rerolls and the dice explosions add don't
+    /// change it. Any other roll is [`CritType::None`].
+    pub fn crit(&self) -> CritType {
+        let mut pools = Vec::new();
+        collect_pools(self, &mut pools);
+        let [roll] = pools.as_slice() else {
+            return CritType::None;
+        };
+        let mut rolled = roll
+            .dice
+            .iter()
+            .filter(|die| die.origin == DieOrigin::Original);
+        match (rolled.next(), rolled.next()) {
+            (Some(die), None) if matches!(roll.sides, DiceSize::Value(_)) => {
+                let first = die.rolls[0];
+                if first == roll.size as f64 {
+                    CritType::Crit
+                } else if first == 1.0 {
+                    CritType::Fail
+                } else {
+                    CritType::None
+                }
+            }
+            _ => CritType::None,
+        }
+    }
+
+    /// Sums the totals of annotated subexpressions per annotation, in order of
+    /// first appearance, so `3d6[fire] + 2d6[cold] + 1d6[fire]` yields the
+    /// fire and cold damage apart. Dropped set elements don't count, and
+    /// neither do function arguments, as which of them count is up to the
+    /// function; repeat markers such as `[x2 fire]` are for display and are
+    /// ignored.
+    pub fn tagged_totals(&self) -> Vec<(String, f64)> {
+        let mut tagged = Vec::new();
+        collect_tagged(self, &mut tagged);
+        tagged
+    }
+}
+
+/// An evaluation result together with the configuration that produced it, so a
+/// logged roll carries everything needed to reproduce it.
 #[derive(Debug, Clone)]
+pub struct RollOutcome {
+    pub result: EvalResult,
+    pub config: EvalConfig,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The fields most callers need from a roll, flattened out of the `Value`
+/// tree: every pool die with whether it counted, the successes tallied by
+/// `cs`, and the crit status of a lone die.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetailedRoll {
+    pub total: f64,
+    /// Each die's value and whether it was kept, in roll order across pools.
+    pub dice: Vec<(f64, bool)>,
+    /// Kept dice counted by `cs`; `None` when no pool counts successes.
+    pub successes: Option<usize>,
+    pub crit: CritType,
+}
+
+impl DetailedRoll {
+    /// Walks `result`, collecting the dice of every pool it contains; the
+    /// successes and crit come from [`DiceRoll::success_tally`] and
+    /// [`EvalResult::crit`].
+    pub fn from_result(result: &EvalResult) -> Self {
+        let mut pools = Vec::new();
+        collect_pools(result, &mut pools);
+        let dice = pools
+            .iter()
+            .flat_map(|roll| roll.dice.iter().map(|die| (die.value, die.kept)))
+            .collect();
+        let successes = pools
+            .iter()
+            .filter_map(|roll| roll.success_tally())
+            .map(|tally| tally.successes)
+            .reduce(|total, successes| total + successes);
+        Self {
+            total: result.total,
+            dice,
+            successes,
+            crit: result.crit(),
+        }
+    }
+}
+
+/// Whether a lone die rolled its natural maximum or a natural one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CritType {
+    #[default]
+    None,
+    Crit,
+    Fail,
+}
+
+/// A non-fatal note raised while evaluating, e.g. when a forgiving config
+/// option adjusted the expression instead of rejecting it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// A dice quantity above `max_dice` was clamped to the cap.
+    QuantityClamped { requested: usize, clamped: usize },
+    /// Integer operands produced a total beyond 2^53, where `f64` can no longer
+    /// represent every integer exactly.
+    PrecisionLoss {
+        operator: BinaryOperator,
+        total: f64,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::QuantityClamped { requested, clamped } => {
+                write!(f, "dice quantity {} clamped to {}", requested, clamped)
+            }
+            Diagnostic::PrecisionLoss { operator, total } => write!(
+                f,
+                "'{}' produced {}, beyond the exactly representable integer range",
+                operator.symbol(),
+                total
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Value {
     Literal(f64),
     Unary {
@@ -44,6 +302,22 @@ pub enum Value {
     },
     Dice(DiceRoll),
     Set(SetRoll),
+    /// An evaluated range argument. Ranges carry no total of their own, so the
+    /// enclosing result's total is 0.
+    Range {
+        low: Box<EvalResult>,
+        high: Box<EvalResult>,
+    },
+    /// A function call with its evaluated arguments; see each function for
+    /// what the arguments record.
+    Call {
+        name: String,
+        args: Vec<EvalResult>,
+    },
+    /// Each roll of a repeated expression; the total is their sum.
+    Repeat {
+        results: Vec<EvalResult>,
+    },
     Annotated {
         expr: Box<EvalResult>,
         annotations: Vec<Annotation>,
@@ -51,14 +325,240 @@ pub enum Value {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DiceRoll {
     pub quantity: usize,
     pub size: u32,
+    /// The die as written (`6`, `%`, `(1d6)`); `size` is the resolved face count.
+    pub sides: DiceSize,
     pub dice: Vec<DieResult>,
+    /// Dice rolled by `eb`, reported apart from the pool and left out of its total.
+    pub bonus: Vec<DieResult>,
     pub operations: Vec<SetOperation>,
+    /// Rolling stopped at `max_rolls` under `truncate_on_limit`, so the pool
+    /// holds fewer dice than it asked for.
+    pub truncated: bool,
+    /// The face slots of a custom die as registered when it was rolled;
+    /// empty for other dice.
+    pub custom_faces: Vec<f64>,
+    /// The dice each keep or drop selected and the selectors that matched
+    /// them, recorded under `record_selector_matches`.
+    pub selector_matches: Vec<SelectorMatches>,
+}
+
+/// The dice one keep or drop selected, each with the indices of the
+/// operation's selectors that matched it, so in `k>5h1` a die picked by both
+/// lists `[0, 1]`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectorMatches {
+    /// The operation's index in the pool's recorded `operations`.
+    pub operation: usize,
+    /// Each selected die's index in `dice` with its matching selectors.
+    pub dice: Vec<(usize, Vec<usize>)>,
+}
+
+/// The successes of a pool counted with `cs`, as [`DiceRoll::success_tally`]
+/// reports them for target-number systems such as Shadowrun.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuccessTally {
+    pub successes: usize,
+    /// Kept dice showing a natural 1.
+    pub ones: usize,
+    /// At least half the kept dice show a 1.
+    pub glitch: bool,
+}
+
+impl DiceRoll {
+    /// Sums the bonus pool rolled by `eb`; 0 when no bonus dice were rolled.
+    pub fn bonus_total(&self) -> f64 {
+        self.bonus.iter().map(|die| die.value).sum()
+    }
+
+    /// Both dice of an advantage or disadvantage roll and the one kept, as
+    /// `(first, second, kept)`; `None` unless the pool is two dice with a
+    /// single keep that left one of them, as `2d20kh1` and `2d20kl1` do.
+    pub fn advantage_pair(&self) -> Option<(f64, f64, f64)> {
+        let [first, second] = self.dice.as_slice() else {
+            return None;
+        };
+        let [operation] = self.operations.as_slice() else {
+            return None;
+        };
+        if operation.operator != SetOperator::Keep || !self.bonus.is_empty() {
+            return None;
+        }
+        let kept = match (first.kept, second.kept) {
+            (true, false) => first.value,
+            (false, true) => second.value,
+            _ => return None,
+        };
+        Some((first.value, second.value, kept))
+    }
+
+    /// The distinct values a die of this pool can show, lowest first: `1..=6`
+    /// for a d6, 0 to 90 in tens for `d%`, 1 to 100 for `d%%` and the registered faces of a custom
+    /// die. Table dice land on rolled entries, so they have no fixed faces and
+    /// report none.
+    pub fn possible_faces(&self) -> Vec<f64> {
+        match &self.sides {
+            DiceSize::Value(_) | DiceSize::Percentile => (1..=self.size).map(f64::from).collect(),
+            DiceSize::Percent => (0..10).map(|n| f64::from(n * 10)).collect(),
+            DiceSize::Fudge => vec![-1.0, 0.0, 1.0],
+            DiceSize::ExprTable(_) => Vec::new(),
+            DiceSize::Custom(_) => {
+                let mut faces = self.custom_faces.clone();
+                faces.sort_by(f64::total_cmp);
+                faces.dedup();
+                faces
+            }
+        }
+    }
+
+    /// The successes `cs` counted among the kept dice and the natural 1s
+    /// beside them; `None` unless the pool counts successes. The pool glitches
+    /// when half or more of its kept dice rolled a 1, so `6d6cs>=5` glitches on
+    /// three 1s even when it also scores successes.
+    pub fn success_tally(&self) -> Option<SuccessTally> {
+        if !self.has_operation(SetOperator::CountSuccess) {
+            return None;
+        }
+        let kept: Vec<_> = self.dice.iter().filter(|die| die.kept).collect();
+        let successes = kept.iter().filter(|die| die.success).count();
+        let ones = kept.iter().filter(|die| die.rolls[0] == 1.0).count();
+        Some(SuccessTally {
+            successes,
+            ones,
+            glitch: ones > 0 && ones * 2 >= kept.len(),
+        })
+    }
+
+    /// The highest kept die less the lowest; `None` when no die was kept.
+    pub fn spread(&self) -> Option<f64> {
+        let mut kept = self.dice.iter().filter(|die| die.kept).map(|die| die.value);
+        let first = kept.next()?;
+        let (low, high) = kept.fold((first, first), |(low, high), value| {
+            (low.min(value), high.max(value))
+        });
+        Some(high - low)
+    }
+
+    /// The length of the longest run of consecutive faces among the kept
+    /// dice, so 2, 3, 4, 6 make a run of 3; 0 when no die was kept.
+    pub fn straight(&self) -> usize {
+        self.longest_run().len()
+    }
+
+    /// The faces of the longest run of consecutive kept faces, lowest first;
+    /// the lowest such run on a tie.
+    fn longest_run(&self) -> Vec<f64> {
+        let mut faces: Vec<f64> = self
+            .dice
+            .iter()
+            .filter(|die| die.kept)
+            .map(|die| die.value)
+            .collect();
+        faces.sort_by(f64::total_cmp);
+        faces.dedup();
+        let (mut best_start, mut best_len) = (0, 0);
+        let mut start = 0;
+        for idx in 0..faces.len() {
+            if idx > 0 && faces[idx] - faces[idx - 1] != 1.0 {
+                start = idx;
+            }
+            if idx + 1 - start > best_len {
+                (best_start, best_len) = (start, idx + 1 - start);
+            }
+        }
+        faces[best_start..best_start + best_len].to_vec()
+    }
+
+    /// Each die's index in `dice` and its share of the pool total, so the
+    /// shares always add up to the total. Dropped dice contribute 0. Under
+    /// `cs`/`cf` a kept die contributes 1 for a success, -1 for a failure and
+    /// 0 otherwise; under `spread` the highest kept die contributes its value,
+    /// the lowest its negated value and the rest 0; under `straight` the first
+    /// kept die showing each face of the longest run contributes 1 and the
+    /// rest 0; otherwise a kept die contributes its value.
+    pub fn contributions(&self) -> Vec<(usize, f64)> {
+        let counting = self.has_operation(SetOperator::CountSuccess)
+            || self.has_operation(SetOperator::CountFailure);
+        let mut run = self
+            .has_operation(SetOperator::Straight)
+            .then(|| self.longest_run());
+        let spread_ends = self.has_operation(SetOperator::Spread).then(|| {
+            let kept = || self.dice.iter().enumerate().filter(|(_, die)| die.kept);
+            let high = kept()
+                .max_by(|a, b| a.1.value.total_cmp(&b.1.value))
+                .map(|(idx, _)| idx);
+            let low = kept()
+                .min_by(|a, b| a.1.value.total_cmp(&b.1.value))
+                .map(|(idx, _)| idx);
+            (high, low)
+        });
+        self.dice
+            .iter()
+            .enumerate()
+            .map(|(idx, die)| {
+                let contribution = if !die.kept {
+                    0.0
+                } else if counting {
+                    (die.success as i32 - die.failure as i32) as f64
+                } else if let Some(run) = &mut run {
+                    match run.iter().position(|&face| face == die.value) {
+                        Some(position) => {
+                            run.remove(position);
+                            1.0
+                        }
+                        None => 0.0,
+                    }
+                } else if let Some((high, low)) = spread_ends {
+                    if high == low {
+                        0.0
+                    } else if high == Some(idx) {
+                        die.value
+                    } else if low == Some(idx) {
+                        -die.value
+                    } else {
+                        0.0
+                    }
+                } else {
+                    die.value
+                };
+                (idx, contribution)
+            })
+            .collect()
+    }
+
+    fn has_operation(&self, operator: SetOperator) -> bool {
+        self.operations
+            .iter()
+            .any(|operation| operation.operator == operator)
+    }
+
+    /// The pool's total: its spread under `spread`, its longest run under
+    /// `straight`, net successes under `cs`/`cf`, and otherwise the sum of the
+    /// kept dice.
+    fn total(&self) -> f64 {
+        let kept = self.dice.iter().filter(|die| die.kept);
+        if self.has_operation(SetOperator::Spread) {
+            self.spread().unwrap_or(0.0)
+        } else if self.has_operation(SetOperator::Straight) {
+            self.straight() as f64
+        } else if self.has_operation(SetOperator::CountSuccess)
+            || self.has_operation(SetOperator::CountFailure)
+        {
+            kept.map(|die| die.success as i32 - die.failure as i32)
+                .sum::<i32>() as f64
+        } else {
+            kept.map(|die| die.value).sum()
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DieResult {
     pub value: f64,
     pub rolls: Vec<f64>,
@@ -66,6 +566,12 @@ pub struct DieResult {
     pub dropped: bool,
     pub origin: DieOrigin,
     pub adjustments: Vec<DieAdjustment>,
+    /// The entry a table die landed on; `value` is its total.
+    pub entry: Option<Box<EvalResult>>,
+    /// Counted as a success by `cs`.
+    pub success: bool,
+    /// Counted as a failure by `cf`.
+    pub failure: bool,
 }
 
 impl DieResult {
@@ -77,6 +583,9 @@ impl DieResult {
             dropped: false,
             origin,
             adjustments: Vec::new(),
+            entry: None,
+            success: false,
+            failure: false,
         }
     }
 
@@ -86,25 +595,48 @@ impl DieResult {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum DieOrigin {
     Original,
     RerollAdd,
     Explosion,
+    /// A die rolled into the bonus pool by `eb`.
+    Bonus,
+    /// A die added by a step explosion, rolled with the stepped-up `size`.
+    StepExplosion {
+        size: u32,
+    },
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum DieAdjustment {
     Minimum { threshold: f64, previous: f64 },
     Maximum { threshold: f64, previous: f64 },
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetRoll {
     pub elements: Vec<SetElement>,
     pub operations: Vec<SetOperation>,
 }
 
+impl SetRoll {
+    /// Kept elements with a non-zero total, so a set of checks such as
+    /// `(1d20 >= 10, 1d20 >= 15)` reports how many of them passed.
+    pub fn success_count(&self) -> usize {
+        self.elements
+            .iter()
+            .filter(|element| element.kept && element.value.total != 0.0)
+            .count()
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetElement {
     pub value: EvalResult,
     pub kept: bool,
@@ -129,22 +661,132 @@ pub fn evaluate_with_rng<R>(expr: &Node, config: EvalConfig, rng: R) -> Result<E
 where
     R: RngCore,
 {
-    Evaluator {
-        rng,
+    Evaluator::new(rng, config).eval_root(expr)
+}
+
+/// Evaluates `expr` with an RNG whose type is only known at runtime, such as
+/// an entropy source picked by a plugin. Every boxed RNG shares the one
+/// evaluator built over `&mut dyn RngCore`.
+pub fn evaluate_with_boxed_rng(
+    expr: &Node,
+    config: EvalConfig,
+    mut rng: Box<dyn RngCore>,
+) -> Result<EvalResult> {
+    let rng: &mut dyn RngCore = &mut *rng;
+    evaluate_with_rng(expr, config, rng)
+}
+
+/// Evaluates `expr` with an RNG seeded from `base_seed` and `namespace`, so
+/// each namespace (a player, a monster) gets its own reproducible stream.
+pub fn evaluate_with_namespace(
+    expr: &Node,
+    config: EvalConfig,
+    base_seed: u64,
+    namespace: &str,
+) -> Result<EvalResult> {
+    let mut hasher = StableHasher::default();
+    hasher.write_u64(base_seed);
+    hasher.write(namespace.as_bytes());
+    evaluate_with_rng(expr, config, StdRng::seed_from_u64(hasher.finish()))
+}
+
+/// Evaluates `expr` and reports the work it took, for tracking down
+/// expressions that are slow to roll.
+pub fn evaluate_with_metrics<R>(
+    expr: &Node,
+    config: EvalConfig,
+    rng: R,
+) -> Result<(EvalResult, EvalMetrics)>
+where
+    R: RngCore,
+{
+    let start = Instant::now();
+    let mut evaluator = Evaluator::new(rng, config);
+    let result = evaluator.eval_root(expr)?;
+    let metrics = EvalMetrics {
+        eval_calls: evaluator.eval_calls,
+        dice_rolled: evaluator.rolls,
+        selections: evaluator.selections,
+        elapsed: start.elapsed(),
+    };
+    Ok((result, metrics))
+}
+
+/// Work counted while evaluating, returned by [`evaluate_with_metrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EvalMetrics {
+    /// Nodes evaluated, counting every selector target and function argument.
+    pub eval_calls: usize,
+    /// Raw die rolls made, counting every reroll and explosion; never more
+    /// than [`EvalConfig::max_rolls`].
+    pub dice_rolled: usize,
+    /// Passes that matched selectors against a pool or set.
+    pub selections: usize,
+    pub elapsed: Duration,
+}
+
+pub fn evaluate_outcome<R>(expr: &Node, config: EvalConfig, rng: R) -> Result<RollOutcome>
+where
+    R: RngCore,
+{
+    let mut evaluator = Evaluator::new(rng, config.clone());
+    let result = evaluator.eval_root(expr)?;
+    Ok(RollOutcome {
+        result,
         config,
-        rolls: 0,
-    }
-    .eval(expr)
+        diagnostics: evaluator.diagnostics,
+    })
 }
 
-struct Evaluator<R: RngCore> {
+pub(crate) struct Evaluator<R: RngCore> {
     rng: R,
     config: EvalConfig,
     rolls: usize,
+    /// Rolls skipped because `max_rolls` was reached under `truncate_on_limit`.
+    truncations: usize,
+    eval_calls: usize,
+    selections: usize,
+    /// The selector matches of the last keep or drop, under
+    /// `record_selector_matches`.
+    last_matches: Option<Vec<(usize, Vec<usize>)>>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<R: RngCore> Evaluator<R> {
-    fn eval(&mut self, node: &Node) -> Result<EvalResult> {
+    pub(crate) fn new(rng: R, config: EvalConfig) -> Self {
+        Self {
+            rng,
+            config,
+            rolls: 0,
+            truncations: 0,
+            eval_calls: 0,
+            selections: 0,
+            last_matches: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Evaluates a whole expression, rounding its total under
+    /// `total_rounding`.
+    fn eval_root(&mut self, expr: &Node) -> Result<EvalResult> {
+        let mut result = self.eval(expr)?;
+        result.total = self.config.total_rounding.apply(result.total);
+        Ok(result)
+    }
+
+    /// Evaluates `node`, failing when its total is not a number, as `0 / 0`,
+    /// a root of a negative number or a sum of opposite infinities has no
+    /// value to total.
+    pub(crate) fn eval(&mut self, node: &Node) -> Result<EvalResult> {
+        let result = self.eval_node(node)?;
+        if result.total.is_nan() {
+            return Err(Eval(format!("'{}' is not a number", node)));
+        }
+        Ok(result)
+    }
+
+    fn eval_node(&mut self, node: &Node) -> Result<EvalResult> {
+        self.eval_calls += 1;
         match node {
             Node::Literal(v) => Ok(EvalResult {
                 total: *v,
@@ -171,24 +813,30 @@ impl<R: RngCore> Evaluator<R> {
             } => {
                 let left_eval = self.eval(left)?;
                 let right_eval = self.eval(right)?;
-                let total = match operator {
-                    BinaryOperator::Add => left_eval.total + right_eval.total,
-                    BinaryOperator::Subtract => left_eval.total - right_eval.total,
-                    BinaryOperator::Multiply => left_eval.total * right_eval.total,
-                    BinaryOperator::Divide => left_eval.total / right_eval.total,
-                    BinaryOperator::IntDivide => (left_eval.total / right_eval.total).trunc(),
-                    BinaryOperator::Modulo => left_eval.total % right_eval.total,
-                    BinaryOperator::Equal => (left_eval.total == right_eval.total) as i32 as f64,
-                    BinaryOperator::NotEqual => (left_eval.total != right_eval.total) as i32 as f64,
-                    BinaryOperator::Greater => (left_eval.total > right_eval.total) as i32 as f64,
-                    BinaryOperator::GreaterEqual => {
-                        (left_eval.total >= right_eval.total) as i32 as f64
-                    }
-                    BinaryOperator::Less => (left_eval.total < right_eval.total) as i32 as f64,
-                    BinaryOperator::LessEqual => {
-                        (left_eval.total <= right_eval.total) as i32 as f64
-                    }
-                };
+                if matches!(
+                    operator,
+                    BinaryOperator::IntDivide | BinaryOperator::CeilDivide | BinaryOperator::Modulo
+                ) && right_eval.total.abs() < EPSILON
+                {
+                    return Err(RollatoriumError::DivideByZero {
+                        operator: *operator,
+                        expr: node.to_string(),
+                    });
+                }
+                let total = operator.apply(left_eval.total, right_eval.total);
+                // `eval` refuses every other NaN; `0 / 0` says why.
+                if total.is_nan()
+                    && *operator == BinaryOperator::Divide
+                    && right_eval.total.abs() < EPSILON
+                {
+                    return Err(RollatoriumError::DivideByZero {
+                        operator: *operator,
+                        expr: node.to_string(),
+                    });
+                }
+                if self.config.warn_precision_loss {
+                    self.check_precision(*operator, left_eval.total, right_eval.total, total);
+                }
                 Ok(EvalResult {
                     total,
                     value: Value::Binary {
@@ -210,6 +858,20 @@ impl<R: RngCore> Evaluator<R> {
                 elements,
                 operations,
             } => self.eval_set(elements, operations),
+            Node::Call { name, args } => self.eval_call(name, args),
+            Node::Repeat { count, expr } => self.eval_repeat(*count, expr),
+            Node::Range { .. } => Err(Eval(format!(
+                "Range '{}' can only be used as a function argument",
+                node
+            ))),
+            Node::Condition(_) => Err(Eval(format!(
+                "Condition '{}' can only be used as a function argument",
+                node
+            ))),
+            Node::Aggregate(aggregate) => Err(Eval(format!(
+                "'{}' can only be used as a selector target",
+                aggregate.name()
+            ))),
             Node::Annotated { expr, annotations } => {
                 let evaluated = self.eval(expr)?;
                 Ok(EvalResult {
@@ -223,6 +885,355 @@ impl<R: RngCore> Evaluator<R> {
         }
     }
 
+    /// Rolls `expr` `count` times. The count is held to `max_dice`, as each
+    /// repetition may roll as much as a pool of dice.
+    fn eval_repeat(&mut self, count: u32, expr: &Node) -> Result<EvalResult> {
+        if count as usize > self.config.max_dice {
+            return Err(Eval(format!(
+                "Repeat count {} exceeds the maximum of {}",
+                count, self.config.max_dice
+            )));
+        }
+        let mut results = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            results.push(self.eval(expr)?);
+        }
+        Ok(EvalResult {
+            total: results.iter().map(|result| result.total).sum(),
+            value: Value::Repeat { results },
+        })
+    }
+
+    fn eval_call(&mut self, name: &str, args: &[Node]) -> Result<EvalResult> {
+        match name {
+            "until" => self.eval_until(args),
+            "clamp" => self.eval_clamp(args),
+            "margin" => self.eval_margin(args),
+            "if" => self.eval_if(args),
+            "gate" => self.eval_gate(args),
+            "chance" => self.eval_chance(args),
+            "needs" => self.eval_needs(args),
+            "reroll_pool" => self.eval_reroll_pool(args),
+            "min" | "max" => self.eval_extreme(name, args),
+            "abs" => self.eval_rounding(name, args, f64::abs),
+            "floor" => self.eval_rounding(name, args, f64::floor),
+            "ceil" => self.eval_rounding(name, args, f64::ceil),
+            "round" => self.eval_rounding(name, args, f64::round),
+            other => Err(Eval(format!("Unknown function '{}'", other))),
+        }
+    }
+
+    /// `until(check, tries)` re-rolls `check` until it is non-zero, totalling
+    /// the number of attempts taken. The recorded arguments are the successful
+    /// attempt and the tries cap; running out of tries is an error.
+    fn eval_until(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [check, tries] = args else {
+            return Err(Eval(format!(
+                "until expects 2 arguments, found {}",
+                args.len()
+            )));
+        };
+        let tries_eval = self.eval(tries)?;
+        let max_tries = self.as_usize(tries_eval.total, "until tries")?;
+        for attempt in 1..=max_tries {
+            let rolls_before = self.rolls;
+            let attempt_eval = self.eval(check)?;
+            if attempt_eval.total != 0.0 {
+                return Ok(EvalResult {
+                    total: attempt as f64,
+                    value: Value::Call {
+                        name: "until".into(),
+                        args: vec![attempt_eval, tries_eval],
+                    },
+                });
+            }
+            if self.rolls == rolls_before {
+                // Nothing was rolled, so every further attempt fails the same way.
+                break;
+            }
+        }
+        Err(Eval(format!(
+            "until condition was not met within {} tries",
+            max_tries
+        )))
+    }
+
+    /// `clamp(value, low..high)` limits the total of `value` to the inclusive
+    /// range. The recorded arguments are the value and the range.
+    fn eval_clamp(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [value, range] = args else {
+            return Err(Eval(format!(
+                "clamp expects 2 arguments, found {}",
+                args.len()
+            )));
+        };
+        let value = self.eval(value)?;
+        let range = self.eval_range(range, "clamp")?;
+        let Value::Range { low, high } = &range.value else {
+            unreachable!("eval_range always yields a range");
+        };
+        Ok(EvalResult {
+            total: value.total.max(low.total).min(high.total),
+            value: Value::Call {
+                name: "clamp".into(),
+                args: vec![value, range],
+            },
+        })
+    }
+
+    /// `margin(check, dc)` totals how far `check` beat `dc`: positive on a
+    /// success, negative on a miss and 0 on an exact hit, unlike `>=`, which
+    /// only reports whether it succeeded. The recorded arguments are both rolls.
+    fn eval_margin(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [check, dc] = args else {
+            return Err(Eval(format!(
+                "margin expects 2 arguments, found {}",
+                args.len()
+            )));
+        };
+        let check = self.eval(check)?;
+        let dc = self.eval(dc)?;
+        Ok(EvalResult {
+            total: check.total - dc.total,
+            value: Value::Call {
+                name: "margin".into(),
+                args: vec![check, dc],
+            },
+        })
+    }
+
+    /// `min(a, b, ...)` and `max(a, b, ...)` total the lowest or highest of
+    /// their arguments, so `max(1d20 + 5, 10)` takes the better of a roll and
+    /// a flat 10. Every argument is rolled and recorded.
+    fn eval_extreme(&mut self, name: &str, args: &[Node]) -> Result<EvalResult> {
+        if args.is_empty() {
+            return Err(Eval(format!(
+                "{} expects at least 1 argument, found 0",
+                name
+            )));
+        }
+        let args = args
+            .iter()
+            .map(|arg| self.eval(arg))
+            .collect::<Result<Vec<_>>>()?;
+        let totals = args.iter().map(|arg| arg.total);
+        let total = if name == "min" {
+            totals.fold(f64::INFINITY, f64::min)
+        } else {
+            totals.fold(f64::NEG_INFINITY, f64::max)
+        };
+        Ok(EvalResult {
+            total,
+            value: Value::Call {
+                name: name.into(),
+                args,
+            },
+        })
+    }
+
+    /// `abs`, `floor`, `ceil` and `round` apply `apply` to the total of their
+    /// single argument; `round` rounds halves away from zero. The recorded
+    /// argument is the roll.
+    fn eval_rounding(
+        &mut self,
+        name: &str,
+        args: &[Node],
+        apply: fn(f64) -> f64,
+    ) -> Result<EvalResult> {
+        let [value] = args else {
+            return Err(Eval(format!(
+                "{} expects 1 argument, found {}",
+                name,
+                args.len()
+            )));
+        };
+        let value = self.eval(value)?;
+        Ok(EvalResult {
+            total: apply(value.total),
+            value: Value::Call {
+                name: name.into(),
+                args: vec![value],
+            },
+        })
+    }
+
+    /// `if(condition, then, else)` totals `then` when the condition is non-zero
+    /// and `else` otherwise. Both branches are rolled unless `lazy_branches`
+    /// is set; the recorded arguments are the condition and the branches
+    /// rolled.
+    fn eval_if(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [condition, then, otherwise] = args else {
+            return Err(Eval(format!(
+                "if expects 3 arguments, found {}",
+                args.len()
+            )));
+        };
+        let condition = self.eval(condition)?;
+        let taken = condition.total != 0.0;
+        let mut rolled = vec![condition];
+        let total = if self.config.lazy_branches {
+            let branch = self.eval(if taken { then } else { otherwise })?;
+            let total = branch.total;
+            rolled.push(branch);
+            total
+        } else {
+            let then = self.eval(then)?;
+            let otherwise = self.eval(otherwise)?;
+            let total = if taken { then.total } else { otherwise.total };
+            rolled.extend([then, otherwise]);
+            total
+        };
+        Ok(EvalResult {
+            total,
+            value: Value::Call {
+                name: "if".into(),
+                args: rolled,
+            },
+        })
+    }
+
+    /// `gate(check, value)` totals `value` when `check` is non-zero and 0
+    /// otherwise, as damage that only lands on a hit. Like `if`, `value` is
+    /// rolled either way unless `lazy_branches` is set; the recorded arguments
+    /// are the check and `value` when it was rolled.
+    fn eval_gate(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [check, value] = args else {
+            return Err(Eval(format!(
+                "gate expects 2 arguments, found {}",
+                args.len()
+            )));
+        };
+        let check = self.eval(check)?;
+        let passed = check.total != 0.0;
+        let mut rolled = vec![check];
+        let mut total = 0.0;
+        if passed || !self.config.lazy_branches {
+            let value = self.eval(value)?;
+            if passed {
+                total = value.total;
+            }
+            rolled.push(value);
+        }
+        Ok(EvalResult {
+            total,
+            value: Value::Call {
+                name: "gate".into(),
+                args: rolled,
+            },
+        })
+    }
+
+    /// `chance(percent)` rolls a d100 and totals 1 when it lands at or under
+    /// `percent`, so `chance(30)` happens 30% of the time. The recorded
+    /// arguments are the percentage and the d100.
+    fn eval_chance(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [percent] = args else {
+            return Err(Eval(format!(
+                "chance expects 1 argument, found {}",
+                args.len()
+            )));
+        };
+        let percent = self.eval(percent)?;
+        let d100 = self.eval_dice(None, &DiceSize::Value(Box::new(Node::Literal(100.0))), &[])?;
+        Ok(EvalResult {
+            total: (d100.total <= percent.total) as i32 as f64,
+            value: Value::Call {
+                name: "chance".into(),
+                args: vec![percent, d100],
+            },
+        })
+    }
+
+    /// `needs(pool, successes)` totals 1 when `pool` totals at least
+    /// `successes` and 0 otherwise, so `needs(6d10cs>=8, 3)` asks whether the
+    /// pool scored three successes. The recorded arguments are the pool, with
+    /// its dice and success count, and the threshold.
+    fn eval_needs(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [pool, successes] = args else {
+            return Err(Eval(format!(
+                "needs expects 2 arguments, found {}",
+                args.len()
+            )));
+        };
+        let pool = self.eval(pool)?;
+        let successes = self.eval(successes)?;
+        Ok(EvalResult {
+            total: (pool.total >= successes.total) as i32 as f64,
+            value: Value::Call {
+                name: "needs".into(),
+                args: vec![pool, successes],
+            },
+        })
+    }
+
+    /// `reroll_pool(pool, condition, rerolls)` rolls `pool` and rolls the whole
+    /// of it again while its total meets `condition`, at most `rerolls` times,
+    /// so `reroll_pool(4d6, <10, 3)` rerolls totals under 10 up to three
+    /// times. The total is the last attempt's, even when an earlier one was
+    /// better. The recorded arguments are every attempt followed by the
+    /// rerolls cap.
+    fn eval_reroll_pool(&mut self, args: &[Node]) -> Result<EvalResult> {
+        let [pool, Node::Condition(condition), rerolls] = args else {
+            return Err(Eval(format!(
+                "reroll_pool expects a pool, a condition such as <10 and a reroll count, found {} arguments",
+                args.len()
+            )));
+        };
+        let target = self.eval(&condition.target)?.total;
+        let rerolls_eval = self.eval(rerolls)?;
+        let max_rerolls = self.as_usize(rerolls_eval.total, "reroll_pool rerolls")?;
+        let mut attempts = Vec::new();
+        loop {
+            let rolls_before = self.rolls;
+            let attempt = self.eval(pool)?;
+            let reroll = selector_matches(
+                condition.kind,
+                attempt.total,
+                target,
+                self.config.selector_epsilon,
+            )
+            .unwrap_or(false);
+            attempts.push(attempt);
+            // A pool that rolled nothing would come up the same every time.
+            if !reroll || attempts.len() > max_rerolls || self.rolls == rolls_before {
+                break;
+            }
+        }
+        let total = attempts.last().map_or(0.0, |attempt| attempt.total);
+        attempts.push(rerolls_eval);
+        Ok(EvalResult {
+            total,
+            value: Value::Call {
+                name: "reroll_pool".into(),
+                args: attempts,
+            },
+        })
+    }
+
+    fn eval_range(&mut self, node: &Node, function: &str) -> Result<EvalResult> {
+        let Node::Range { low, high } = node else {
+            return Err(Eval(format!(
+                "{} expects a range such as 1..20, found '{}'",
+                function, node
+            )));
+        };
+        let low = self.eval(low)?;
+        let high = self.eval(high)?;
+        if low.total > high.total {
+            return Err(Eval(format!(
+                "Range {}..{} is empty; the low bound must not exceed the high bound",
+                low.total, high.total
+            )));
+        }
+        Ok(EvalResult {
+            total: 0.0,
+            value: Value::Range {
+                low: Box::new(low),
+                high: Box::new(high),
+            },
+        })
+    }
+
     fn eval_dice(
         &mut self,
         quantity: Option<&Node>,
@@ -236,12 +1247,47 @@ impl<R: RngCore> Evaluator<R> {
             }
             None => 1,
         };
+        let quantity_value = self.cap_quantity(quantity_value)?;
 
         let (die_low, die_high) = match size {
             DiceSize::Percent => (0u32, 9),
+            DiceSize::Percentile => (1, 100),
+            DiceSize::Fudge => (0, 2),
             DiceSize::Value(inner) => {
                 let result = self.eval(inner)?;
-                (1, self.as_u32(result.total, "die size")?)
+                let sides = self.as_u32(result.total, "die size")?;
+                if sides > self.config.max_die_size {
+                    return Err(Eval(format!(
+                        "Die size {} exceeds the maximum of {}",
+                        sides, self.config.max_die_size
+                    )));
+                }
+                (1, sides)
+            }
+            DiceSize::ExprTable(entries) => {
+                if let Some(operation) = operations.iter().find(|operation| {
+                    !matches!(
+                        operation.operator,
+                        SetOperator::Keep
+                            | SetOperator::Drop
+                            | SetOperator::SortAscending
+                            | SetOperator::SortDescending
+                    )
+                }) {
+                    return Err(Eval(format!(
+                        "Set operation {:?} is not supported for table dice",
+                        operation.operator
+                    )));
+                }
+                let entries = u32::try_from(entries.len())
+                    .map_err(|_| Eval("Too many table dice entries".into()))?;
+                (1, entries)
+            }
+            DiceSize::Custom(name) => {
+                let faces = self.custom_faces(name)?.len();
+                let faces = u32::try_from(faces)
+                    .map_err(|_| Eval(format!("Too many faces on custom die 'd{}'", name)))?;
+                (1, faces)
             }
         };
 
@@ -251,25 +1297,122 @@ impl<R: RngCore> Evaluator<R> {
 
         let distribution = Uniform::new_inclusive(die_low, die_high)
             .map_err(|err| Eval(format!("Invalid die size {}: {}", die_high, err)))?;
-        let mut dice = Vec::with_capacity(quantity_value);
+        let truncations_before = self.truncations;
+        // No pool outgrows `max_rolls`, so a huge quantity under a raised
+        // `max_dice` fails at the roll counter instead of in the allocator.
+        let mut dice = Vec::with_capacity(quantity_value.min(self.config.max_rolls));
         for _ in 0..quantity_value {
-            let roll = self.roll_die(&distribution, size)?;
-            dice.push(DieResult::new(roll, DieOrigin::Original));
+            let Some(roll) = self.roll_die(&distribution, size)? else {
+                break;
+            };
+            let die = match size {
+                DiceSize::ExprTable(entries) => {
+                    let entry = self.eval(&entries[roll as usize - 1])?;
+                    let mut die = DieResult::new(entry.total, DieOrigin::Original);
+                    die.entry = Some(Box::new(entry));
+                    die
+                }
+                _ => DieResult::new(roll, DieOrigin::Original),
+            };
+            dice.push(die);
+        }
+
+        let mut bonus = Vec::new();
+        let mut recorded = Vec::with_capacity(operations.len());
+        let mut selector_matches: Vec<SelectorMatches> = Vec::new();
+        let mut keep_base = None;
+        for operation in operations {
+            let before = self
+                .config
+                .prune_noop_operations
+                .then(|| pool_state(&dice, &bonus));
+            // Matches recorded so far name dice by index, so follow them to
+            // where a sort moves their dice.
+            if matches!(
+                operation.operator,
+                SetOperator::SortAscending | SetOperator::SortDescending
+            ) {
+                let order = sort_order(&dice, operation.operator);
+                let mut moved_to = vec![0; order.len()];
+                for (new_idx, &old_idx) in order.iter().enumerate() {
+                    moved_to[old_idx] = new_idx;
+                }
+                for matches in &mut selector_matches {
+                    for (idx, _) in &mut matches.dice {
+                        *idx = moved_to[*idx];
+                    }
+                    matches.dice.sort_by_key(|(idx, _)| *idx);
+                }
+            }
+            // A run of keeps and drops spans several operations, so its state
+            // is kept here.
+            if matches!(operation.operator, SetOperator::Keep | SetOperator::Drop) {
+                self.apply_selection(&mut dice, operation, &mut keep_base)?;
+            } else {
+                keep_base = None;
+                self.apply_dice_operations(
+                    &mut dice,
+                    &mut bonus,
+                    &distribution,
+                    die_high,
+                    std::slice::from_ref(operation),
+                    size,
+                )?;
+            }
+            // Operations that change how the pool totals always count.
+            let sets_total = matches!(
+                operation.operator,
+                SetOperator::Spread
+                    | SetOperator::Straight
+                    | SetOperator::CountSuccess
+                    | SetOperator::CountFailure
+            );
+            if sets_total || before.is_none_or(|before| before != pool_state(&dice, &bonus)) {
+                if let Some(dice) = self.last_matches.take() {
+                    selector_matches.push(SelectorMatches {
+                        operation: recorded.len(),
+                        dice,
+                    });
+                }
+                recorded.push(operation.clone());
+            }
+            self.last_matches = None;
         }
-
-        self.apply_dice_operations(&mut dice, &distribution, operations, size)?;
         for die in &mut dice {
             die.refresh_drop_state();
         }
-        let total: f64 = dice.iter().filter(|d| d.kept).map(|d| d.value).sum();
+        let roll = DiceRoll {
+            quantity: quantity_value,
+            size: die_high,
+            sides: size.clone(),
+            dice,
+            bonus,
+            operations: recorded,
+            truncated: self.truncations > truncations_before,
+            custom_faces: match size {
+                DiceSize::Custom(name) => self.custom_faces(name)?.to_vec(),
+                _ => Vec::new(),
+            },
+            selector_matches,
+        };
+        if roll.has_operation(SetOperator::Spread)
+            && (roll.has_operation(SetOperator::CountSuccess)
+                || roll.has_operation(SetOperator::CountFailure))
+        {
+            return Err(Eval("spread cannot be combined with cs/cf".into()));
+        }
+        if roll.has_operation(SetOperator::Straight)
+            && (roll.has_operation(SetOperator::Spread)
+                || roll.has_operation(SetOperator::CountSuccess)
+                || roll.has_operation(SetOperator::CountFailure))
+        {
+            return Err(Eval(
+                "straight cannot be combined with spread or cs/cf".into(),
+            ));
+        }
         Ok(EvalResult {
-            total,
-            value: Value::Dice(DiceRoll {
-                quantity: quantity_value,
-                size: die_high,
-                dice,
-                operations: operations.to_vec(),
-            }),
+            total: roll.total(),
+            value: Value::Dice(roll),
         })
     }
 
@@ -302,17 +1445,82 @@ impl<R: RngCore> Evaluator<R> {
         })
     }
 
-    fn roll_die(&mut self, distribution: &Uniform<u32>, die_size: &DiceSize) -> Result<f64> {
+    /// Rolls one die, or `None` once `max_rolls` is reached under
+    /// `truncate_on_limit`; callers then stop adding dice.
+    fn roll_die(
+        &mut self,
+        distribution: &Uniform<u32>,
+        die_size: &DiceSize,
+    ) -> Result<Option<f64>> {
         if self.rolls >= self.config.max_rolls {
+            if self.config.truncate_on_limit {
+                self.truncations += 1;
+                return Ok(None);
+            }
             return Err(Eval("Exceeded maximum number of rolls".into()));
         }
         self.rolls += 1;
         let mut value = distribution.sample(&mut self.rng) as f64;
-        if DiceSize::Percent == *die_size {
-            value *= 10.0;
+        match die_size {
+            DiceSize::Percent => value = percent_value(value)?,
+            DiceSize::Fudge => value -= 1.0,
+            DiceSize::Custom(name) => value = self.custom_faces(name)?[value as usize - 1],
+            _ => {}
         }
 
-        Ok(value)
+        Ok(Some(value))
+    }
+
+    fn custom_faces(&self, name: &str) -> Result<&[f64]> {
+        self.config
+            .dice
+            .faces(name)
+            .ok_or_else(|| Eval(format!("Unknown custom die 'd{}'", name)))
+    }
+
+    fn check_precision(&mut self, operator: BinaryOperator, left: f64, right: f64, total: f64) {
+        let exact_operation = matches!(
+            operator,
+            BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply
+        );
+        if exact_operation
+            && left.fract() == 0.0
+            && right.fract() == 0.0
+            && total.abs() > MAX_SAFE_INTEGER
+        {
+            self.diagnostics
+                .push(Diagnostic::PrecisionLoss { operator, total });
+        }
+    }
+
+    fn cap_quantity(&mut self, quantity: usize) -> Result<usize> {
+        let max_dice = self.config.max_dice;
+        if quantity <= max_dice {
+            return Ok(quantity);
+        }
+        if !self.config.clamp_quantity_to_max {
+            return Err(Eval(format!(
+                "Dice quantity {} exceeds the maximum of {}",
+                quantity, max_dice
+            )));
+        }
+        self.diagnostics.push(Diagnostic::QuantityClamped {
+            requested: quantity,
+            clamped: max_dice,
+        });
+        Ok(max_dice)
+    }
+
+    /// The smallest ladder size above `current`; the top of the ladder (or a
+    /// size beyond it) keeps exploding at its own size.
+    fn next_ladder_size(&self, current: u32) -> u32 {
+        self.config
+            .explode_ladder
+            .iter()
+            .copied()
+            .filter(|&size| size > current)
+            .min()
+            .unwrap_or(current)
     }
 
     fn as_usize(&self, value: f64, context: &str) -> Result<usize> {
@@ -341,81 +1549,339 @@ impl<R: RngCore> Evaluator<R> {
         Ok(value.round() as u32)
     }
 
+    /// Applies a keep or drop to a dice pool, with the same semantics as
+    /// `apply_set_operations`: each keep in a run of keeps and drops selects
+    /// among the dice kept when the run's first keep began, and the pool keeps
+    /// the union of those selections, so `4d6kh2kl1` keeps the two highest dice
+    /// and the lowest. A drop removes dice that are still kept. `keep_base`
+    /// holds the dice kept before the run's first keep; callers reset it to
+    /// `None` when any other operation ends the run, as parentheses do here.
+    fn apply_selection(
+        &mut self,
+        dice: &mut [DieResult],
+        operation: &SetOperation,
+        keep_base: &mut Option<Vec<bool>>,
+    ) -> Result<()> {
+        if operation.after_parens {
+            *keep_base = None;
+        }
+        let kept_before: Vec<bool> = dice.iter().map(|die| die.kept).collect();
+        let first_keep = operation.operator == SetOperator::Keep && keep_base.is_none();
+        if operation.operator == SetOperator::Keep {
+            let base = keep_base.get_or_insert_with(|| kept_before.clone());
+            for (die, &kept) in dice.iter_mut().zip(base.iter()) {
+                die.kept = kept;
+            }
+        }
+        let selectors = self.resolve_selectors(&operation.selectors, dice)?;
+        let per_selector = self.selector_indices(dice, &selectors)?;
+        let selected = union_indices(dice.len(), &per_selector);
+        if self.config.record_selector_matches {
+            let mut matches: Vec<(usize, Vec<usize>)> =
+                selected.iter().map(|&idx| (idx, Vec::new())).collect();
+            for (selector, indices) in per_selector.iter().enumerate() {
+                for idx in indices {
+                    if let Ok(position) = selected.binary_search(idx) {
+                        matches[position].1.push(selector);
+                    }
+                }
+            }
+            self.last_matches = Some(matches);
+        }
+        if operation.operator == SetOperator::Keep {
+            for (die, kept) in dice.iter_mut().zip(kept_before) {
+                die.kept = kept && !first_keep;
+            }
+            for idx in selected {
+                dice[idx].kept = true;
+            }
+        } else {
+            for idx in selected {
+                if let Some(die) = dice.get_mut(idx) {
+                    die.kept = false;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the dice a `cs` selects as successes or a `cf` selects as
+    /// failures. A pool with either operation totals its kept successes less
+    /// its kept failures rather than its pips, so `5d10cs>=8` counts the dice
+    /// showing 8 or more and `cf<=1` takes one off for each 1.
+    fn apply_count(&mut self, dice: &mut [DieResult], operation: &SetOperation) -> Result<()> {
+        let success = operation.operator == SetOperator::CountSuccess;
+        for idx in self.select_dice(dice, &operation.selectors)? {
+            if success {
+                dice[idx].success = true;
+            } else {
+                dice[idx].failure = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Totals a pool of face values after keep/drop `operations`, exactly as
+    /// dice that rolled those faces would be totalled.
+    pub(crate) fn kept_total(&mut self, faces: &[f64], operations: &[SetOperation]) -> Result<f64> {
+        let mut dice: Vec<_> = faces
+            .iter()
+            .map(|&face| DieResult::new(face, DieOrigin::Original))
+            .collect();
+        let mut keep_base = None;
+        for operation in operations {
+            match operation.operator {
+                SetOperator::Keep | SetOperator::Drop => {
+                    self.apply_selection(&mut dice, operation, &mut keep_base)?
+                }
+                // Sorting only reorders the faces, ending any run of keeps.
+                SetOperator::SortAscending | SetOperator::SortDescending => keep_base = None,
+                other => {
+                    return Err(Eval(format!(
+                        "Set operation {:?} cannot be totalled without rolling",
+                        other
+                    )));
+                }
+            }
+        }
+        Ok(dice.iter().filter(|d| d.kept).map(|d| d.value).sum())
+    }
+
     fn apply_dice_operations(
         &mut self,
         dice: &mut Vec<DieResult>,
+        bonus: &mut Vec<DieResult>,
         distribution: &Uniform<u32>,
+        die_high: u32,
         operations: &[SetOperation],
         size: &DiceSize,
     ) -> Result<()> {
+        let mut keep_base = None;
         for operation in operations {
+            if !matches!(operation.operator, SetOperator::Keep | SetOperator::Drop) {
+                keep_base = None;
+            }
             match operation.operator {
-                SetOperator::Keep => {
-                    let selected = self.select_dice(dice, &operation.selectors)?;
-                    let selected: HashSet<_> = selected.into_iter().collect();
-                    for (idx, die) in dice.iter_mut().enumerate() {
-                        if die.kept {
-                            die.kept = selected.contains(&idx);
-                        }
-                    }
+                SetOperator::Keep | SetOperator::Drop => {
+                    self.apply_selection(dice, operation, &mut keep_base)?
                 }
-                SetOperator::Drop => {
-                    let selected = self.select_dice(dice, &operation.selectors)?;
-                    for idx in selected {
-                        if let Some(die) = dice.get_mut(idx) {
-                            die.kept = false;
+                SetOperator::Reroll => {
+                    let selectors = self.resolve_selectors(&operation.selectors, dice)?;
+                    let mut rerolls = vec![0; dice.len()];
+                    'reroll: loop {
+                        let selected = self.select_resolved(dice, &selectors)?;
+                        if selected.is_empty() {
+                            break;
                         }
-                    }
-                }
-                SetOperator::Reroll => loop {
-                    let selected = self.select_dice(dice, &operation.selectors)?;
-                    if selected.is_empty() {
-                        break;
-                    }
-                    let mut changed = false;
-                    for idx in selected {
-                        if let Some(die) = dice.get_mut(idx) {
-                            let new_value = self.roll_die(distribution, size)?;
-                            die.rolls.push(new_value);
-                            die.value = new_value;
-                            changed = true;
+                        let mut changed = false;
+                        for idx in selected {
+                            // A die at its cap keeps its last value.
+                            if self
+                                .config
+                                .max_rerolls_per_die
+                                .is_some_and(|cap| rerolls[idx] >= cap)
+                            {
+                                continue;
+                            }
+                            if let Some(die) = dice.get_mut(idx) {
+                                let Some(new_value) = self.roll_die(distribution, size)? else {
+                                    break 'reroll;
+                                };
+                                die.rolls.push(new_value);
+                                die.value = new_value;
+                                rerolls[idx] += 1;
+                                changed = true;
+                            }
+                        }
+                        if !changed {
+                            break;
                         }
                     }
-                    if !changed {
-                        break;
-                    }
-                },
+                }
                 SetOperator::RerollOnce => {
-                    let selected = self.select_dice(dice, &operation.selectors)?;
+                    let selectors = self.resolve_selectors(&operation.selectors, dice)?;
+                    let selected = self.select_resolved(dice, &selectors)?;
                     for idx in selected {
                         if let Some(die) = dice.get_mut(idx) {
-                            let new_value = self.roll_die(distribution, size)?;
+                            let Some(new_value) = self.roll_die(distribution, size)? else {
+                                break;
+                            };
                             die.rolls.push(new_value);
                             die.value = new_value;
                         }
                     }
                 }
                 SetOperator::RerollAdd => {
-                    let selected = self.select_dice(dice, &operation.selectors)?;
+                    let selectors = self.resolve_selectors(&operation.selectors, dice)?;
+                    let selected = self.select_resolved(dice, &selectors)?;
                     for _ in 0..selected.len() {
-                        let new_value = self.roll_die(distribution, size)?;
+                        let Some(new_value) = self.roll_die(distribution, size)? else {
+                            break;
+                        };
                         dice.push(DieResult::new(new_value, DieOrigin::RerollAdd));
                     }
                 }
+                SetOperator::RerollBetter | SetOperator::RerollWorse => {
+                    let selected = if operation.selectors.is_empty() {
+                        (0..dice.len()).filter(|&idx| dice[idx].kept).collect()
+                    } else {
+                        let selectors = self.resolve_selectors(&operation.selectors, dice)?;
+                        self.select_resolved(dice, &selectors)?
+                    };
+                    let better = operation.operator == SetOperator::RerollBetter;
+                    for idx in selected {
+                        let Some(new_value) = self.roll_die(distribution, size)? else {
+                            break;
+                        };
+                        let die = &mut dice[idx];
+                        die.rolls.push(new_value);
+                        die.value = if better {
+                            die.value.max(new_value)
+                        } else {
+                            die.value.min(new_value)
+                        };
+                    }
+                }
                 SetOperator::Explode => {
-                    let mut queue = self.select_dice(dice, &operation.selectors)?;
+                    let selectors = self.resolve_selectors(&operation.selectors, dice)?;
+                    // How deep each pending explosion sits in its chain.
+                    let mut queue = vec![0; self.select_resolved(dice, &selectors)?.len()];
+                    let epsilon = self.config.selector_epsilon;
+                    // Value selectors such as `>=8` judge a new die by its own
+                    // value; positional ones such as `h1` rank it in the pool.
+                    let positional = selectors.iter().any(|&(kind, target)| {
+                        selector_matches(kind, 0.0, target, epsilon).is_none()
+                    });
                     let mut idx = 0;
                     while idx < queue.len() {
+                        let depth = queue[idx];
                         idx += 1;
-                        let new_value = self.roll_die(distribution, size)?;
+                        if self
+                            .config
+                            .max_explosion_depth
+                            .is_some_and(|cap| depth >= cap)
+                        {
+                            continue;
+                        }
+                        let Some(new_value) = self.roll_die(distribution, size)? else {
+                            break;
+                        };
                         dice.push(DieResult::new(new_value, DieOrigin::Explosion));
-                        let new_idx = dice.len() - 1;
-                        let matches = self
-                            .select_dice(dice, &operation.selectors)?
-                            .into_iter()
-                            .any(|i| i == new_idx);
+                        let matches = if positional {
+                            let new_idx = dice.len() - 1;
+                            self.select_resolved(dice, &selectors)?.contains(&new_idx)
+                        } else {
+                            selectors.iter().any(|&(kind, target)| {
+                                selector_matches(kind, new_value, target, epsilon) == Some(true)
+                            })
+                        };
                         if matches {
-                            queue.push(new_idx);
+                            queue.push(depth + 1);
+                        }
+                    }
+                }
+                SetOperator::ExplodeCompound => {
+                    let selectors = self.explosion_selectors(operation, dice, size, die_high)?;
+                    let epsilon = self.config.selector_epsilon;
+                    'compound: for idx in self.select_resolved(dice, &selectors)? {
+                        loop {
+                            let Some(new_value) = self.roll_die(distribution, size)? else {
+                                break 'compound;
+                            };
+                            let die = &mut dice[idx];
+                            die.rolls.push(new_value);
+                            die.value += new_value;
+                            let chains = selectors.iter().any(|&(kind, target)| {
+                                selector_matches(kind, new_value, target, epsilon) == Some(true)
+                            });
+                            if !chains {
+                                break;
+                            }
+                        }
+                    }
+                }
+                SetOperator::ExplodePenetrate => {
+                    let selectors = self.explosion_selectors(operation, dice, size, die_high)?;
+                    let epsilon = self.config.selector_epsilon;
+                    'penetrate: for idx in self.select_resolved(dice, &selectors)? {
+                        loop {
+                            let Some(raw) = self.roll_die(distribution, size)? else {
+                                break 'penetrate;
+                            };
+                            let die = &mut dice[idx];
+                            die.rolls.push(raw);
+                            die.value += raw - 1.0;
+                            // As with `!p`, chains follow the raw roll.
+                            let chains = selectors.iter().any(|&(kind, target)| {
+                                selector_matches(kind, raw, target, epsilon) == Some(true)
+                            });
+                            if !chains {
+                                break;
+                            }
+                        }
+                    }
+                }
+                SetOperator::Penetrate => {
+                    let selectors = self.explosion_selectors(operation, dice, size, die_high)?;
+                    let epsilon = self.config.selector_epsilon;
+                    let mut pending = self.select_resolved(dice, &selectors)?.len();
+                    while pending > 0 {
+                        pending -= 1;
+                        let Some(raw) = self.roll_die(distribution, size)? else {
+                            break;
+                        };
+                        let mut die = DieResult::new(raw - 1.0, DieOrigin::Explosion);
+                        die.rolls = vec![raw];
+                        dice.push(die);
+                        // Chains follow the raw roll, so a penetrating 6 on a
+                        // d6 keeps going though it only counts 5.
+                        if selectors.iter().any(|&(kind, target)| {
+                            selector_matches(kind, raw, target, epsilon) == Some(true)
+                        }) {
+                            pending += 1;
+                        }
+                    }
+                }
+                SetOperator::BonusExplode => {
+                    let selectors = self.resolve_selectors(&operation.selectors, dice)?;
+                    let mut pending = self.select_resolved(dice, &selectors)?.len();
+                    while pending > 0 {
+                        pending -= 1;
+                        let Some(new_value) = self.roll_die(distribution, size)? else {
+                            break;
+                        };
+                        bonus.push(DieResult::new(new_value, DieOrigin::Bonus));
+                        let new_idx = bonus.len() - 1;
+                        if self.select_resolved(bonus, &selectors)?.contains(&new_idx) {
+                            pending += 1;
+                        }
+                    }
+                }
+                SetOperator::StepExplode => {
+                    if !matches!(size, DiceSize::Value(_)) {
+                        return Err(Eval("Step explosion requires numbered dice".into()));
+                    }
+                    let aces = dice
+                        .iter()
+                        .filter(|die| die.kept && die.value == die_high as f64)
+                        .count();
+                    'aces: for _ in 0..aces {
+                        let mut current = die_high;
+                        loop {
+                            current = self.next_ladder_size(current);
+                            let step = Uniform::new_inclusive(1, current).map_err(|err| {
+                                Eval(format!("Invalid die size {}: {}", current, err))
+                            })?;
+                            let Some(new_value) = self.roll_die(&step, size)? else {
+                                break 'aces;
+                            };
+                            dice.push(DieResult::new(
+                                new_value,
+                                DieOrigin::StepExplosion { size: current },
+                            ));
+                            if new_value != current as f64 {
+                                break;
+                            }
                         }
                     }
                 }
@@ -452,6 +1918,29 @@ impl<R: RngCore> Evaluator<R> {
                         }
                     }
                 }
+                SetOperator::WeightedKeep => {
+                    if !matches!(size, DiceSize::Value(_)) {
+                        return Err(Eval("Weighted keep requires numbered dice".into()));
+                    }
+                    // A die of the pool's size lands at or under the value with
+                    // probability `value / sides`.
+                    for die in dice.iter_mut().filter(|die| die.kept) {
+                        let Some(draw) = self.roll_die(distribution, size)? else {
+                            break;
+                        };
+                        die.kept = draw <= die.value;
+                    }
+                }
+                // The spread and straight are taken from the final pool when
+                // totalling.
+                SetOperator::Spread | SetOperator::Straight => {}
+                SetOperator::SortAscending | SetOperator::SortDescending => {
+                    let order = sort_order(dice, operation.operator);
+                    *dice = order.into_iter().map(|idx| dice[idx].clone()).collect();
+                }
+                SetOperator::CountSuccess | SetOperator::CountFailure => {
+                    self.apply_count(dice, operation)?
+                }
                 SetOperator::Maximum => {
                     if operation.selectors.is_empty() {
                         return Err(Eval("Maximum operation requires a selector".into()));
@@ -482,33 +1971,36 @@ impl<R: RngCore> Evaluator<R> {
                         }
                     }
                 }
-                other => {
-                    return Err(Eval(format!(
-                        "Set operation {:?} is not supported in the evaluator",
-                        other
-                    )));
-                }
             }
         }
         Ok(())
     }
 
+    /// Applies keeps and drops to set elements. Each keep selects among the
+    /// elements kept when the first keep began, and the set keeps the union of
+    /// those selections: `(1, 2, 3, 4, 5)kh2kl1` keeps 5, 4 and 1. A drop only
+    /// removes elements that are still kept. Dice pools follow the same rules;
+    /// see `apply_selection`.
     fn apply_set_operations(
         &mut self,
         elements: &mut [SetElement],
         operations: &[SetOperation],
     ) -> Result<()> {
-        let mut keep_initialized = false;
+        let mut keep_base: Option<Vec<bool>> = None;
         for operation in operations {
             match operation.operator {
                 SetOperator::Keep => {
+                    let kept_before: Vec<bool> =
+                        elements.iter().map(|element| element.kept).collect();
+                    let first_keep = keep_base.is_none();
+                    let base = keep_base.get_or_insert_with(|| kept_before.clone());
+                    for (element, &kept) in elements.iter_mut().zip(base.iter()) {
+                        element.kept = kept;
+                    }
                     let selected =
-                        self.select_set_elements(elements, &operation.selectors, false)?;
-                    if !keep_initialized {
-                        for element in elements.iter_mut() {
-                            element.kept = false;
-                        }
-                        keep_initialized = true;
+                        self.select_set_elements(elements, &operation.selectors, true)?;
+                    for (element, kept) in elements.iter_mut().zip(kept_before) {
+                        element.kept = kept && !first_keep;
                     }
                     for idx in selected {
                         if let Some(element) = elements.get_mut(idx) {
@@ -536,57 +2028,93 @@ impl<R: RngCore> Evaluator<R> {
         Ok(())
     }
 
-    fn select_dice(&mut self, dice: &[DieResult], selectors: &[Selector]) -> Result<Vec<usize>> {
-        if selectors.is_empty() {
-            return Ok(Vec::new());
+    /// The selectors of `!!` or `!p`, which on their own explode on the die's
+    /// highest face.
+    fn explosion_selectors(
+        &mut self,
+        operation: &SetOperation,
+        dice: &[DieResult],
+        size: &DiceSize,
+        die_high: u32,
+    ) -> Result<Vec<(SelectorKind, f64)>> {
+        if !operation.selectors.is_empty() {
+            return self.resolve_selectors(&operation.selectors, dice);
         }
-        let mut selected = HashSet::new();
-        for selector in selectors {
-            let mut indices = match selector.kind {
+        let highest = match size {
+            DiceSize::Percent => 90.0,
+            DiceSize::Fudge => 1.0,
+            DiceSize::Custom(name) => self
+                .custom_faces(name)?
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max),
+            _ => die_high as f64,
+        };
+        Ok(vec![(SelectorKind::EqualTo, highest)])
+    }
+
+    fn select_dice(&mut self, dice: &[DieResult], selectors: &[Selector]) -> Result<Vec<usize>> {
+        let selectors = self.resolve_selectors(selectors, dice)?;
+        self.select_resolved(dice, &selectors)
+    }
+
+    /// Evaluates each selector target once, so operations that select
+    /// repeatedly (reroll loops, explosion chains) match every die against the
+    /// same rolled threshold. Aggregates such as `avg` are taken over the dice
+    /// still kept when the operation starts, so `4d6rr<avg` rerolls the dice
+    /// that rolled under the pool's first average.
+    fn resolve_selectors(
+        &mut self,
+        selectors: &[Selector],
+        dice: &[DieResult],
+    ) -> Result<Vec<(SelectorKind, f64)>> {
+        let pool: Vec<f64> = dice.iter().filter(|d| d.kept).map(|d| d.value).collect();
+        selectors
+            .iter()
+            .map(|selector| Ok((selector.kind, self.resolve_target(&selector.target, &pool)?)))
+            .collect()
+    }
+
+    fn select_resolved(
+        &mut self,
+        dice: &[DieResult],
+        selectors: &[(SelectorKind, f64)],
+    ) -> Result<Vec<usize>> {
+        let per_selector = self.selector_indices(dice, selectors)?;
+        Ok(union_indices(dice.len(), &per_selector))
+    }
+
+    /// The dice each selector picks on its own, one list per selector.
+    fn selector_indices(
+        &mut self,
+        dice: &[DieResult],
+        selectors: &[(SelectorKind, f64)],
+    ) -> Result<Vec<Vec<usize>>> {
+        self.selections += 1;
+        let epsilon = self.config.selector_epsilon;
+        let mut per_selector = Vec::with_capacity(selectors.len());
+        for &(kind, target) in selectors {
+            let indices = match kind {
                 SelectorKind::Highest => {
-                    let value = self.eval(&selector.target)?.total;
-                    let count = self.as_usize(value, "selector")?;
+                    let count = self.as_usize(target, "selector")?;
                     self.select_highest(dice, count)
                 }
                 SelectorKind::Lowest => {
-                    let value = self.eval(&selector.target)?.total;
-                    let count = self.as_usize(value, "selector")?;
+                    let count = self.as_usize(target, "selector")?;
                     self.select_lowest(dice, count)
                 }
-                SelectorKind::GreaterThan => {
-                    let value = self.eval(&selector.target)?.total;
-                    self.select_value(dice, |die_value| die_value > value)
-                }
-                SelectorKind::GreaterThanOrEqual => {
-                    let value = self.eval(&selector.target)?.total;
-                    self.select_value(dice, |die_value| die_value >= value)
-                }
-                SelectorKind::LessThan => {
-                    let value = self.eval(&selector.target)?.total;
-                    self.select_value(dice, |die_value| die_value < value)
-                }
-                SelectorKind::LessThanOrEqual => {
-                    let value = self.eval(&selector.target)?.total;
-                    self.select_value(dice, |die_value| die_value <= value)
-                }
-                SelectorKind::EqualTo => {
-                    let value = self.eval(&selector.target)?.total;
-                    self.select_value(dice, |die_value| (die_value - value).abs() <= EPSILON)
-                }
-                SelectorKind::NotEqual => {
-                    let value = self.eval(&selector.target)?.total;
-                    self.select_value(dice, |die_value| (die_value - value).abs() > EPSILON)
-                }
-                SelectorKind::Literal => {
-                    let value = self.eval(&selector.target)?.total;
-                    self.select_value(dice, |die_value| (die_value - value).abs() <= EPSILON)
+                SelectorKind::Middle => {
+                    let count = self.as_usize(target, "selector")?;
+                    self.select_lowest(dice, usize::MAX)
+                        .map(|sorted| middle_of(sorted, count))
                 }
+                _ => self.select_value(dice, |value| {
+                    selector_matches(kind, value, target, epsilon).unwrap_or(false)
+                }),
             }?;
-            selected.extend(indices.drain(..));
+            per_selector.push(indices);
         }
-        let mut collected: Vec<_> = selected.into_iter().collect();
-        collected.sort_unstable();
-        Ok(collected)
+        Ok(per_selector)
     }
 
     fn select_set_elements(
@@ -598,65 +2126,88 @@ impl<R: RngCore> Evaluator<R> {
         if selectors.is_empty() {
             return Ok(Vec::new());
         }
-        let mut selected = HashSet::new();
+        self.selections += 1;
+        let epsilon = self.config.selector_epsilon;
+        let pool: Vec<f64> = elements
+            .iter()
+            .filter(|element| !only_kept || element.kept)
+            .map(|element| element.value.total)
+            .collect();
+        let mut selected = vec![false; elements.len()];
         for selector in selectors {
-            let mut indices = match selector.kind {
+            let indices = match selector.kind {
                 SelectorKind::Highest => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     let count = self.as_usize(value, "selector")?;
                     self.select_set_highest(elements, count, only_kept)
                 }
                 SelectorKind::Lowest => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     let count = self.as_usize(value, "selector")?;
                     self.select_set_lowest(elements, count, only_kept)
                 }
+                SelectorKind::Middle => {
+                    let value = self.resolve_target(&selector.target, &pool)?;
+                    let count = self.as_usize(value, "selector")?;
+                    self.select_set_lowest(elements, usize::MAX, only_kept)
+                        .map(|sorted| middle_of(sorted, count))
+                }
                 SelectorKind::GreaterThan => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     self.select_set_value(elements, |element| element > value, only_kept)
                 }
                 SelectorKind::GreaterThanOrEqual => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     self.select_set_value(elements, |element| element >= value, only_kept)
                 }
                 SelectorKind::LessThan => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     self.select_set_value(elements, |element| element < value, only_kept)
                 }
                 SelectorKind::LessThanOrEqual => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     self.select_set_value(elements, |element| element <= value, only_kept)
                 }
                 SelectorKind::EqualTo => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     self.select_set_value(
                         elements,
-                        |element| (element - value).abs() <= EPSILON,
+                        |element| (element - value).abs() <= epsilon,
                         only_kept,
                     )
                 }
                 SelectorKind::NotEqual => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     self.select_set_value(
                         elements,
-                        |element| (element - value).abs() > EPSILON,
+                        |element| (element - value).abs() > epsilon,
                         only_kept,
                     )
                 }
                 SelectorKind::Literal => {
-                    let value = self.eval(&selector.target)?.total;
+                    let value = self.resolve_target(&selector.target, &pool)?;
                     self.select_set_value(
                         elements,
-                        |element| (element - value).abs() <= EPSILON,
+                        |element| (element - value).abs() <= epsilon,
                         only_kept,
                     )
                 }
             }?;
-            selected.extend(indices.drain(..));
+            for idx in indices {
+                selected[idx] = true;
+            }
+        }
+        Ok(mask_indices(&selected))
+    }
+
+    /// Resolves a selector target, computing aggregates such as `mean` over
+    /// `pool`. Ties are left to the comparison: `k>mean` skips elements equal to
+    /// the mean while `k>=mean` keeps them.
+    fn resolve_target(&mut self, target: &Node, pool: &[f64]) -> Result<f64> {
+        match target {
+            Node::Aggregate(aggregate) => Ok(aggregate_of(*aggregate, pool)),
+            other => Ok(self.eval(other)?.total),
         }
-        let mut collected: Vec<_> = selected.into_iter().collect();
-        collected.sort_unstable();
-        Ok(collected)
     }
 
     fn select_highest(&self, dice: &[DieResult], count: usize) -> Result<Vec<usize>> {
@@ -666,7 +2217,11 @@ impl<R: RngCore> Evaluator<R> {
             .filter(|(_, die)| die.kept)
             .map(|(idx, _)| idx)
             .collect();
-        indices.sort_by(|a, b| self.compare_desc(&dice[*a].value, &dice[*b].value));
+        // Equal dice go to the earliest rolled, however the sort is done.
+        indices.sort_by(|a, b| {
+            self.compare_desc(&dice[*a].value, &dice[*b].value)
+                .then(a.cmp(b))
+        });
         indices.truncate(count.min(indices.len()));
         Ok(indices)
     }
@@ -678,7 +2233,10 @@ impl<R: RngCore> Evaluator<R> {
             .filter(|(_, die)| die.kept)
             .map(|(idx, _)| idx)
             .collect();
-        indices.sort_by(|a, b| self.compare_asc(&dice[*a].value, &dice[*b].value));
+        indices.sort_by(|a, b| {
+            self.compare_asc(&dice[*a].value, &dice[*b].value)
+                .then(a.cmp(b))
+        });
         indices.truncate(count.min(indices.len()));
         Ok(indices)
     }
@@ -709,6 +2267,7 @@ impl<R: RngCore> Evaluator<R> {
             .collect();
         indices.sort_by(|a, b| {
             self.compare_desc(&elements[*a].value.total, &elements[*b].value.total)
+                .then(a.cmp(b))
         });
         indices.truncate(count.min(indices.len()));
         Ok(indices)
@@ -726,8 +2285,10 @@ impl<R: RngCore> Evaluator<R> {
             .filter(|(_, element)| !only_kept || element.kept)
             .map(|(idx, _)| idx)
             .collect();
-        indices
-            .sort_by(|a, b| self.compare_asc(&elements[*a].value.total, &elements[*b].value.total));
+        indices.sort_by(|a, b| {
+            self.compare_asc(&elements[*a].value.total, &elements[*b].value.total)
+                .then(a.cmp(b))
+        });
         indices.truncate(count.min(indices.len()));
         Ok(indices)
     }
@@ -757,3 +2318,324 @@ impl<R: RngCore> Evaluator<R> {
         a.partial_cmp(b).unwrap_or(Ordering::Equal)
     }
 }
+
+/// What an operation can change about a pool: each die's value, keep state,
+/// rerolls, adjustments and success marks, plus the bonus pool's size.
+fn pool_state(dice: &[DieResult], bonus: &[DieResult]) -> (Vec<PoolDieState>, usize) {
+    let dice = dice
+        .iter()
+        .map(|die| {
+            (
+                die.value.to_bits(),
+                die.kept,
+                die.rolls.len(),
+                die.adjustments.len(),
+                die.success,
+                die.failure,
+            )
+        })
+        .collect();
+    (dice, bonus.len())
+}
+
+type PoolDieState = (u64, bool, usize, usize, bool, bool);
+
+/// Gathers every dice pool in `result`, in evaluation order.
+fn collect_pools<'a>(result: &'a EvalResult, pools: &mut Vec<&'a DiceRoll>) {
+    match &result.value {
+        Value::Literal(_) => {}
+        Value::Unary { operand, .. } => collect_pools(operand, pools),
+        Value::Binary { left, right, .. } => {
+            collect_pools(left, pools);
+            collect_pools(right, pools);
+        }
+        Value::Dice(roll) => pools.push(roll),
+        Value::Set(set) => {
+            for element in &set.elements {
+                collect_pools(&element.value, pools);
+            }
+        }
+        Value::Range { low, high } => {
+            collect_pools(low, pools);
+            collect_pools(high, pools);
+        }
+        Value::Call { args, .. } => {
+            for arg in args {
+                collect_pools(arg, pools);
+            }
+        }
+        Value::Repeat { results } => {
+            for repetition in results {
+                collect_pools(repetition, pools);
+            }
+        }
+        Value::Annotated { expr, .. } => collect_pools(expr, pools),
+    }
+}
+
+/// The pool's die indices in the order `operator` sorts them by value; ties
+/// keep their roll order.
+fn sort_order(dice: &[DieResult], operator: SetOperator) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..dice.len()).collect();
+    order.sort_by(|&a, &b| {
+        let ordering = dice[a].value.total_cmp(&dice[b].value);
+        if operator == SetOperator::SortDescending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    order
+}
+
+fn collect_tagged(result: &EvalResult, tagged: &mut Vec<(String, f64)>) {
+    match &result.value {
+        Value::Literal(_) | Value::Range { .. } | Value::Call { .. } => {}
+        Value::Unary { operand, .. } => collect_tagged(operand, tagged),
+        Value::Binary { left, right, .. } => {
+            collect_tagged(left, tagged);
+            collect_tagged(right, tagged);
+        }
+        Value::Dice(roll) => {
+            for die in roll.dice.iter().filter(|die| die.kept) {
+                if let Some(entry) = &die.entry {
+                    collect_tagged(entry, tagged);
+                }
+            }
+        }
+        Value::Set(set) => {
+            for element in set.elements.iter().filter(|element| !element.dropped) {
+                collect_tagged(&element.value, tagged);
+            }
+        }
+        Value::Repeat { results } => {
+            for repetition in results {
+                collect_tagged(repetition, tagged);
+            }
+        }
+        Value::Annotated { expr, annotations } => {
+            for annotation in annotations.iter().filter(|a| !a.text.is_empty()) {
+                match tagged.iter_mut().find(|(text, _)| *text == annotation.text) {
+                    Some((_, total)) => *total += expr.total,
+                    None => tagged.push((annotation.text.clone(), expr.total)),
+                }
+            }
+            collect_tagged(expr, tagged);
+        }
+    }
+}
+
+/// Whether a die showing `value` satisfies a value selector against `target`;
+/// `None` for the positional `h`/`l`/`m` selectors.
+pub(crate) fn selector_matches(
+    kind: SelectorKind,
+    value: f64,
+    target: f64,
+    epsilon: f64,
+) -> Option<bool> {
+    Some(match kind {
+        SelectorKind::Highest | SelectorKind::Lowest | SelectorKind::Middle => return None,
+        SelectorKind::GreaterThan => value > target,
+        SelectorKind::GreaterThanOrEqual => value >= target,
+        SelectorKind::LessThan => value < target,
+        SelectorKind::LessThanOrEqual => value <= target,
+        SelectorKind::EqualTo | SelectorKind::Literal => (value - target).abs() <= epsilon,
+        SelectorKind::NotEqual => (value - target).abs() > epsilon,
+    })
+}
+
+/// Scales a percent die's 0-9 draw to its face, 0 to 90 in tens. A draw
+/// outside 0-9 means the die was sampled from the wrong range and is an error
+/// rather than an odd face.
+fn percent_value(draw: f64) -> Result<f64> {
+    if draw.fract() != 0.0 || !(0.0..=9.0).contains(&draw) {
+        return Err(Eval(format!(
+            "Percent die drew {}, outside its 0-9 range",
+            draw
+        )));
+    }
+    Ok(draw * 10.0)
+}
+
+/// The union of each selector's picks among `len` dice, in ascending order.
+/// Selectors overlap, so the union is kept as a mask over the pool.
+fn union_indices(len: usize, per_selector: &[Vec<usize>]) -> Vec<usize> {
+    let mut selected = vec![false; len];
+    for &idx in per_selector.iter().flatten() {
+        selected[idx] = true;
+    }
+    mask_indices(&selected)
+}
+
+/// The middle `count` of `sorted`, indices ordered lowest value first. The
+/// rest split evenly between the ends, the extra one coming off the top.
+fn middle_of(sorted: Vec<usize>, count: usize) -> Vec<usize> {
+    let count = count.min(sorted.len());
+    let low = (sorted.len() - count) / 2;
+    sorted[low..low + count].to_vec()
+}
+
+/// The indices set in a selection mask, in ascending order.
+fn mask_indices(mask: &[bool]) -> Vec<usize> {
+    mask.iter()
+        .enumerate()
+        .filter_map(|(idx, &selected)| selected.then_some(idx))
+        .collect()
+}
+
+/// Computes `aggregate` over `pool`; an empty pool aggregates to zero. The
+/// median of an even-sized pool is the mean of its two middle values.
+fn aggregate_of(aggregate: Aggregate, pool: &[f64]) -> f64 {
+    if pool.is_empty() {
+        return 0.0;
+    }
+    match aggregate {
+        Aggregate::Sum => pool.iter().sum(),
+        Aggregate::Min => pool.iter().copied().fold(f64::INFINITY, f64::min),
+        Aggregate::Max => pool.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        Aggregate::Mean => pool.iter().sum::<f64>() / pool.len() as f64,
+        Aggregate::Median => {
+            let mut sorted = pool.to_vec();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let mid = sorted.len() / 2;
+            if sorted.len().is_multiple_of(2) {
+                (sorted[mid - 1] + sorted[mid]) / 2.0
+            } else {
+                sorted[mid]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    fn selector(kind: SelectorKind, target: f64) -> Selector {
+        Selector {
+            kind,
+            target: Box::new(Node::Literal(target)),
+        }
+    }
+
+    #[test]
+    fn selection_takes_the_sorted_union_of_its_selectors() {
+        use SelectorKind::*;
+
+        // Faces 5, 3, 2, 6, 4 at indices 0 to 4.
+        let cases = [
+            (vec![], vec![]),
+            (
+                vec![selector(Highest, 2.0), selector(Lowest, 1.0)],
+                vec![0, 2, 3],
+            ),
+            (
+                vec![selector(GreaterThan, 4.0), selector(Highest, 1.0)],
+                vec![0, 3],
+            ),
+            (vec![selector(Lowest, 1.0), selector(Lowest, 1.0)], vec![2]),
+            (
+                vec![selector(EqualTo, 3.0), selector(NotEqual, 3.0)],
+                vec![0, 1, 2, 3, 4],
+            ),
+            (
+                vec![selector(Literal, 6.0), selector(LessThanOrEqual, 3.0)],
+                vec![1, 2, 3],
+            ),
+            (
+                vec![
+                    selector(Middle, 1.0),
+                    selector(GreaterThanOrEqual, 6.0),
+                    selector(LessThan, 0.0),
+                ],
+                vec![3, 4],
+            ),
+        ];
+        let faces = [5.0, 3.0, 2.0, 6.0, 4.0];
+        let dice: Vec<_> = faces
+            .iter()
+            .map(|&value| DieResult::new(value, DieOrigin::Original))
+            .collect();
+        let elements: Vec<_> = faces
+            .iter()
+            .map(|&total| SetElement {
+                value: EvalResult {
+                    total,
+                    value: Value::Literal(total),
+                },
+                kept: true,
+                dropped: false,
+            })
+            .collect();
+        let mut evaluator = Evaluator::new(StdRng::seed_from_u64(0), EvalConfig::default());
+        for (selectors, expected) in cases {
+            assert_eq!(
+                evaluator.select_dice(&dice, &selectors).unwrap(),
+                expected,
+                "dice {:?}",
+                selectors
+            );
+            assert_eq!(
+                evaluator
+                    .select_set_elements(&elements, &selectors, false)
+                    .unwrap(),
+                expected,
+                "set {:?}",
+                selectors
+            );
+        }
+    }
+
+    #[test]
+    fn set_selection_skips_elements_already_dropped() {
+        use SelectorKind::*;
+
+        let elements: Vec<_> = [5.0, 3.0, 2.0, 6.0, 4.0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &total)| SetElement {
+                value: EvalResult {
+                    total,
+                    value: Value::Literal(total),
+                },
+                kept: idx != 3,
+                dropped: false,
+            })
+            .collect();
+        let selectors = [selector(Highest, 1.0), selector(LessThan, 4.0)];
+        let mut evaluator = Evaluator::new(StdRng::seed_from_u64(0), EvalConfig::default());
+        assert_eq!(
+            evaluator
+                .select_set_elements(&elements, &selectors, true)
+                .unwrap(),
+            [0, 1, 2]
+        );
+        assert_eq!(
+            evaluator
+                .select_set_elements(&elements, &selectors, false)
+                .unwrap(),
+            [1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn percent_die_rejects_draws_outside_its_range() {
+        let mut evaluator = Evaluator::new(
+            crate::testing::SequenceRng::new([u32::MAX]),
+            EvalConfig::default(),
+        );
+        let wide = Uniform::new_inclusive(0, 10).unwrap();
+        assert!(evaluator.roll_die(&wide, &DiceSize::Percent).is_err());
+        let faces: Vec<f64> = (0..=9)
+            .map(|draw| percent_value(draw as f64).unwrap())
+            .collect();
+        assert_eq!(
+            faces,
+            [0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0]
+        );
+        assert!(percent_value(4.5).is_err());
+        assert!(percent_value(-1.0).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::ast::{Aggregate, BinaryOperator, Node, SelectorKind, SetOperator};
+use crate::eval::FUNCTIONS;
+use crate::lexer::IDENTIFIERS;
+use crate::parser::Parser;
+
+/// A machine-readable summary of the syntax the parser accepts, for editors
+/// and other tools that want to highlight or complete dice expressions.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GrammarSpec {
+    pub set_operators: Vec<SetOperatorSpec>,
+    pub selectors: Vec<SelectorSpec>,
+    pub binary_operators: Vec<BinaryOperatorSpec>,
+    /// Function names callable as `name(args)`.
+    pub functions: Vec<&'static str>,
+    /// Aggregates usable as selector targets, as in `k>mean`.
+    pub aggregates: Vec<&'static str>,
+}
+
+/// A dice or set operation such as `k` or `rr`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SetOperatorSpec {
+    pub name: String,
+    /// Every spelling the parser accepts, canonical first.
+    pub lexemes: Vec<&'static str>,
+    /// Whether the operator is followed by selectors, as `k` is in `4d6kh3`.
+    pub takes_selectors: bool,
+}
+
+/// A selector prefix such as `h` or `>=`; the literal selector has an empty
+/// lexeme.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SelectorSpec {
+    pub name: String,
+    pub lexeme: &'static str,
+}
+
+/// An infix operator; higher precedence binds tighter.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BinaryOperatorSpec {
+    pub name: String,
+    pub lexeme: &'static str,
+    pub precedence: u8,
+}
+
+/// Lists the operators, selectors and functions the parser accepts. Each
+/// lexeme is checked against the parser itself, so operators that exist in the
+/// syntax tree but cannot be written yet are left out.
+pub fn grammar_spec() -> GrammarSpec {
+    let set_operators = SetOperator::ALL
+        .into_iter()
+        .filter_map(|operator| {
+            let takes_selectors = Parser::operator_takes_selectors(operator);
+            let selector = if takes_selectors { "1" } else { "" };
+            let lexemes: Vec<_> = operator
+                .lexemes()
+                .iter()
+                .copied()
+                .filter(|lexeme| {
+                    matches!(
+                        parse_probe(&format!("4d6{}{}", lexeme, selector)),
+                        Some(Node::DiceWithOps { operations, .. })
+                            if operations.len() == 1 && operations[0].operator == operator
+                    )
+                })
+                .collect();
+            (!lexemes.is_empty()).then(|| SetOperatorSpec {
+                name: format!("{:?}", operator),
+                lexemes,
+                takes_selectors,
+            })
+        })
+        .collect();
+
+    let selectors = SelectorKind::ALL
+        .into_iter()
+        .filter(|&kind| {
+            matches!(
+                parse_probe(&format!("4d6k{}3", kind.symbol())),
+                Some(Node::DiceWithOps { operations, .. })
+                    if operations.len() == 1
+                        && operations[0].selectors.len() == 1
+                        && operations[0].selectors[0].kind == kind
+            )
+        })
+        .map(|kind| SelectorSpec {
+            name: format!("{:?}", kind),
+            lexeme: kind.symbol(),
+        })
+        .collect();
+
+    let binary_operators = BinaryOperator::ALL
+        .into_iter()
+        .filter(|&operator| {
+            matches!(
+                parse_probe(&format!("1 {} 2", operator.symbol())),
+                Some(Node::Binary { operator: parsed, .. }) if parsed == operator
+            )
+        })
+        .map(|operator| BinaryOperatorSpec {
+            name: format!("{:?}", operator),
+            lexeme: operator.symbol(),
+            precedence: operator.precedence(),
+        })
+        .collect();
+
+    let aggregates: Vec<_> = IDENTIFIERS
+        .iter()
+        .copied()
+        .filter(|name| Aggregate::from_name(name).is_some())
+        .collect();
+    // `min` and `max` are both functions and aggregates.
+    let functions = IDENTIFIERS
+        .iter()
+        .copied()
+        .filter(|name| FUNCTIONS.contains(name))
+        .collect();
+
+    GrammarSpec {
+        set_operators,
+        selectors,
+        binary_operators,
+        functions,
+        aggregates,
+    }
+}
+
+fn parse_probe(input: &str) -> Option<Node> {
+    Parser::new(input)
+        .and_then(|mut parser| parser.parse())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_rollable_set_operator_has_a_lexeme() {
+        // Written out rather than probed, so an operator the parser stops
+        // accepting drops out of the spec and fails here.
+        let expected = [
+            ("Keep", vec!["k"], true),
+            ("Drop", vec!["p"], true),
+            ("Reroll", vec!["rr"], true),
+            ("RerollOnce", vec!["ro"], true),
+            ("RerollAdd", vec!["ra"], true),
+            ("RerollBetter", vec!["rb"], true),
+            ("RerollWorse", vec!["rw"], true),
+            ("Explode", vec!["e", "!"], true),
+            ("StepExplode", vec!["stepe"], false),
+            ("BonusExplode", vec!["eb"], true),
+            ("ExplodeCompound", vec!["!!"], true),
+            ("ExplodePenetrate", vec!["!!p"], true),
+            ("Penetrate", vec!["!p"], true),
+            ("Minimum", vec!["mi"], true),
+            ("Maximum", vec!["ma"], true),
+            ("Spread", vec!["spread"], false),
+            ("Straight", vec!["straight"], false),
+            ("WeightedKeep", vec!["wkeep"], false),
+            ("CountSuccess", vec!["cs"], true),
+            ("CountFailure", vec!["cf"], true),
+            ("SortAscending", vec!["sa"], false),
+            ("SortDescending", vec!["sd"], false),
+        ];
+        let spec = grammar_spec();
+        let listed: Vec<_> = spec
+            .set_operators
+            .iter()
+            .map(|entry| {
+                (
+                    entry.name.as_str(),
+                    entry.lexemes.clone(),
+                    entry.takes_selectors,
+                )
+            })
+            .collect();
+        assert_eq!(listed, expected);
+    }
+
+    #[test]
+    fn explode_lists_both_spellings() {
+        let spec = grammar_spec();
+        let explode = spec
+            .set_operators
+            .iter()
+            .find(|entry| entry.name == "Explode")
+            .unwrap();
+        assert_eq!(explode.lexemes, ["e", "!"]);
+        assert!(explode.takes_selectors);
+    }
+
+    #[test]
+    fn binary_operators_carry_precedence() {
+        let spec = grammar_spec();
+        assert_eq!(spec.binary_operators.len(), BinaryOperator::ALL.len());
+        let precedence = |lexeme| {
+            spec.binary_operators
+                .iter()
+                .find(|entry| entry.lexeme == lexeme)
+                .unwrap()
+                .precedence
+        };
+        assert!(precedence("*") > precedence("+"));
+        assert!(precedence("+") > precedence(">="));
+    }
+
+    #[test]
+    fn selectors_functions_and_aggregates() {
+        let spec = grammar_spec();
+        assert_eq!(spec.selectors.len(), SelectorKind::ALL.len());
+        assert!(spec.functions.contains(&"until"));
+        assert!(spec.functions.contains(&"max"));
+        assert!(!spec.functions.contains(&"mean"));
+        assert_eq!(
+            spec.aggregates,
+            ["median", "mean", "sum", "min", "max", "avg"]
+        );
+        for name in IDENTIFIERS {
+            assert!(
+                spec.functions.contains(name) || spec.aggregates.contains(name),
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -1,9 +1,55 @@
+use std::ops::Range;
+
 use crate::{error::RollatoriumError, token::Token};
 
+/// Named words recognised by the lexer, longest first so a prefix never
+/// shadows a longer name.
+pub(crate) const IDENTIFIERS: &[&str] = &[
+    "reroll_pool",
+    "median",
+    "margin",
+    "chance",
+    "clamp",
+    "until",
+    "floor",
+    "round",
+    "needs",
+    "gate",
+    "mean",
+    "ceil",
+    "sum",
+    "min",
+    "max",
+    "abs",
+    "avg",
+    "if",
+];
+
+#[derive(Clone)]
 pub(crate) struct Lexer {
     chars: Vec<char>,
     pos: usize,
+    /// Where the token being lexed starts.
+    start: usize,
     annotation_mode: bool,
+    /// Set while an annotation's closing `]` is still to come.
+    annotation_open: bool,
+    /// Set right after a `d`, where `[` opens a table die instead of an
+    /// annotation and a letter starts a custom die name. Whitespace after the
+    /// `d` ends this, so `1d6 [fire]` and `1d6[fire]` are both annotations
+    /// while only `d[1, 2, 3]` is a table die.
+    after_dice: bool,
+    /// Set after a `d`, until the die's size has been lexed.
+    size_next: bool,
+    /// Set right after a die's size, where `adv` and `dis` are the advantage
+    /// shorthands rather than, as in `2dis`, a custom die named `is`.
+    /// Whitespace keeps this, so `1d20 dis` works as well as `1d20dis`.
+    after_size: bool,
+    paren_depth: usize,
+    /// The paren depths at which a die's size opened, as in `d(20)`, so the
+    /// matching `)` can close the size.
+    size_parens: Vec<usize>,
+    table_depth: usize,
 }
 
 impl Lexer {
@@ -11,7 +57,30 @@ impl Lexer {
         Lexer {
             chars: input.chars().collect(),
             pos: 0,
+            start: 0,
             annotation_mode: false,
+            annotation_open: false,
+            after_dice: false,
+            size_next: false,
+            after_size: false,
+            paren_depth: 0,
+            size_parens: Vec::new(),
+            table_depth: 0,
+        }
+    }
+
+    /// The character range of the token last returned by `next_token`.
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.pos
+    }
+
+    /// A lexer error covering the text from the current token's start, or the
+    /// character there when nothing has been consumed yet.
+    fn error(&self, message: impl Into<String>) -> RollatoriumError {
+        let end = self.pos.max(self.start + 1).min(self.chars.len());
+        RollatoriumError::Lexer {
+            message: message.into(),
+            span: self.start..end,
         }
     }
 
@@ -63,10 +132,15 @@ impl Lexer {
             if c.is_ascii_digit() {
                 seen_digit = true;
                 self.advance();
+            } else if c == '.' && self.peek_offset(1) == '.' {
+                // The start of a range such as `1..20`, not a decimal point.
+                break;
             } else if c == '.' && !seen_dot {
                 let next = self.peek_offset(1);
                 if !next.is_ascii_digit() {
-                    return Err(RollatoriumError::Lexer(format!(
+                    // Take in the `.` so the error covers it.
+                    self.advance();
+                    return Err(self.error(format!(
                         "Invalid decimal literal starting at position {}",
                         start
                     )));
@@ -79,26 +153,76 @@ impl Lexer {
         }
 
         if !seen_digit {
-            return Err(RollatoriumError::Lexer(format!(
+            return Err(self.error(format!(
                 "Number literal missing digits at position {}",
                 start
             )));
         }
 
+        // An exponent as in `1.5e2`, unless the number is a die size or
+        // selector target, as in `1d6e6` or `rr<2e6`, where `e` explodes.
+        let in_dice = start > 0 && {
+            let before = self.chars[start - 1];
+            before.is_ascii_alphanumeric() || matches!(before, '<' | '>' | '=' | '!')
+        };
+        let exponent_digits = match self.peek_offset(1) {
+            '+' | '-' => 2,
+            _ => 1,
+        };
+        if !in_dice
+            && matches!(self.peek(), 'e' | 'E')
+            && self.peek_offset(exponent_digits).is_ascii_digit()
+        {
+            self.advance_by(exponent_digits);
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+        }
+
         let num_str: String = self.chars[start..self.pos].iter().collect();
         match num_str.parse::<f64>() {
-            Ok(value) => Ok(Token::Number(value)),
-            Err(_) => Err(RollatoriumError::Lexer(format!(
-                "Failed to parse number literal '{}'",
-                num_str
-            ))),
+            Ok(value) if value.is_finite() => Ok(Token::Number(value)),
+            Ok(_) => Err(self.error(format!("Number literal '{}' is out of range", num_str))),
+            Err(_) => Err(self.error(format!("Failed to parse number literal '{}'", num_str))),
         }
     }
 
     pub fn next_token(&mut self) -> crate::Result<Token> {
+        let size_next = std::mem::take(&mut self.size_next);
+        let token = self.lex_token()?;
+        self.after_size = match token {
+            Token::Dice => {
+                self.size_next = true;
+                false
+            }
+            Token::Number(_) | Token::DiceName(_) | Token::DiceFudge => size_next,
+            Token::DicePercent | Token::DicePercentile | Token::TableEnd => true,
+            Token::LParen => {
+                if size_next {
+                    self.size_parens.push(self.paren_depth);
+                }
+                self.paren_depth += 1;
+                false
+            }
+            Token::RParen => {
+                self.paren_depth = self.paren_depth.saturating_sub(1);
+                let closes_size = self.size_parens.last() == Some(&self.paren_depth);
+                if closes_size {
+                    self.size_parens.pop();
+                }
+                closes_size
+            }
+            _ => false,
+        };
+        Ok(token)
+    }
+
+    fn lex_token(&mut self) -> crate::Result<Token> {
+        let after_dice = std::mem::take(&mut self.after_dice) && !self.peek().is_whitespace();
         if !self.annotation_mode {
             self.skip_ws();
         }
+        self.start = self.pos;
         if self.is_at_end() {
             return Ok(Token::Eof);
         }
@@ -114,9 +238,7 @@ impl Lexer {
             }
 
             if self.is_at_end() {
-                return Err(RollatoriumError::Lexer(
-                    "Unterminated annotation; missing closing ']'".into(),
-                ));
+                return Err(self.error("Unterminated annotation; missing closing ']'"));
             }
 
             let text: String = self.chars[start..self.pos].iter().collect();
@@ -124,10 +246,30 @@ impl Lexer {
             return Ok(Token::AnnotationText(text.trim().to_string()));
         }
 
+        if after_dice && self.peek().is_ascii_alphabetic() {
+            let start = self.pos;
+            while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+                self.advance();
+            }
+            let name: String = self.chars[start..self.pos].iter().collect();
+            if name == "F" {
+                return Ok(Token::DiceFudge);
+            }
+            return Ok(Token::DiceName(name));
+        }
+
         if self.starts_with("//") {
             self.advance_by(2);
             return Ok(Token::DoubleSlash);
         }
+        if self.starts_with("/^") {
+            self.advance_by(2);
+            return Ok(Token::SlashCaret);
+        }
+        if self.starts_with("..") {
+            self.advance_by(2);
+            return Ok(Token::DotDot);
+        }
         if self.starts_with("==") {
             self.advance_by(2);
             return Ok(Token::EqualEqual);
@@ -136,6 +278,19 @@ impl Lexer {
             self.advance_by(2);
             return Ok(Token::NotEqual);
         }
+        if self.starts_with("!!p") {
+            self.advance_by(3);
+            return Ok(Token::ExplodePenetrate);
+        }
+        if self.starts_with("!p") {
+            self.advance_by(2);
+            return Ok(Token::Penetrate);
+        }
+        // `1d6!!=3` explodes on anything but a 3, as it did before `!!`.
+        if self.starts_with("!!") && (!self.starts_with("!!=") || self.starts_with("!!==")) {
+            self.advance_by(2);
+            return Ok(Token::ExplodeCompound);
+        }
         if self.starts_with(">=") {
             self.advance_by(2);
             return Ok(Token::GreaterEqual);
@@ -144,6 +299,58 @@ impl Lexer {
             self.advance_by(2);
             return Ok(Token::LessEqual);
         }
+        if let Some(ident) = IDENTIFIERS.iter().find(|ident| self.starts_with(ident)) {
+            self.advance_by(ident.len());
+            return Ok(Token::Ident(ident.to_string()));
+        }
+        if self.starts_with("spread") {
+            self.advance_by(6);
+            return Ok(Token::Spread);
+        }
+        if self.starts_with("straight") {
+            self.advance_by(8);
+            return Ok(Token::Straight);
+        }
+        if self.starts_with("wkeep") {
+            self.advance_by(5);
+            return Ok(Token::WeightedKeep);
+        }
+        if self.starts_with("stepe") {
+            self.advance_by(5);
+            return Ok(Token::StepExplode);
+        }
+        if self.starts_with("eacc") {
+            self.advance_by(4);
+            return Ok(Token::ElvenAccuracy);
+        }
+        if self.after_size && self.starts_with("adv") {
+            self.advance_by(3);
+            return Ok(Token::Advantage);
+        }
+        if self.after_size && self.starts_with("dis") {
+            self.advance_by(3);
+            return Ok(Token::Disadvantage);
+        }
+        if self.starts_with("eb") {
+            self.advance_by(2);
+            return Ok(Token::BonusExplode);
+        }
+        if self.starts_with("cs") {
+            self.advance_by(2);
+            return Ok(Token::CountSuccess);
+        }
+        if self.starts_with("cf") {
+            self.advance_by(2);
+            return Ok(Token::CountFailure);
+        }
+        if self.starts_with("sa") {
+            self.advance_by(2);
+            return Ok(Token::SortAscending);
+        }
+        if self.starts_with("sd") {
+            self.advance_by(2);
+            return Ok(Token::SortDescending);
+        }
         if self.starts_with("rr") {
             self.advance_by(2);
             return Ok(Token::Reroll);
@@ -156,6 +363,14 @@ impl Lexer {
             self.advance_by(2);
             return Ok(Token::RerollAdd);
         }
+        if self.starts_with("rb") {
+            self.advance_by(2);
+            return Ok(Token::RerollBetter);
+        }
+        if self.starts_with("rw") {
+            self.advance_by(2);
+            return Ok(Token::RerollWorse);
+        }
         if self.starts_with("mi") {
             self.advance_by(2);
             return Ok(Token::Min);
@@ -165,6 +380,10 @@ impl Lexer {
             return Ok(Token::Max);
         }
 
+        if self.starts_with("d%%") {
+            self.advance_by(3);
+            return Ok(Token::DicePercentile);
+        }
         if self.starts_with("d%") {
             self.advance_by(2);
             return Ok(Token::DicePercent);
@@ -180,10 +399,18 @@ impl Lexer {
                 self.advance();
                 Ok(Token::Minus)
             }
+            '*' if self.starts_with("**") => {
+                self.advance_by(2);
+                Ok(Token::Caret)
+            }
             '*' => {
                 self.advance();
                 Ok(Token::Star)
             }
+            '^' => {
+                self.advance();
+                Ok(Token::Caret)
+            }
             '/' => {
                 self.advance();
                 Ok(Token::Slash)
@@ -216,21 +443,38 @@ impl Lexer {
                 self.advance();
                 Ok(Token::SetEnd)
             }
+            '[' if after_dice => {
+                self.advance();
+                self.table_depth += 1;
+                Ok(Token::TableStart)
+            }
             '[' => {
                 self.advance();
                 self.annotation_mode = true;
+                self.annotation_open = true;
                 Ok(Token::AnnotationStart)
             }
+            ']' if !self.annotation_open && self.table_depth > 0 => {
+                self.advance();
+                self.table_depth -= 1;
+                Ok(Token::TableEnd)
+            }
             ']' => {
                 self.advance();
+                self.annotation_open = false;
                 Ok(Token::AnnotationEnd)
             }
             ',' => {
                 self.advance();
                 Ok(Token::Comma)
             }
+            'x' | '×' => {
+                self.advance();
+                Ok(Token::Repeat)
+            }
             'd' => {
                 self.advance();
+                self.after_dice = true;
                 Ok(Token::Dice)
             }
             'k' => {
@@ -257,14 +501,22 @@ impl Lexer {
                 self.advance();
                 Ok(Token::SelectorLow)
             }
-            '=' => Err(RollatoriumError::Lexer(format!(
+            'm' => {
+                self.advance();
+                Ok(Token::SelectorMiddle)
+            }
+            '=' => Err(self.error(format!(
                 "Unexpected '=' at position {}. Did you mean '=='?",
                 self.pos
             ))),
             c if c.is_ascii_digit() || (c == '.' && self.peek_offset(1).is_ascii_digit()) => {
                 self.number()
             }
-            _ => Err(RollatoriumError::Lexer(format!(
+            '.' => Err(self.error(format!(
+                "Expected a digit after '.' at position {}",
+                self.pos
+            ))),
+            _ => Err(self.error(format!(
                 "Unexpected character '{}' at position {}",
                 c, self.pos
             ))),
@@ -1,22 +1,52 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![forbid(unsafe_code)]
 
+mod analysis;
 mod ast;
+#[cfg(feature = "binary")]
+mod binary;
+mod canonical;
+mod debug;
+mod display;
 mod error;
 mod eval;
+mod grammar;
 mod lexer;
 mod parser;
+mod registry;
+mod stats;
+pub mod testing;
 mod token;
+mod validate;
 
+use std::collections::HashMap;
+
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+pub use crate::analysis::{
+    ExprProfile, bounds, clamp_mean_shift, expected_successes, luck, profile,
+};
 use crate::ast::Node;
+pub use crate::ast::{BinaryOperator, keep_highest, keep_lowest};
+pub use crate::debug::render_tree;
+pub use crate::error::RollatoriumError;
 pub use crate::eval::{
-    DiceRoll, DieAdjustment, DieOrigin, DieResult, EvalConfig, EvalResult, SetElement, SetRoll,
-    Value,
+    CritType, DetailedRoll, Diagnostic, DiceRoll, DieAdjustment, DieOrigin, DieResult, EvalConfig,
+    EvalMetrics, EvalResult, RollOutcome, RoundingRule, SelectorMatches, SetElement, SetRoll,
+    SuccessTally, Value,
 };
 pub use crate::eval::{
-    evaluate as eval_expression, evaluate_with_config as eval_with_config,
+    evaluate as eval_expression, evaluate_outcome as eval_outcome,
+    evaluate_with_boxed_rng as eval_with_boxed_rng, evaluate_with_config as eval_with_config,
+    evaluate_with_metrics as eval_with_metrics, evaluate_with_namespace as eval_with_namespace,
     evaluate_with_rng as eval_with_rng,
 };
+pub use crate::grammar::{
+    BinaryOperatorSpec, GrammarSpec, SelectorSpec, SetOperatorSpec, grammar_spec,
+};
+pub use crate::registry::DiceRegistry;
+pub use crate::stats::{expected_value, sample_distribution};
+pub use crate::validate::validate;
 
 pub type Result<T> = std::result::Result<T, error::RollatoriumError>;
 
@@ -34,6 +64,138 @@ pub fn roll<I: AsRef<str>>(input: &I) -> Result<EvalResult> {
     eval(&ast)
 }
 
+/// Rolls `input` `count` times, as for each participant in an initiative
+/// table. The input is parsed once and each roll is independent; the first
+/// failing roll's error is returned.
+pub fn roll_many<I: AsRef<str>>(input: &I, count: usize) -> Result<Vec<EvalResult>> {
+    let ast = parse(input)?;
+    (0..count).map(|_| eval(&ast)).collect()
+}
+
+/// Rolls `input` lazily, one result per item, drawing every roll from
+/// `rng`, so a huge batch such as `roll_iter(&expr, rng)?.take(1_000_000)`
+/// never holds more than one result at a time. The input is parsed up front
+/// and a parse error returned straight away; each roll's own error is
+/// returned as its item.
+pub fn roll_iter<'a, I: AsRef<str>>(
+    input: &'a I,
+    mut rng: impl RngCore + 'a,
+) -> Result<impl Iterator<Item = Result<EvalResult>> + 'a> {
+    let ast = parse(input)?;
+    Ok(std::iter::repeat_with(move || {
+        eval_with_rng(&ast, EvalConfig::default(), &mut rng)
+    }))
+}
+
+/// Rolls `input` with an RNG seeded from `seed`, so the same seed and input
+/// always roll the same result.
+pub fn roll_with_seed<I: AsRef<str>>(input: &I, seed: u64) -> Result<EvalResult> {
+    let ast = parse(input)?;
+    eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(seed))
+}
+
+/// Rolls `input` under `config` and returns the result alongside the config used.
+pub fn roll_outcome<I: AsRef<str>>(input: &I, config: EvalConfig) -> Result<RollOutcome> {
+    let ast = parse(input)?;
+    eval_outcome(&ast, config, rand::rng())
+}
+
+/// Rolls `input` under `config` and flattens the result into a [`DetailedRoll`].
+pub fn roll_detailed<I: AsRef<str>>(input: &I, config: EvalConfig) -> Result<DetailedRoll> {
+    let ast = parse(input)?;
+    let result = eval_with_config(&ast, config)?;
+    Ok(DetailedRoll::from_result(&result))
+}
+
+/// Rolls many expressions from one RNG, keeping a running total of them all,
+/// as for initiative tracking or a score built up over turns.
+pub struct RollSession<R: RngCore = StdRng> {
+    rng: R,
+    config: EvalConfig,
+    total: f64,
+}
+
+impl RollSession {
+    /// A session whose rolls are reproducible from `seed`.
+    pub fn seeded(seed: u64, config: EvalConfig) -> Self {
+        Self::new(StdRng::seed_from_u64(seed), config)
+    }
+}
+
+impl<R: RngCore> RollSession<R> {
+    pub fn new(rng: R, config: EvalConfig) -> Self {
+        Self {
+            rng,
+            config,
+            total: 0.0,
+        }
+    }
+
+    /// Rolls `input`, adds its total to the running total and returns it. A
+    /// failed roll leaves the running total unchanged.
+    pub fn roll<I: AsRef<str>>(&mut self, input: &I) -> Result<f64> {
+        let ast = parse(input)?;
+        let total = eval_with_rng(&ast, self.config.clone(), &mut self.rng)?.total;
+        self.total += total;
+        Ok(total)
+    }
+
+    /// The sum of every roll since the session started or was last reset.
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    /// Clears the running total; the RNG carries on where it was.
+    pub fn reset(&mut self) {
+        self.total = 0.0;
+    }
+}
+
+/// Parses each distinct expression once and rolls the cached tree on every
+/// later call, for applications rolling a fixed set of expressions often.
+#[derive(Debug, Default)]
+pub struct ExprCache {
+    exprs: HashMap<String, Node>,
+}
+
+impl ExprCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rolls `input` under `config`, parsing it only the first time it is seen.
+    /// Expressions that fail to parse are not cached.
+    pub fn roll<I: AsRef<str>>(
+        &mut self,
+        input: &I,
+        config: EvalConfig,
+        rng: &mut impl RngCore,
+    ) -> Result<EvalResult> {
+        let input = input.as_ref();
+        let ast = match self.exprs.get(input) {
+            Some(ast) => ast,
+            None => {
+                let ast = parse(&input)?;
+                self.exprs.entry(input.to_string()).or_insert(ast)
+            }
+        };
+        eval_with_rng(ast, config, rng)
+    }
+
+    /// The number of distinct expressions cached so far.
+    pub fn len(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exprs.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.exprs.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::parser::Parser;
@@ -1,20 +1,34 @@
+use std::ops::Range;
+
 use crate::{
     Result,
     ast::{
-        Annotation, BinaryOperator, DiceSize, Node, Selector, SelectorKind, SetOperation,
-        SetOperator, UnaryOperator,
+        Aggregate, Annotation, BinaryOperator, DiceSize, Node, Selector, SelectorKind,
+        SetOperation, SetOperator, UnaryOperator, keep_highest, keep_lowest,
     },
     error::RollatoriumError,
     lexer::Lexer,
     token::Token,
 };
 
+/// How deep parentheses, calls, signs, powers and selector targets may nest
+/// before parsing fails rather than overflowing the stack. Each of them counts
+/// once per level, as does each operator in a chain such as `1 + 2 + 3`,
+/// which builds a tree one level deeper per link. A parenthesised level runs
+/// through every precedence level of the parser, and every other level adds
+/// a frame to evaluation, so this stays inside a 2 MiB thread stack even in
+/// debug builds.
+const MAX_DEPTH: usize = 128;
+
 // ---------- Parser ----------
 pub(crate) struct Parser<'a> {
     lexer: Lexer,
     cur_token: Token,
+    /// The character range of `cur_token`, for pointing errors at it.
+    cur_span: Range<usize>,
     input: &'a str,
     selector_depth: usize,
+    depth: usize,
 }
 
 impl<'a> Parser<'a> {
@@ -22,38 +36,129 @@ impl<'a> Parser<'a> {
         let mut lexer = Lexer::new(input);
         let first = lexer.next_token()?;
         Ok(Parser {
+            cur_span: lexer.span(),
             lexer,
             cur_token: first,
             input,
             selector_depth: 0,
+            depth: 0,
         })
     }
 
     fn eat(&mut self, expected: Token) -> Result<()> {
         if std::mem::discriminant(&self.cur_token) == std::mem::discriminant(&expected) {
             self.cur_token = self.lexer.next_token()?;
+            self.cur_span = self.lexer.span();
             Ok(())
         } else {
-            Err(RollatoriumError::Parser(format!(
+            Err(self.error(format!(
                 "Expected {:?}, got {:?} in '{}'",
                 expected, self.cur_token, self.input
             )))
         }
     }
 
+    /// A parser error pointing at the current token.
+    fn error(&self, message: impl Into<String>) -> RollatoriumError {
+        RollatoriumError::Parser {
+            message: message.into(),
+            span: self.cur_span.clone(),
+        }
+    }
+
     pub fn parse(&mut self) -> Result<Node> {
-        let expr = self.parse_comparison()?;
-        if self.cur_token != Token::Eof {
-            return Err(RollatoriumError::Parser(format!(
-                "Unexpected trailing input: {:?}",
-                self.cur_token
+        let expr = self.parse_repeat()?;
+        if self.cur_token == Token::DotDot {
+            return Err(self.error(format!(
+                "Ranges can only be used as function arguments in '{}'",
+                self.input
             )));
         }
+        if matches!(
+            self.cur_token,
+            Token::Number(_)
+                | Token::Dice
+                | Token::DicePercent
+                | Token::DicePercentile
+                | Token::LParen
+                | Token::Ident(_)
+        ) {
+            return Err(self.error(format!(
+                "Missing operator between expressions before {:?} in '{}'",
+                self.cur_token, self.input
+            )));
+        }
+        if self.cur_token != Token::Eof {
+            return Err(self.error(format!("Unexpected trailing input: {:?}", self.cur_token)));
+        }
         Ok(expr)
     }
 
+    /// Parses an expression with an optional repeat count, either before it
+    /// (`3x2d6`) or after it (`2d6 x3`). Repetition binds loosest of all, so
+    /// `1d6 + 2 x3` rolls the whole `1d6 + 2` three times.
+    fn parse_repeat(&mut self) -> Result<Node> {
+        let prefix = self.repeat_prefix()?;
+        let expr = self.parse_comparison()?;
+        self.finish_repeat(prefix, expr)
+    }
+
+    /// The `3x` count in front of a repeated expression, if there is one.
+    fn repeat_prefix(&mut self) -> Result<Option<u32>> {
+        match self.cur_token {
+            Token::Number(value) if self.lexer.clone().next_token()? == Token::Repeat => {
+                self.eat(Token::Number(value))?;
+                self.eat(Token::Repeat)?;
+                Ok(Some(self.repeat_count(value)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads any ` x3` count after `expr` and wraps it in a repeat. Kept apart
+    /// from `parse_repeat`, which sits on the stack once per parenthesised
+    /// level.
+    fn finish_repeat(&mut self, prefix: Option<u32>, expr: Node) -> Result<Node> {
+        let postfix = if self.cur_token == Token::Repeat {
+            self.eat(Token::Repeat)?;
+            let Token::Number(value) = self.cur_token else {
+                return Err(self.error(format!(
+                    "Expected a repeat count after 'x', found {:?} in '{}'",
+                    self.cur_token, self.input
+                )));
+            };
+            self.eat(Token::Number(value))?;
+            Some(self.repeat_count(value)?)
+        } else {
+            None
+        };
+        match (prefix, postfix) {
+            (Some(_), Some(_)) => {
+                Err(self.error(format!("Repeat count given twice in '{}'", self.input)))
+            }
+            (Some(count), None) | (None, Some(count)) => Ok(Node::Repeat {
+                count,
+                expr: Box::new(expr),
+            }),
+            (None, None) => Ok(expr),
+        }
+    }
+
+    fn repeat_count(&self, value: f64) -> Result<u32> {
+        if value < 1.0 || value.fract() != 0.0 || value > u32::MAX as f64 {
+            return Err(self.error(format!(
+                "Repeat count must be a positive integer, found {} in '{}'",
+                value, self.input
+            )));
+        }
+        Ok(value as u32)
+    }
+
     fn parse_comparison(&mut self) -> Result<Node> {
         let mut node = self.parse_additive()?;
+        // Each link sits a level above the last, so the chain keeps its depth
+        // until it ends.
+        let depth = self.depth;
         loop {
             let operator = match self.cur_token {
                 Token::EqualEqual => Some(BinaryOperator::Equal),
@@ -68,6 +173,7 @@ impl<'a> Parser<'a> {
             let Some(operator) = operator else { break };
             let token = self.cur_token.clone();
             self.eat(token)?;
+            self.deepen()?;
             let right = self.parse_additive()?;
             node = Node::Binary {
                 operator,
@@ -75,11 +181,13 @@ impl<'a> Parser<'a> {
                 right: Box::new(right),
             };
         }
+        self.depth = depth;
         Ok(node)
     }
 
     fn parse_additive(&mut self) -> Result<Node> {
         let mut node = self.parse_multiplicative()?;
+        let depth = self.depth;
         loop {
             let operator = match self.cur_token {
                 Token::Plus => Some(BinaryOperator::Add),
@@ -90,6 +198,7 @@ impl<'a> Parser<'a> {
             let Some(operator) = operator else { break };
             let token = self.cur_token.clone();
             self.eat(token)?;
+            self.deepen()?;
             let right = self.parse_multiplicative()?;
             node = Node::Binary {
                 operator,
@@ -97,16 +206,19 @@ impl<'a> Parser<'a> {
                 right: Box::new(right),
             };
         }
+        self.depth = depth;
         Ok(node)
     }
 
     fn parse_multiplicative(&mut self) -> Result<Node> {
-        let mut node = self.parse_unary()?;
+        let mut node = self.parse_power()?;
+        let depth = self.depth;
         loop {
             let operator = match self.cur_token {
                 Token::Star => Some(BinaryOperator::Multiply),
                 Token::Slash => Some(BinaryOperator::Divide),
                 Token::DoubleSlash => Some(BinaryOperator::IntDivide),
+                Token::SlashCaret => Some(BinaryOperator::CeilDivide),
                 Token::Percent => Some(BinaryOperator::Modulo),
                 _ => None,
             };
@@ -114,34 +226,45 @@ impl<'a> Parser<'a> {
             let Some(operator) = operator else { break };
             let token = self.cur_token.clone();
             self.eat(token)?;
-            let right = self.parse_unary()?;
+            self.deepen()?;
+            let right = self.parse_power()?;
             node = Node::Binary {
                 operator,
                 left: Box::new(node),
                 right: Box::new(right),
             };
         }
+        self.depth = depth;
         Ok(node)
     }
 
-    fn parse_unary(&mut self) -> Result<Node> {
-        match self.cur_token {
-            Token::Plus => {
-                self.eat(Token::Plus)?;
-                Ok(Node::Unary {
-                    operator: UnaryOperator::Plus,
-                    operand: Box::new(self.parse_unary()?),
-                })
-            }
-            Token::Minus => {
-                self.eat(Token::Minus)?;
-                Ok(Node::Unary {
-                    operator: UnaryOperator::Minus,
-                    operand: Box::new(self.parse_unary()?),
-                })
-            }
-            _ => self.parse_postfix(),
+    /// `base ^ exponent`, recursing on the right so `2^3^2` is `2^(3^2)`.
+    fn parse_power(&mut self) -> Result<Node> {
+        let base = self.parse_unary()?;
+        if self.cur_token != Token::Caret {
+            return Ok(base);
         }
+        self.eat(Token::Caret)?;
+        let exponent = self.nested(Self::parse_power)?;
+        Ok(Node::Binary {
+            operator: BinaryOperator::Power,
+            left: Box::new(base),
+            right: Box::new(exponent),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Node> {
+        let operator = match self.cur_token {
+            Token::Plus => UnaryOperator::Plus,
+            Token::Minus => UnaryOperator::Minus,
+            _ => return self.parse_postfix(),
+        };
+        let token = self.cur_token.clone();
+        self.eat(token)?;
+        Ok(Node::Unary {
+            operator,
+            operand: Box::new(self.nested(Self::parse_unary)?),
+        })
     }
 
     fn parse_postfix(&mut self) -> Result<Node> {
@@ -155,24 +278,75 @@ impl<'a> Parser<'a> {
             Token::Number(value) => {
                 let literal = Node::Literal(*value);
                 self.eat(Token::Number(*value))?;
-                if matches!(self.cur_token, Token::Dice | Token::DicePercent) {
+                if matches!(
+                    self.cur_token,
+                    Token::Dice | Token::DicePercent | Token::DicePercentile
+                ) {
                     self.parse_dice_literal(Some(literal))
                 } else {
                     Ok(literal)
                 }
             }
-            Token::Dice | Token::DicePercent => self.parse_dice_literal(None),
-            Token::LParen => self.parse_parenthesized_or_set(),
-            Token::AnnotationStart => Err(RollatoriumError::Parser(
-                "Unexpected annotation start; annotations must follow an expression".into(),
-            )),
-            token => Err(RollatoriumError::Parser(format!(
-                "Unexpected token {:?} in '{}'",
-                token, self.input
-            ))),
+            Token::Dice | Token::DicePercent | Token::DicePercentile => {
+                self.parse_dice_literal(None)
+            }
+            Token::LParen => self.nested(Self::parse_parenthesized_or_set),
+            Token::Ident(name) => {
+                let name = name.clone();
+                self.eat(Token::Ident(name.clone()))?;
+                if self.cur_token != Token::LParen {
+                    return Err(
+                        self.error(format!("Expected '(' after '{}' in '{}'", name, self.input))
+                    );
+                }
+                self.nested(|parser| parser.parse_call(name))
+            }
+            Token::AnnotationStart => {
+                Err(self
+                    .error("Unexpected annotation start; annotations must follow an expression"))
+            }
+            token => Err(self.error(format!("Unexpected token {:?} in '{}'", token, self.input))),
         }
     }
 
+    fn parse_call(&mut self, name: String) -> Result<Node> {
+        self.eat(Token::LParen)?;
+        let mut args = Vec::new();
+        if self.cur_token != Token::RParen {
+            args.push(self.parse_argument()?);
+            while self.cur_token == Token::Comma {
+                self.eat(Token::Comma)?;
+                args.push(self.parse_argument()?);
+            }
+        }
+        self.eat(Token::RParen)?;
+        Ok(Node::Call { name, args })
+    }
+
+    fn parse_argument(&mut self) -> Result<Node> {
+        if matches!(
+            self.cur_token,
+            Token::Greater
+                | Token::GreaterEqual
+                | Token::Less
+                | Token::LessEqual
+                | Token::EqualEqual
+                | Token::NotEqual
+        ) {
+            return Ok(Node::Condition(self.parse_selector()?));
+        }
+        let node = self.parse_comparison()?;
+        if self.cur_token != Token::DotDot {
+            return Ok(node);
+        }
+        self.eat(Token::DotDot)?;
+        let high = self.parse_comparison()?;
+        Ok(Node::Range {
+            low: Box::new(node),
+            high: Box::new(high),
+        })
+    }
+
     fn parse_parenthesized_or_set(&mut self) -> Result<Node> {
         self.eat(Token::LParen)?;
         if self.cur_token == Token::RParen {
@@ -183,7 +357,13 @@ impl<'a> Parser<'a> {
             });
         }
 
-        let first = self.parse_comparison()?;
+        let first = self.parse_repeat()?;
+        self.finish_parenthesized_or_set(first)
+    }
+
+    /// Everything after the first element of a parenthesised expression: the
+    /// rest of a set, the closing paren and what it turns into.
+    fn finish_parenthesized_or_set(&mut self, first: Node) -> Result<Node> {
         let mut elements = vec![first];
         let mut is_set = false;
 
@@ -193,7 +373,7 @@ impl<'a> Parser<'a> {
             if self.cur_token == Token::RParen {
                 break;
             }
-            elements.push(self.parse_comparison()?);
+            elements.push(self.parse_repeat()?);
         }
 
         self.eat(Token::RParen)?;
@@ -205,9 +385,21 @@ impl<'a> Parser<'a> {
                 | Token::Reroll
                 | Token::RerollOnce
                 | Token::RerollAdd
+                | Token::RerollBetter
+                | Token::RerollWorse
                 | Token::Explode
+                | Token::ExplodeCompound
+                | Token::Penetrate
+                | Token::ExplodePenetrate
                 | Token::Min
                 | Token::Max
+                | Token::CountSuccess
+                | Token::Spread
+                | Token::Straight
+                | Token::WeightedKeep
+                | Token::CountFailure
+                | Token::SortAscending
+                | Token::SortDescending
         );
 
         let first_is_dice = matches!(
@@ -215,6 +407,17 @@ impl<'a> Parser<'a> {
             Some(Node::Dice { .. }) | Some(Node::DiceWithOps { .. })
         );
 
+        if !is_set
+            && matches!(
+                self.cur_token,
+                Token::Dice | Token::DicePercent | Token::DicePercentile
+            )
+        {
+            // A parenthesized quantity, as in `(1+1)d6`.
+            let quantity = elements.pop().expect("at least one element present");
+            return self.parse_dice_literal(Some(quantity));
+        }
+
         if is_set || (set_ops_follow && !first_is_dice) {
             Ok(Node::Set {
                 elements,
@@ -238,8 +441,35 @@ impl<'a> Parser<'a> {
                         self.eat(Token::Number(value))?;
                         Node::Literal(value)
                     }
+                    Token::LParen => {
+                        self.eat(Token::LParen)?;
+                        let faces = self.outside_selector_context(Self::parse_comparison)?;
+                        self.eat(Token::RParen)?;
+                        faces
+                    }
+                    Token::TableStart => {
+                        return Ok(Node::Dice {
+                            num: quantity.map(Box::new),
+                            size: DiceSize::ExprTable(self.parse_table()?),
+                        });
+                    }
+                    Token::DiceFudge => {
+                        self.eat(Token::DiceFudge)?;
+                        return Ok(Node::Dice {
+                            num: quantity.map(Box::new),
+                            size: DiceSize::Fudge,
+                        });
+                    }
+                    Token::DiceName(name) => {
+                        let name = name.clone();
+                        self.eat(Token::DiceName(name.clone()))?;
+                        return Ok(Node::Dice {
+                            num: quantity.map(Box::new),
+                            size: DiceSize::Custom(name),
+                        });
+                    }
                     token => {
-                        return Err(RollatoriumError::Parser(format!(
+                        return Err(self.error(format!(
                             "Expected die size after 'd', found {:?} in '{}'",
                             token, self.input
                         )));
@@ -257,61 +487,64 @@ impl<'a> Parser<'a> {
                     size: DiceSize::Percent,
                 })
             }
-            _ => Err(RollatoriumError::Parser(format!(
-                "Invalid dice expression in '{}'",
+            Token::DicePercentile => {
+                self.eat(Token::DicePercentile)?;
+                Ok(Node::Dice {
+                    num: quantity.map(Box::new),
+                    size: DiceSize::Percentile,
+                })
+            }
+            _ => Err(self.error(format!("Invalid dice expression in '{}'", self.input))),
+        }
+    }
+
+    fn parse_table(&mut self) -> Result<Vec<Node>> {
+        self.eat(Token::TableStart)?;
+        if self.cur_token == Token::TableEnd {
+            return Err(self.error(format!(
+                "Table dice need at least one entry in '{}'",
                 self.input
-            ))),
+            )));
         }
+        let mut entries = vec![self.outside_selector_context(Self::parse_comparison)?];
+        while self.cur_token == Token::Comma {
+            self.eat(Token::Comma)?;
+            entries.push(self.outside_selector_context(Self::parse_comparison)?);
+        }
+        self.eat(Token::TableEnd)?;
+        Ok(entries)
     }
 
     fn parse_modifiers(&mut self, node: Node) -> Result<Node> {
         if self.selector_depth > 0 {
             return Ok(node);
         }
+        let node = match self.cur_token {
+            Token::ElvenAccuracy => {
+                self.eat(Token::ElvenAccuracy)?;
+                self.single_die_pool(node, "eacc", 3, SelectorKind::Highest)?
+            }
+            Token::Advantage => {
+                self.eat(Token::Advantage)?;
+                self.single_die_pool(node, "adv", 2, SelectorKind::Highest)?
+            }
+            Token::Disadvantage => {
+                self.eat(Token::Disadvantage)?;
+                self.single_die_pool(node, "dis", 2, SelectorKind::Lowest)?
+            }
+            _ => node,
+        };
 
         let mut operations = Vec::new();
         loop {
-            let (operator, symbol) = match self.cur_token {
-                Token::Keep => {
-                    self.eat(Token::Keep)?;
-                    (SetOperator::Keep, "k")
-                }
-                Token::Drop => {
-                    self.eat(Token::Drop)?;
-                    (SetOperator::Drop, "p")
-                }
-                Token::Reroll => {
-                    self.eat(Token::Reroll)?;
-                    (SetOperator::Reroll, "rr")
-                }
-                Token::RerollOnce => {
-                    self.eat(Token::RerollOnce)?;
-                    (SetOperator::RerollOnce, "ro")
-                }
-                Token::RerollAdd => {
-                    self.eat(Token::RerollAdd)?;
-                    (SetOperator::RerollAdd, "ra")
-                }
-                Token::Explode => {
-                    self.eat(Token::Explode)?;
-                    (SetOperator::Explode, "!")
-                }
-                Token::Min => {
-                    self.eat(Token::Min)?;
-                    (SetOperator::Minimum, "mi")
-                }
-                Token::Max => {
-                    self.eat(Token::Max)?;
-                    (SetOperator::Maximum, "ma")
-                }
-                _ => break,
+            if self.cur_token == Token::LParen && self.operation_group_follows()? {
+                self.parse_operation_group(&mut operations)?;
+                continue;
+            }
+            let Some(operation) = self.parse_operation()? else {
+                break;
             };
-
-            let selectors = self.parse_selector_list(symbol, operator)?;
-            operations.push(SetOperation {
-                operator,
-                selectors,
-            });
+            operations.push(operation);
         }
 
         if operations.is_empty() {
@@ -323,10 +556,13 @@ impl<'a> Parser<'a> {
                 dice: Box::new(Node::Dice { num, size }),
                 operations,
             }),
+            // The pool came back whole from parentheses, so its keeps are
+            // finished and the next operation starts afresh.
             Node::DiceWithOps {
                 dice,
                 operations: mut existing,
             } => {
+                operations[0].after_parens = true;
                 existing.extend(operations);
                 Ok(Node::DiceWithOps {
                     dice,
@@ -343,20 +579,230 @@ impl<'a> Parser<'a> {
                     operations: existing,
                 })
             }
-            other => Err(RollatoriumError::Parser(format!(
+            other => Err(self.error(format!(
                 "Set operations can only be applied to dice or sets, not {:?}",
                 other
             ))),
         }
     }
 
+    /// Desugars a single die followed by `keyword` into `count` of that die
+    /// keeping the highest or lowest one: `d20eacc` (elven accuracy) is
+    /// `3d20kh1`, `d20adv` is `2d20kh1` and `d20dis` is `2d20kl1`.
+    fn single_die_pool(
+        &self,
+        node: Node,
+        keyword: &str,
+        count: u32,
+        kind: SelectorKind,
+    ) -> Result<Node> {
+        if let Node::Dice {
+            num,
+            size: DiceSize::Value(sides),
+        } = &node
+            && num.as_deref().is_none_or(|num| *num == Node::Literal(1.0))
+            && let Node::Literal(sides) = **sides
+            && sides >= 1.0
+            && sides.fract() == 0.0
+            && sides <= u32::MAX as f64
+        {
+            return Ok(if kind == SelectorKind::Highest {
+                keep_highest(count, sides as u32, 1)
+            } else {
+                keep_lowest(count, sides as u32, 1)
+            });
+        }
+        Err(self.error(format!(
+            "'{}' applies to a single numbered die such as d20{}, not {} in '{}'",
+            keyword, keyword, node, self.input
+        )))
+    }
+
+    /// Parses one dice/set operation and its selectors, or returns `None` when
+    /// the current token doesn't start an operation.
+    fn parse_operation(&mut self) -> Result<Option<SetOperation>> {
+        let (operator, symbol) = match self.cur_token {
+            Token::Keep => {
+                self.eat(Token::Keep)?;
+                (SetOperator::Keep, "k")
+            }
+            Token::Drop => {
+                self.eat(Token::Drop)?;
+                (SetOperator::Drop, "p")
+            }
+            Token::Reroll => {
+                self.eat(Token::Reroll)?;
+                (SetOperator::Reroll, "rr")
+            }
+            Token::RerollOnce => {
+                self.eat(Token::RerollOnce)?;
+                (SetOperator::RerollOnce, "ro")
+            }
+            Token::RerollAdd => {
+                self.eat(Token::RerollAdd)?;
+                (SetOperator::RerollAdd, "ra")
+            }
+            Token::RerollBetter => {
+                self.eat(Token::RerollBetter)?;
+                (SetOperator::RerollBetter, "rb")
+            }
+            Token::RerollWorse => {
+                self.eat(Token::RerollWorse)?;
+                (SetOperator::RerollWorse, "rw")
+            }
+            Token::Explode => {
+                self.eat(Token::Explode)?;
+                (SetOperator::Explode, "!")
+            }
+            Token::ExplodeCompound => {
+                self.eat(Token::ExplodeCompound)?;
+                (SetOperator::ExplodeCompound, "!!")
+            }
+            Token::Penetrate => {
+                self.eat(Token::Penetrate)?;
+                (SetOperator::Penetrate, "!p")
+            }
+            Token::ExplodePenetrate => {
+                self.eat(Token::ExplodePenetrate)?;
+                (SetOperator::ExplodePenetrate, "!!p")
+            }
+            Token::StepExplode => {
+                self.eat(Token::StepExplode)?;
+                (SetOperator::StepExplode, "stepe")
+            }
+            Token::BonusExplode => {
+                self.eat(Token::BonusExplode)?;
+                (SetOperator::BonusExplode, "eb")
+            }
+            Token::Min => {
+                self.eat(Token::Min)?;
+                (SetOperator::Minimum, "mi")
+            }
+            Token::Max => {
+                self.eat(Token::Max)?;
+                (SetOperator::Maximum, "ma")
+            }
+            Token::Spread => {
+                self.eat(Token::Spread)?;
+                (SetOperator::Spread, "spread")
+            }
+            Token::Straight => {
+                self.eat(Token::Straight)?;
+                (SetOperator::Straight, "straight")
+            }
+            Token::WeightedKeep => {
+                self.eat(Token::WeightedKeep)?;
+                (SetOperator::WeightedKeep, "wkeep")
+            }
+            Token::CountSuccess => {
+                self.eat(Token::CountSuccess)?;
+                (SetOperator::CountSuccess, "cs")
+            }
+            Token::CountFailure => {
+                self.eat(Token::CountFailure)?;
+                (SetOperator::CountFailure, "cf")
+            }
+            Token::SortAscending => {
+                self.eat(Token::SortAscending)?;
+                (SetOperator::SortAscending, "sa")
+            }
+            Token::SortDescending => {
+                self.eat(Token::SortDescending)?;
+                (SetOperator::SortDescending, "sd")
+            }
+            _ => return Ok(None),
+        };
+
+        // A bare `!!`, `!p` or `!!p` explodes on the die's highest face, and
+        // a bare `rb` or `rw` rerolls every die.
+        let selectors = if matches!(
+            operator,
+            SetOperator::ExplodeCompound
+                | SetOperator::Penetrate
+                | SetOperator::ExplodePenetrate
+                | SetOperator::RerollBetter
+                | SetOperator::RerollWorse
+        ) && !self.is_selector_start(&self.cur_token)
+        {
+            Vec::new()
+        } else if Self::operator_takes_selectors(operator) {
+            self.parse_selector_list(symbol, operator)?
+        } else {
+            Vec::new()
+        };
+        Ok(Some(SetOperation {
+            operator,
+            selectors,
+            after_parens: false,
+        }))
+    }
+
+    /// Whether the `(` at the current token opens an operation group such as
+    /// the one in `4d6(kh3 rr<2)` rather than an expression.
+    fn operation_group_follows(&self) -> Result<bool> {
+        let next = self.lexer.clone().next_token()?;
+        Ok(matches!(
+            next,
+            Token::Keep
+                | Token::Drop
+                | Token::Reroll
+                | Token::RerollOnce
+                | Token::RerollAdd
+                | Token::RerollBetter
+                | Token::RerollWorse
+                | Token::Explode
+                | Token::ExplodeCompound
+                | Token::Penetrate
+                | Token::ExplodePenetrate
+                | Token::StepExplode
+                | Token::BonusExplode
+                | Token::Min
+                | Token::Max
+                | Token::CountSuccess
+                | Token::Spread
+                | Token::Straight
+                | Token::WeightedKeep
+                | Token::CountFailure
+                | Token::SortAscending
+                | Token::SortDescending
+        ))
+    }
+
+    fn parse_operation_group(&mut self, operations: &mut Vec<SetOperation>) -> Result<()> {
+        self.eat(Token::LParen)?;
+        while self.cur_token != Token::RParen {
+            match self.parse_operation()? {
+                Some(operation) => operations.push(operation),
+                None => {
+                    return Err(self.error(format!(
+                        "Expected a dice operation in group, found {:?} in '{}'",
+                        self.cur_token, self.input
+                    )));
+                }
+            }
+        }
+        self.eat(Token::RParen)
+    }
+
+    pub(crate) fn operator_takes_selectors(operator: SetOperator) -> bool {
+        !matches!(
+            operator,
+            SetOperator::StepExplode
+                | SetOperator::Spread
+                | SetOperator::Straight
+                | SetOperator::WeightedKeep
+                | SetOperator::SortAscending
+                | SetOperator::SortDescending
+        )
+    }
+
     fn parse_selector_list(
         &mut self,
         symbol: &str,
         operator: SetOperator,
     ) -> Result<Vec<Selector>> {
         if !self.is_selector_start(&self.cur_token) {
-            return Err(RollatoriumError::Parser(format!(
+            return Err(self.error(format!(
                 "Expected selector after '{}' in '{}'",
                 symbol, self.input
             )));
@@ -368,7 +814,7 @@ impl<'a> Parser<'a> {
         }
 
         if selectors.is_empty() {
-            return Err(RollatoriumError::Parser(format!(
+            return Err(self.error(format!(
                 "Operator '{:?}' must be followed by at least one selector",
                 operator
             )));
@@ -387,6 +833,10 @@ impl<'a> Parser<'a> {
                 self.eat(Token::SelectorLow)?;
                 (SelectorKind::Lowest, "l")
             }
+            Token::SelectorMiddle => {
+                self.eat(Token::SelectorMiddle)?;
+                (SelectorKind::Middle, "m")
+            }
             Token::Greater => {
                 self.eat(Token::Greater)?;
                 (SelectorKind::GreaterThan, ">")
@@ -420,7 +870,7 @@ impl<'a> Parser<'a> {
             } else {
                 prefix
             };
-            return Err(RollatoriumError::Parser(format!(
+            return Err(self.error(format!(
                 "Expected selector target after '{}' in '{}'",
                 label, self.input
             )));
@@ -438,6 +888,7 @@ impl<'a> Parser<'a> {
             token,
             Token::SelectorHigh
                 | Token::SelectorLow
+                | Token::SelectorMiddle
                 | Token::Greater
                 | Token::GreaterEqual
                 | Token::Less
@@ -450,6 +901,8 @@ impl<'a> Parser<'a> {
                 | Token::LParen
                 | Token::Dice
                 | Token::DicePercent
+                | Token::DicePercentile
+                | Token::Ident(_)
         )
     }
 
@@ -462,9 +915,24 @@ impl<'a> Parser<'a> {
                 | Token::LParen
                 | Token::Dice
                 | Token::DicePercent
+                | Token::DicePercentile
+                | Token::Ident(_)
         )
     }
 
+    /// Parses a bracketed sub-expression (a die size or table entry) a level
+    /// deeper, with full modifier support even when the die itself sits in a
+    /// selector.
+    fn outside_selector_context<F>(&mut self, f: F) -> Result<Node>
+    where
+        F: FnOnce(&mut Self) -> Result<Node>,
+    {
+        let depth = std::mem::take(&mut self.selector_depth);
+        let result = self.nested(f);
+        self.selector_depth = depth;
+        result
+    }
+
     fn with_selector_context<F>(&mut self, f: F) -> Result<Node>
     where
         F: FnOnce(&mut Self) -> Result<Node>,
@@ -475,45 +943,82 @@ impl<'a> Parser<'a> {
         result
     }
 
+    /// Runs `f` a level deeper.
+    fn nested<F>(&mut self, f: F) -> Result<Node>
+    where
+        F: FnOnce(&mut Self) -> Result<Node>,
+    {
+        self.deepen()?;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Goes a level deeper, failing once nesting passes `MAX_DEPTH`.
+    fn deepen(&mut self) -> Result<()> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(format!(
+                "Expression too deeply nested, past {} levels",
+                MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
     fn parse_selector_value_inner(&mut self) -> Result<Node> {
         match &self.cur_token {
             Token::Plus => {
                 self.eat(Token::Plus)?;
                 Ok(Node::Unary {
                     operator: UnaryOperator::Plus,
-                    operand: Box::new(self.parse_selector_value_inner()?),
+                    operand: Box::new(self.nested(Self::parse_selector_value_inner)?),
                 })
             }
             Token::Minus => {
                 self.eat(Token::Minus)?;
                 Ok(Node::Unary {
                     operator: UnaryOperator::Minus,
-                    operand: Box::new(self.parse_selector_value_inner()?),
+                    operand: Box::new(self.nested(Self::parse_selector_value_inner)?),
                 })
             }
             Token::Number(value) => {
                 let literal = Node::Literal(*value);
                 self.eat(Token::Number(*value))?;
-                if matches!(self.cur_token, Token::Dice | Token::DicePercent) {
+                if matches!(
+                    self.cur_token,
+                    Token::Dice | Token::DicePercent | Token::DicePercentile
+                ) {
                     self.parse_dice_literal(Some(literal))
                 } else {
                     Ok(literal)
                 }
             }
-            Token::Dice | Token::DicePercent => self.parse_dice_literal(None),
+            Token::Dice | Token::DicePercent | Token::DicePercentile => {
+                self.parse_dice_literal(None)
+            }
+            Token::Ident(name) => {
+                let Some(aggregate) = Aggregate::from_name(name) else {
+                    return Err(self.error(format!(
+                        "Unknown selector target '{}' in '{}'",
+                        name, self.input
+                    )));
+                };
+                let token = self.cur_token.clone();
+                self.eat(token)?;
+                Ok(Node::Aggregate(aggregate))
+            }
             Token::LParen => {
                 self.eat(Token::LParen)?;
                 let expr = if self.cur_token == Token::RParen {
-                    return Err(RollatoriumError::Parser(
-                        "Empty parentheses are not valid selector targets".into(),
-                    ));
+                    return Err(self.error("Empty parentheses are not valid selector targets"));
                 } else {
-                    self.parse_comparison()?
+                    self.nested(Self::parse_comparison)?
                 };
                 self.eat(Token::RParen)?;
                 Ok(expr)
             }
-            token => Err(RollatoriumError::Parser(format!(
+            token => Err(self.error(format!(
                 "Invalid selector target starting with {:?} in '{}'",
                 token, self.input
             ))),
@@ -537,7 +1042,7 @@ impl<'a> Parser<'a> {
                     text
                 }
                 token => {
-                    return Err(RollatoriumError::Parser(format!(
+                    return Err(self.error(format!(
                         "Expected annotation text, found {:?} in '{}'",
                         token, self.input
                     )));
@@ -547,12 +1052,10 @@ impl<'a> Parser<'a> {
             if let Token::AnnotationEnd = self.cur_token {
                 self.eat(Token::AnnotationEnd)?;
             } else {
-                return Err(RollatoriumError::Parser(
-                    "Unterminated annotation; expected closing ']'".into(),
-                ));
+                return Err(self.error("Unterminated annotation; expected closing ']'"));
             }
 
-            annotations.push(Annotation { text });
+            annotations.push(Annotation::from_text(&text));
         }
 
         if annotations.is_empty() {
@@ -582,7 +1085,7 @@ impl<'a> Parser<'a> {
 mod tests {
     use super::Parser;
     use crate::ast::{
-        Annotation, DiceSize, Node, Selector, SelectorKind, SetOperation, SetOperator,
+        Aggregate, Annotation, DiceSize, Node, Selector, SelectorKind, SetOperation, SetOperator,
         UnaryOperator,
     };
 
@@ -656,6 +1159,7 @@ mod tests {
                         kind: SelectorKind::Highest,
                         target: Box::new(Node::Literal(3.0)),
                     }],
+                    after_parens: false,
                 }],
             }
         );
@@ -673,11 +1177,45 @@ mod tests {
                 }),
                 annotations: vec![Annotation {
                     text: "fire".to_string(),
+                    repeat: 1,
                 }],
             }
         );
     }
 
+    #[test]
+    fn parses_repeated_annotation() {
+        let node = parse("3d6 [x3 fire] [x2]");
+        let Node::Annotated { annotations, .. } = node else {
+            panic!("expected annotated node");
+        };
+        assert_eq!(
+            annotations,
+            vec![
+                Annotation {
+                    text: "fire".to_string(),
+                    repeat: 3,
+                },
+                Annotation {
+                    text: String::new(),
+                    repeat: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_plain_annotation_text() {
+        for text in ["x-ray", "x0 fire", "xylophone", "fire x3"] {
+            let node = parse(&format!("1d6 [{}]", text));
+            let Node::Annotated { annotations, .. } = node else {
+                panic!("expected annotated node");
+            };
+            assert_eq!(annotations[0].text, text);
+            assert_eq!(annotations[0].repeat, 1);
+        }
+    }
+
     #[test]
     fn parses_unary_in_selector() {
         let node = parse("d6k-1");
@@ -697,8 +1235,64 @@ mod tests {
                             operand: Box::new(Node::Literal(1.0)),
                         }),
                     }],
+                    after_parens: false,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_aggregate_selector_target() {
+        let node = parse("(1, 2, 3)k>mean");
+        assert_eq!(
+            node,
+            Node::Set {
+                elements: vec![Node::Literal(1.0), Node::Literal(2.0), Node::Literal(3.0)],
+                operations: vec![SetOperation {
+                    operator: SetOperator::Keep,
+                    selectors: vec![Selector {
+                        kind: SelectorKind::GreaterThan,
+                        target: Box::new(Node::Aggregate(Aggregate::Mean)),
+                    }],
+                    after_parens: false,
                 }],
             }
         );
     }
+
+    #[test]
+    fn parses_operation_groups() {
+        assert_eq!(parse("4d6(kh3)"), parse("4d6kh3"));
+        assert_eq!(parse("4d6(kh3 rr<2)"), parse("4d6kh3rr<2"));
+        assert_eq!(parse("4d6(kh3)(rr<2)e6"), parse("4d6kh3rr<2e6"));
+        assert_eq!(parse("(1, 2, 3)(kh2)"), parse("(1, 2, 3)kh2"));
+    }
+
+    #[test]
+    fn rejects_expressions_in_operation_groups() {
+        let mut parser = Parser::new("4d6(kh3 * 2)").unwrap();
+        assert!(parser.parse().is_err());
+        let mut parser = Parser::new("4d6 (2)").unwrap();
+        let err = parser.parse().unwrap_err().to_string();
+        assert!(err.contains("Missing operator"), "{}", err);
+    }
+
+    #[test]
+    fn desugars_advantage_and_disadvantage() {
+        assert_eq!(parse("d20adv"), parse("2d20kh1"));
+        assert_eq!(parse("1d20 adv"), parse("2d20kh1"));
+        assert_eq!(parse("d20dis"), parse("2d20kl1"));
+        assert_eq!(parse("1d20 dis"), parse("2d20kl1"));
+        assert_eq!(parse("1d20 dis + 1"), parse("1d20dis + 1"));
+        assert_eq!(parse("d(20)dis"), parse("2d(20)kl1"));
+        assert_eq!(parse("5 + 1d20dis"), parse("5 + 2d20kl1"));
+        // Away from a die's size, `dis` is still a custom die named `is`.
+        assert_eq!(
+            parse("2dis"),
+            Node::Dice {
+                num: Some(Box::new(Node::Literal(2.0))),
+                size: DiceSize::Custom("is".to_string()),
+            }
+        );
+    }
 }
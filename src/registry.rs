@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use crate::Result;
+use crate::error::RollatoriumError::Eval;
+
+/// Named custom dice such as `dRune`, looked up by the evaluator through
+/// [`EvalConfig::dice`](crate::EvalConfig::dice).
+#[derive(Debug, Clone, Default)]
+pub struct DiceRegistry {
+    dice: HashMap<String, Vec<f64>>,
+}
+
+impl DiceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `name` as a die that lands on each of `faces` with equal
+    /// chance, replacing any die already registered under it. Names start with
+    /// a letter and continue with letters, digits or underscores, so `2dRune`
+    /// rolls two of them; operations follow after a space, as in `4dRune kh2`.
+    /// `F` is taken by the Fudge die `dF`.
+    pub fn register(&mut self, name: impl Into<String>, faces: impl Into<Vec<f64>>) -> Result<()> {
+        let name = name.into();
+        let faces = faces.into();
+        if !is_dice_name(&name) {
+            return Err(Eval(format!("Invalid custom die name '{}'", name)));
+        }
+        if name == "F" {
+            return Err(Eval("'dF' is the built-in Fudge die".into()));
+        }
+        if faces.is_empty() {
+            return Err(Eval(format!(
+                "Custom die 'd{}' needs at least one face",
+                name
+            )));
+        }
+        if let Some(face) = faces.iter().find(|face| !face.is_finite()) {
+            return Err(Eval(format!(
+                "Custom die 'd{}' has a non-finite face {}",
+                name, face
+            )));
+        }
+        self.dice.insert(name, faces);
+        Ok(())
+    }
+
+    /// Registers `name` with weighted faces: each face is as likely as its
+    /// weight relative to the others, so `[(1.0, 2), (0.0, 1)]` shows 1 two
+    /// times in three.
+    pub fn register_weighted(
+        &mut self,
+        name: impl Into<String>,
+        faces: &[(f64, u32)],
+    ) -> Result<()> {
+        let faces: Vec<f64> = faces
+            .iter()
+            .flat_map(|&(face, weight)| std::iter::repeat_n(face, weight as usize))
+            .collect();
+        self.register(name, faces)
+    }
+
+    /// The face slots of the die registered as `name`, each equally likely.
+    pub fn faces(&self, name: &str) -> Option<&[f64]> {
+        self.dice.get(name).map(Vec::as_slice)
+    }
+}
+
+/// Whether `name` can follow a `d` as a custom die name.
+fn is_dice_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
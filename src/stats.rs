@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::ast::{BinaryOperator, DiceSize, Node, SelectorKind, SetOperator, UnaryOperator};
+use crate::error::RollatoriumError::Eval;
+use crate::eval::{EvalConfig, evaluate_with_rng};
+use crate::{Result, parse};
+
+/// Returns the mean total of `expr`, computed in closed form rather than by
+/// enumerating or sampling its rolls. Plain dice, keeping or dropping the
+/// highest or lowest few of a pool, sets without operations, repeats and
+/// `+`, `-` and `*` between independent rolls are supported; other operations,
+/// such as rerolls and explosions, are an error, as are keeps and drops on
+/// dice with more sides than the default `max_die_size`.
+pub fn expected_value(expr: &Node) -> Result<f64> {
+    let unsupported = || Eval(format!("expected_value has no closed form for '{}'", expr));
+    match expr {
+        Node::Literal(value) => Ok(*value),
+        Node::Unary { operator, operand } => {
+            let mean = expected_value(operand)?;
+            Ok(match operator {
+                UnaryOperator::Plus => mean,
+                UnaryOperator::Minus => -mean,
+            })
+        }
+        Node::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            let (left, right) = (expected_value(left)?, expected_value(right)?);
+            match operator {
+                BinaryOperator::Add => Ok(left + right),
+                BinaryOperator::Subtract => Ok(left - right),
+                // The operands roll independently, so their means multiply.
+                BinaryOperator::Multiply => Ok(left * right),
+                _ => Err(unsupported()),
+            }
+        }
+        Node::Dice { num, size } => {
+            let quantity = match num {
+                Some(num) => expected_value(num)?,
+                None => 1.0,
+            };
+            let faces = faces(size).ok_or_else(unsupported)?;
+            Ok(quantity * faces.mean())
+        }
+        Node::DiceWithOps { dice, operations } => {
+            let Node::Dice { num, size } = dice.as_ref() else {
+                return Err(unsupported());
+            };
+            let [operation] = operations.as_slice() else {
+                return Err(unsupported());
+            };
+            let [selector] = operation.selectors.as_slice() else {
+                return Err(unsupported());
+            };
+            let highest = match selector.kind {
+                SelectorKind::Highest => true,
+                SelectorKind::Lowest => false,
+                _ => return Err(unsupported()),
+            };
+            let quantity = match num.as_deref() {
+                Some(num) => constant_count(num).ok_or_else(unsupported)?,
+                None => 1,
+            };
+            let count = constant_count(&selector.target)
+                .ok_or_else(unsupported)?
+                .min(quantity);
+            let faces = faces(size).ok_or_else(unsupported)?;
+            // Order statistics step through every face, so dice with more
+            // sides than a roll allows are refused.
+            if faces.count > EvalConfig::default().max_die_size as usize {
+                return Err(unsupported());
+            }
+            // Dropping the highest few keeps the lowest of the rest.
+            let (highest, kept) = match operation.operator {
+                SetOperator::Keep => (highest, count),
+                SetOperator::Drop => (!highest, quantity - count),
+                _ => return Err(unsupported()),
+            };
+            let ranks = if highest {
+                quantity - kept + 1..=quantity
+            } else {
+                1..=kept
+            };
+            Ok(ranks
+                .map(|rank| order_statistic_mean(&faces, quantity, rank))
+                .sum())
+        }
+        Node::Set {
+            elements,
+            operations,
+        } if operations.is_empty() => elements.iter().map(expected_value).sum(),
+        Node::Repeat { count, expr } => Ok(*count as f64 * expected_value(expr)?),
+        Node::Annotated { expr, .. } => expected_value(expr),
+        _ => Err(unsupported()),
+    }
+}
+
+/// Rolls `input` `trials` times from an RNG seeded with `seed` and counts
+/// how often each total came up, rounded to the nearest integer. The input is
+/// parsed once and the first failing roll's error is returned.
+pub fn sample_distribution<I: AsRef<str>>(
+    input: &I,
+    trials: usize,
+    seed: u64,
+) -> Result<BTreeMap<i64, u64>> {
+    let ast = parse(input)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut counts = BTreeMap::new();
+    for _ in 0..trials {
+        let total = evaluate_with_rng(&ast, EvalConfig::default(), &mut rng)?.total;
+        *counts.entry(total.round() as i64).or_insert(0) += 1;
+    }
+    Ok(counts)
+}
+
+/// The equally likely faces of a die: `count` of them, `step` apart from
+/// `lowest` up.
+struct Faces {
+    lowest: f64,
+    step: f64,
+    count: usize,
+}
+
+impl Faces {
+    fn mean(&self) -> f64 {
+        self.lowest + self.step * (self.count - 1) as f64 / 2.0
+    }
+}
+
+/// The faces of a die of `size`, when they don't depend on a roll or a
+/// registry.
+fn faces(size: &DiceSize) -> Option<Faces> {
+    let (lowest, step, count) = match size {
+        DiceSize::Value(sides) => (1.0, 1.0, constant_count(sides)?),
+        DiceSize::Percent => (0.0, 10.0, 10),
+        DiceSize::Percentile => (1.0, 1.0, 100),
+        DiceSize::Fudge => (-1.0, 1.0, 3),
+        DiceSize::ExprTable(_) | DiceSize::Custom(_) => return None,
+    };
+    (count > 0).then_some(Faces {
+        lowest,
+        step,
+        count,
+    })
+}
+
+/// The value of `node` as a count, when it is a non-negative whole constant.
+fn constant_count(node: &Node) -> Option<usize> {
+    match node {
+        Node::Literal(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as usize),
+        Node::Annotated { expr, .. } => constant_count(expr),
+        _ => None,
+    }
+}
+
+/// The mean of the `rank`-th lowest of `quantity` dice over `faces`. The die
+/// at that rank shows at least the `face`-th face when at least
+/// `quantity - rank + 1` dice do, so its mean is the lowest face plus each
+/// step between faces weighted by the chance of reaching it.
+fn order_statistic_mean(faces: &Faces, quantity: usize, rank: usize) -> f64 {
+    let needed = quantity - rank + 1;
+    let sides = faces.count as f64;
+    let mut mean = faces.lowest;
+    for face in 1..faces.count {
+        let reach = (faces.count - face) as f64 / sides;
+        let at_least: f64 = (needed..=quantity)
+            .map(|dice| {
+                binomial(quantity, dice)
+                    * reach.powi(dice as i32)
+                    * (1.0 - reach).powi((quantity - dice) as i32)
+            })
+            .sum();
+        mean += faces.step * at_least;
+    }
+    mean
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |ways, step| {
+        ways * (n - step) as f64 / (step + 1) as f64
+    })
+}
@@ -0,0 +1,238 @@
+// ---------- Testing ----------
+
+#[cfg(feature = "test-util")]
+use rand::Rng;
+use rand::RngCore;
+use rand::rand_core::impls;
+
+/// A deterministic [`RngCore`] that replays a fixed sequence of `u32` words,
+/// for driving [`eval_with_rng`](crate::eval_with_rng) with known rolls.
+///
+/// Dice are sampled through [`rand::distr::Uniform`], so a raw word is not a
+/// face value. Use [`SequenceRng::from_faces`] or [`SequenceRng::face`] to
+/// encode the faces a die of a given size should show; a word encoded for one
+/// die size yields an unspecified face on a die of another size. `d%` dice
+/// roll a d10 whose face `n` reads as `(n - 1) * 10`; `d%%` dice roll a d100.
+#[derive(Debug, Clone)]
+pub struct SequenceRng {
+    values: Vec<u32>,
+    position: usize,
+    cycle: bool,
+}
+
+impl SequenceRng {
+    /// Replays `values`, starting over once the end is reached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is empty.
+    pub fn new(values: impl Into<Vec<u32>>) -> Self {
+        let values = values.into();
+        assert!(!values.is_empty(), "SequenceRng needs at least one value");
+        SequenceRng {
+            values,
+            position: 0,
+            cycle: true,
+        }
+    }
+
+    /// Replays `values` once; drawing past the end panics, which surfaces
+    /// evaluations that roll more dice than expected.
+    pub fn once(values: impl Into<Vec<u32>>) -> Self {
+        SequenceRng {
+            values: values.into(),
+            position: 0,
+            cycle: false,
+        }
+    }
+
+    /// Replays `faces` once, each encoded for a die with `sides` sides.
+    pub fn from_faces(sides: u32, faces: &[u32]) -> Self {
+        Self::once(
+            faces
+                .iter()
+                .map(|&face| Self::face(sides, face))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Encodes the word that makes a die with `sides` sides roll `face`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `1 <= face <= sides`.
+    pub fn face(sides: u32, face: u32) -> u32 {
+        assert!(
+            (1..=sides).contains(&face),
+            "face {} is out of range for a d{}",
+            face,
+            sides
+        );
+        // `Uniform` maps a word `x` to `(x * sides) >> 32`; the largest word
+        // mapping to `face - 1` also clears its rejection zone.
+        ((((face as u64) << 32) - 1) / sides as u64) as u32
+    }
+
+    /// Number of words left before the sequence wraps or runs out.
+    pub fn remaining(&self) -> usize {
+        self.values.len() - self.position
+    }
+}
+
+impl RngCore for SequenceRng {
+    fn next_u32(&mut self) -> u32 {
+        if self.position == self.values.len() {
+            assert!(self.cycle, "SequenceRng ran out of values");
+            self.position = 0;
+        }
+        let value = self.values[self.position];
+        self.position += 1;
+        value
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+/// Generates a random expression the parser accepts, for fuzzing code built
+/// on rollatorium. Follows the same grammar as the crate's own property
+/// tests: comparisons of sums of products of unary terms, where a term is
+/// dice with operations, a set with operations or a literal, optionally
+/// annotated. Terms nest in parentheses at most `max_depth` deep.
+#[cfg(feature = "test-util")]
+pub fn random_expression(rng: &mut impl RngCore, max_depth: usize) -> String {
+    comparison(rng, max_depth, false)
+}
+
+#[cfg(feature = "test-util")]
+fn comparison(rng: &mut impl RngCore, depth: usize, followed: bool) -> String {
+    let op = pick(rng, &["==", ">=", "<=", "!=", "<", ">"]);
+    join(rng, depth, followed, op, additive)
+}
+
+#[cfg(feature = "test-util")]
+fn additive(rng: &mut impl RngCore, depth: usize, followed: bool) -> String {
+    let op = pick(rng, &["+", "-"]);
+    join(rng, depth, followed, op, multiplicative)
+}
+
+#[cfg(feature = "test-util")]
+fn multiplicative(rng: &mut impl RngCore, depth: usize, followed: bool) -> String {
+    let op = pick(rng, &["*", "//", "/^", "/", "%"]);
+    join(rng, depth, followed, op, unary)
+}
+
+/// One to three `part`s joined by ` op `. `followed` is whether an operator
+/// comes after the whole chain.
+#[cfg(feature = "test-util")]
+fn join<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+    followed: bool,
+    op: &str,
+    part: fn(&mut R, usize, bool) -> String,
+) -> String {
+    let count = rng.random_range(1..=3);
+    (0..count)
+        .map(|i| part(rng, depth, followed || i + 1 < count))
+        .collect::<Vec<_>>()
+        .join(&format!(" {} ", op))
+}
+
+#[cfg(feature = "test-util")]
+fn unary(rng: &mut impl RngCore, depth: usize, followed: bool) -> String {
+    let prefix = pick(rng, &["+", "-", ""]);
+    let (term, has_operations) = match rng.random_range(0..4) {
+        0 if depth > 0 => (format!("({})", comparison(rng, depth - 1, false)), false),
+        0 | 1 => dice(rng),
+        2 => set(rng),
+        _ => (literal(rng), false),
+    };
+    // An operator right after a selector would be read as another selector,
+    // as `>` is in `4d6k3 > 2`.
+    let term = if followed && has_operations {
+        format!("({})", term)
+    } else {
+        term
+    };
+    let annotations: String = (0..rng.random_range(0..=2))
+        .map(|_| annotation(rng))
+        .collect();
+    format!("{}{}{}", prefix, term, annotations)
+}
+
+#[cfg(feature = "test-util")]
+fn literal(rng: &mut impl RngCore) -> String {
+    match rng.random_range(0..3) {
+        0 => rng.random_range(0..1000).to_string(),
+        1 => format!("{}.{}", rng.random_range(0..100), rng.random_range(0..1000)),
+        _ => format!(".{}", rng.random_range(1..1000)),
+    }
+}
+
+/// `[quantity]d[size]` followed by up to three operations, and whether it has
+/// any.
+#[cfg(feature = "test-util")]
+fn dice(rng: &mut impl RngCore) -> (String, bool) {
+    let quantity = if rng.random_bool(0.5) {
+        rng.random_range(1..=20).to_string()
+    } else {
+        String::new()
+    };
+    let size = if rng.random_bool(0.9) {
+        rng.random_range(1..=100).to_string()
+    } else {
+        "%".to_string()
+    };
+    let operations: String = (0..rng.random_range(0..=3))
+        .map(|_| {
+            let op = pick(rng, &["rr", "ro", "ra", "e", "mi", "ma", "k", "p"]);
+            format!("{}{}", op, selector(rng))
+        })
+        .collect();
+    let has_operations = !operations.is_empty();
+    (
+        format!("{}d{}{}", quantity, size, operations),
+        has_operations,
+    )
+}
+
+/// `(literal, ...)` followed by up to two keep or drop operations, and whether
+/// it has any.
+#[cfg(feature = "test-util")]
+fn set(rng: &mut impl RngCore) -> (String, bool) {
+    let elements: Vec<_> = (0..rng.random_range(0..=5)).map(|_| literal(rng)).collect();
+    let operations: String = (0..rng.random_range(0..=2))
+        .map(|_| format!("{}{}", pick(rng, &["k", "p"]), selector(rng)))
+        .collect();
+    let has_operations = !operations.is_empty();
+    (
+        format!("({}){}", elements.join(", "), operations),
+        has_operations,
+    )
+}
+
+#[cfg(feature = "test-util")]
+fn selector(rng: &mut impl RngCore) -> String {
+    let kind = pick(rng, &["", "h", "l", "m", "<", ">", "==", "!="]);
+    format!("{}{}", kind, rng.random_range(1..=10))
+}
+
+#[cfg(feature = "test-util")]
+fn annotation(rng: &mut impl RngCore) -> String {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+    let text: String = (0..rng.random_range(0..=20))
+        .map(|_| CHARS[rng.random_range(0..CHARS.len())] as char)
+        .collect();
+    format!("[{}]", text)
+}
+
+#[cfg(feature = "test-util")]
+fn pick<'a>(rng: &mut impl RngCore, options: &[&'a str]) -> &'a str {
+    options[rng.random_range(0..options.len())]
+}
@@ -6,7 +6,11 @@ pub(crate) enum Token {
     Star,
     Slash,
     DoubleSlash,
+    /// `/^`, dividing and rounding up.
+    SlashCaret,
     Percent,
+    /// `^` or `**`.
+    Caret,
     EqualEqual,
     NotEqual,
     GreaterEqual,
@@ -14,22 +18,63 @@ pub(crate) enum Token {
     Greater,
     Less,
     Dice,
+    /// A custom die name right after `d`, as in `2dRune`.
+    DiceName(String),
     DicePercent,
+    /// `d%%`, a percentile die from 1 to 100.
+    DicePercentile,
+    /// `F` right after `d`, as in `4dF`.
+    DiceFudge,
     Keep,
     Drop,
     Reroll,
     RerollOnce,
     RerollAdd,
+    /// `rb`, rerolling and keeping the better of the two rolls.
+    RerollBetter,
+    /// `rw`, rerolling and keeping the worse of the two rolls.
+    RerollWorse,
     Explode,
+    /// `!!`, adding each explosion onto the die that exploded.
+    ExplodeCompound,
+    /// `!p`, exploding into dice worth one less than they roll.
+    Penetrate,
+    /// `!!p`, adding each explosion, less one, onto the die that exploded.
+    ExplodePenetrate,
+    StepExplode,
+    Spread,
+    Straight,
+    WeightedKeep,
+    /// `sa`, listing a pool's dice lowest first.
+    SortAscending,
+    /// `sd`, listing a pool's dice highest first.
+    SortDescending,
+    BonusExplode,
+    /// `eacc`, turning a single die into three keeping the highest.
+    ElvenAccuracy,
+    /// `adv`, turning a single die into two keeping the highest.
+    Advantage,
+    /// `dis`, turning a single die into two keeping the lowest.
+    Disadvantage,
+    CountSuccess,
+    CountFailure,
     Min,
     Max,
     SelectorHigh,
     SelectorLow,
+    /// `m`, as in `5d6km1`.
+    SelectorMiddle,
     LParen,
     RParen,
     SetStart,
     SetEnd,
+    TableStart,
+    TableEnd,
     Comma,
+    /// `x` or `×`, repeating an expression as in `3x1d6` or `1d6 x3`.
+    Repeat,
+    DotDot,
+    Ident(String),
     AnnotationStart,
     AnnotationText(String),
     AnnotationEnd,
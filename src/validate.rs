@@ -0,0 +1,135 @@
+use crate::ast::{DiceSize, Node, SelectorKind, SetOperation, UnaryOperator};
+use crate::error::RollatoriumError::Eval;
+use crate::eval::{EPSILON, EvalConfig};
+use crate::{Result, parse};
+
+/// Checks that `input` parses and holds nothing bound to fail however the
+/// dice land, such as a literal die size past [`EvalConfig::max_die_size`]
+/// or a negative `kh` count, without rolling or touching an RNG. Returns the
+/// first problem found with the message evaluation would give; anything that
+/// depends on a roll is left for evaluation to report.
+pub fn validate<I: AsRef<str>>(input: &I) -> Result<()> {
+    check(&parse(input)?, &EvalConfig::default())
+}
+
+fn check(node: &Node, config: &EvalConfig) -> Result<()> {
+    match node {
+        Node::Literal(_) | Node::Aggregate(_) => Ok(()),
+        Node::Unary { operand, .. } => check(operand, config),
+        Node::Binary { left, right, .. }
+        | Node::Range {
+            low: left,
+            high: right,
+        } => {
+            check(left, config)?;
+            check(right, config)
+        }
+        Node::Dice { num, size } => check_dice(num.as_deref(), size, config),
+        Node::DiceWithOps { dice, operations } => {
+            check(dice, config)?;
+            check_operations(operations, config)
+        }
+        Node::Set {
+            elements,
+            operations,
+        } => {
+            for element in elements {
+                check(element, config)?;
+            }
+            check_operations(operations, config)
+        }
+        Node::Call { args, .. } => args.iter().try_for_each(|arg| check(arg, config)),
+        Node::Condition(selector) => check(&selector.target, config),
+        Node::Repeat { count, expr } => {
+            if *count as usize > config.max_dice {
+                return Err(Eval(format!(
+                    "Repeat count {} exceeds the maximum of {}",
+                    count, config.max_dice
+                )));
+            }
+            check(expr, config)
+        }
+        Node::Annotated { expr, .. } => check(expr, config),
+    }
+}
+
+fn check_dice(num: Option<&Node>, size: &DiceSize, config: &EvalConfig) -> Result<()> {
+    if let Some(num) = num {
+        check(num, config)?;
+        if let Some(quantity) = constant(num) {
+            check_whole(quantity, "dice quantity")?;
+            if quantity > config.max_dice as f64 && !config.clamp_quantity_to_max {
+                return Err(Eval(format!(
+                    "Dice quantity {} exceeds the maximum of {}",
+                    quantity, config.max_dice
+                )));
+            }
+        }
+    }
+    match size {
+        DiceSize::Value(sides) => {
+            check(sides, config)?;
+            let Some(sides) = constant(sides) else {
+                return Ok(());
+            };
+            if sides <= 0.0 {
+                return Err(Eval("die size must be positive".into()));
+            }
+            check_whole(sides, "die size")?;
+            if sides > config.max_die_size as f64 {
+                return Err(Eval(format!(
+                    "Die size {} exceeds the maximum of {}",
+                    sides, config.max_die_size
+                )));
+            }
+            Ok(())
+        }
+        DiceSize::ExprTable(entries) => entries.iter().try_for_each(|entry| check(entry, config)),
+        DiceSize::Percent | DiceSize::Percentile | DiceSize::Fudge | DiceSize::Custom(_) => Ok(()),
+    }
+}
+
+fn check_operations(operations: &[SetOperation], config: &EvalConfig) -> Result<()> {
+    for selector in operations.iter().flat_map(|operation| &operation.selectors) {
+        check(&selector.target, config)?;
+        let positional = matches!(
+            selector.kind,
+            SelectorKind::Highest | SelectorKind::Lowest | SelectorKind::Middle
+        );
+        if positional && let Some(count) = constant(&selector.target) {
+            check_whole(count, "selector")?;
+        }
+    }
+    Ok(())
+}
+
+/// Fails as evaluation would on a `context` that isn't a whole number of at
+/// least zero.
+fn check_whole(value: f64, context: &str) -> Result<()> {
+    if value < 0.0 {
+        return Err(Eval(format!("{} must be non-negative", context)));
+    }
+    if (value.round() - value).abs() > EPSILON {
+        return Err(Eval(format!(
+            "{} must be an integer, found {}",
+            context, value
+        )));
+    }
+    Ok(())
+}
+
+/// The value of `node` when it is a literal, possibly signed or annotated.
+fn constant(node: &Node) -> Option<f64> {
+    match node {
+        Node::Literal(value) => Some(*value),
+        Node::Unary { operator, operand } => {
+            let value = constant(operand)?;
+            Some(match operator {
+                UnaryOperator::Plus => value,
+                UnaryOperator::Minus => -value,
+            })
+        }
+        Node::Annotated { expr, .. } => constant(expr),
+        _ => None,
+    }
+}
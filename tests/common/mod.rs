@@ -1,6 +1,31 @@
-use rollatorium::roll;
+// Each test crate compiles this module and uses only some of its helpers.
+#![allow(dead_code)]
+
+use rollatorium::testing::SequenceRng;
+use rollatorium::{DiceRoll, EvalConfig, EvalResult, Value, eval_with_rng, parse, roll};
 
 /// Helper function to get total from roll
 pub fn r(expr: &str) -> f64 {
     roll(&expr).unwrap().total
 }
+
+/// Evaluates `expr` with `sides`-sided dice landing on `faces` in order.
+pub fn eval_faces(expr: &str, sides: u32, faces: &[u32], config: EvalConfig) -> EvalResult {
+    let ast = parse(&expr).unwrap();
+    eval_with_rng(&ast, config, SequenceRng::from_faces(sides, faces)).unwrap()
+}
+
+/// Rolls the dice pool `expr` with `sides`-sided dice landing on `faces` in
+/// order, returning the result and its pool.
+pub fn roll_faces(
+    expr: &str,
+    sides: u32,
+    faces: &[u32],
+    config: EvalConfig,
+) -> (EvalResult, DiceRoll) {
+    let result = eval_faces(expr, sides, faces, config);
+    match result.value.clone() {
+        Value::Dice(roll) => (result, roll),
+        other => panic!("expected dice result, got {:?}", other),
+    }
+}
@@ -0,0 +1,335 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{
+    EvalConfig, ExprProfile, bounds, clamp_mean_shift, eval_with_rng, expected_successes, luck,
+    parse, profile, roll,
+};
+
+// ============================================================================
+// Analysis - Luck
+// ============================================================================
+
+fn luck_of(expr: &str, total: f64) -> f64 {
+    luck(&parse(&expr).unwrap(), total, EvalConfig::default()).unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn test_luck_extremes_of_2d6() {
+    assert_close(luck_of("2d6", 12.0), 1.0);
+    assert_close(luck_of("2d6", 2.0), 1.0 / 36.0);
+    assert_close(luck_of("2d6", 1.0), 0.0);
+    assert_close(luck_of("2d6", 7.0), 21.0 / 36.0);
+}
+
+#[test]
+fn test_luck_keep_highest_is_exact() {
+    assert_close(luck_of("4d6kh3", 3.0), 1.0 / 1296.0);
+    assert_close(luck_of("4d6kh3", 18.0), 1.0);
+    // Rolling 17 or less misses only the 21 pools with three sixes.
+    assert_close(luck_of("4d6kh3", 17.0), 1.0 - 21.0 / 1296.0);
+    assert_close(luck_of("2d20kl1", 1.0), 39.0 / 400.0);
+}
+
+#[test]
+fn test_luck_arithmetic_and_sets() {
+    assert_close(luck_of("1d4 + 3", 4.0), 0.25);
+    assert_close(luck_of("-1d4", -4.0), 0.25);
+    assert_close(luck_of("(1d4, 1d4)", 2.0), 1.0 / 16.0);
+    assert_close(luck_of("1d20 >= 11", 0.0), 0.5);
+    assert_close(luck_of("d%", 0.0), 0.1);
+    assert_close(luck_of("1d[1, 2d6]", 1.0), 0.5);
+}
+
+#[test]
+fn test_luck_sampled_for_explosions() {
+    let low = luck_of("1d6e6", 1.0);
+    assert!((low - 1.0 / 6.0).abs() < 0.02, "sampled luck {}", low);
+    assert_close(luck_of("1d6e6", 0.0), 0.0);
+    assert_close(luck_of("1d6e6", 1000.0), 1.0);
+    assert_eq!(luck_of("1d6e6", 7.0), luck_of("1d6e6", 7.0));
+}
+
+#[test]
+fn test_luck_is_monotonic() {
+    for expr in ["3d6", "4d6kh3", "1d8rr1"] {
+        let mut previous = 0.0;
+        for total in 0..=25 {
+            let current = luck_of(expr, total as f64);
+            assert!(current >= previous, "{} not monotonic at {}", expr, total);
+            previous = current;
+        }
+    }
+}
+
+#[test]
+fn test_luck_of_actual_rolls_in_range() {
+    let ast = parse(&"3d6").unwrap();
+    for _ in 0..100 {
+        let total = roll(&"3d6").unwrap().total;
+        let value = luck(&ast, total, EvalConfig::default()).unwrap();
+        assert!(value > 0.0 && value <= 1.0);
+    }
+}
+
+#[test]
+fn test_luck_reports_evaluation_errors() {
+    assert!(
+        luck(
+            &parse(&"1d6e6 + 1d(1d4 - 1d4)").unwrap(),
+            3.0,
+            EvalConfig::default()
+        )
+        .is_err()
+    );
+}
+
+// ============================================================================
+// Analysis - Expected Successes
+// ============================================================================
+
+fn successes(expr: &str) -> rollatorium::Result<f64> {
+    expected_successes(&parse(&expr).unwrap(), EvalConfig::default())
+}
+
+#[test]
+fn test_expected_successes_threshold() {
+    assert_close(successes("6d10cs>=8").unwrap(), 1.8);
+    assert_close(successes("d6cs6").unwrap(), 1.0 / 6.0);
+    assert_close(successes("4d6cs>4 [attack]").unwrap(), 4.0 / 3.0);
+}
+
+#[test]
+fn test_expected_successes_net_of_failures() {
+    assert_close(successes("10d10cs>=8cf1").unwrap(), 10.0 * (0.3 - 0.1));
+    assert_close(successes("5d10cs<3>9").unwrap(), 5.0 * 0.3);
+    assert_close(successes("2d%cs>=50").unwrap(), 2.0 * 0.5);
+}
+
+#[test]
+fn test_expected_successes_rejects_other_expressions() {
+    assert!(successes("6d10").is_err());
+    assert!(successes("6d10kh3").is_err());
+    assert!(successes("6d10cs>=8kh3").is_err());
+    assert!(successes("6d10csh2").is_err());
+    assert!(successes("6d(1d4)cs>=8").is_err());
+    assert!(successes("1 + 6d10cs>=8").is_err());
+}
+
+// ============================================================================
+// Analysis - Clamp Mean Shift
+// ============================================================================
+
+fn clamp_shift(expr: &str) -> rollatorium::Result<f64> {
+    clamp_mean_shift(&parse(&expr).unwrap(), EvalConfig::default())
+}
+
+#[test]
+fn test_clamp_mean_shift_minimum() {
+    // 2d6 averages 7; raising ones and twos to three lifts each die by 0.5.
+    assert_close(clamp_shift("2d6mi3").unwrap(), 1.0);
+    assert_close(clamp_shift("2d6mi1").unwrap(), 0.0);
+}
+
+#[test]
+fn test_clamp_mean_shift_maximum_and_both() {
+    assert_close(clamp_shift("1d6ma4").unwrap(), -0.5);
+    assert_close(clamp_shift("1d6mi2ma5").unwrap(), 0.0);
+    assert_close(clamp_shift("3d%mi10 [floor]").unwrap(), 3.0 * 0.1 * 10.0);
+}
+
+#[test]
+fn test_clamp_mean_shift_matches_rolled_mean() {
+    let trials = 20_000;
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut mean_of = |expr: &str| {
+        let ast = parse(&expr).unwrap();
+        let sum: f64 = (0..trials)
+            .map(|_| {
+                eval_with_rng(&ast, EvalConfig::default(), &mut rng)
+                    .unwrap()
+                    .total
+            })
+            .sum();
+        sum / trials as f64
+    };
+    let observed = mean_of("2d6mi3") - mean_of("2d6");
+    assert!(
+        (observed - clamp_shift("2d6mi3").unwrap()).abs() < 0.15,
+        "observed shift {}",
+        observed
+    );
+}
+
+#[test]
+fn test_clamp_mean_shift_rejects_other_expressions() {
+    assert!(clamp_shift("2d6").is_err());
+    assert!(clamp_shift("2d6kh1").is_err());
+    assert!(clamp_shift("2d6mi3kh1").is_err());
+    assert!(clamp_shift("2d6mi3<2").is_err());
+    assert!(clamp_shift("1 + 2d6mi3").is_err());
+}
+
+// ============================================================================
+// Analysis - Profile
+// ============================================================================
+
+fn profile_of(expr: &str) -> ExprProfile {
+    profile(&parse(&expr).unwrap(), EvalConfig::default()).unwrap()
+}
+
+#[test]
+fn test_profile_of_2d6() {
+    let stats = profile_of("2d6");
+    assert!(stats.exact);
+    assert_close(stats.min, 2.0);
+    assert_close(stats.max, 12.0);
+    assert_close(stats.mean, 7.0);
+    assert_close(stats.variance, 35.0 / 6.0);
+    assert_close(stats.mode, 7.0);
+    assert_close(stats.p10, 4.0);
+    assert_close(stats.p50, 7.0);
+    assert_close(stats.p90, 10.0);
+    assert_eq!(stats.histogram.len(), 11);
+    assert_close(stats.histogram[0].1, 1.0 / 36.0);
+    assert_close(stats.histogram[5].1, 6.0 / 36.0);
+}
+
+#[test]
+fn test_profile_of_constant() {
+    let stats = profile_of("3 + 4");
+    assert_close(stats.min, 7.0);
+    assert_close(stats.max, 7.0);
+    assert_close(stats.variance, 0.0);
+    assert_close(stats.p10, 7.0);
+    assert_close(stats.p90, 7.0);
+}
+
+#[test]
+fn test_profile_samples_explosions() {
+    let stats = profile_of("1d6e6");
+    assert!(!stats.exact);
+    assert_close(stats.min, 1.0);
+    assert!((stats.mean - 4.2).abs() < 0.1, "mean {}", stats.mean);
+    let total: f64 = stats.histogram.iter().map(|(_, p)| p).sum();
+    assert_close(total, 1.0);
+}
+
+// ============================================================================
+// Analysis - Can Produce
+// ============================================================================
+
+fn can_produce(expr: &str, target: f64) -> bool {
+    parse(&expr)
+        .unwrap()
+        .can_produce(target, EvalConfig::default())
+        .unwrap()
+}
+
+#[test]
+fn test_can_produce_2d6() {
+    for target in 2..=12 {
+        assert!(can_produce("2d6", target as f64), "2d6 can roll {}", target);
+    }
+    assert!(!can_produce("2d6", 1.0));
+    assert!(!can_produce("2d6", 13.0));
+    assert!(!can_produce("2d6", 7.5));
+}
+
+#[test]
+fn test_can_produce_4d6kh3() {
+    for target in 3..=18 {
+        assert!(
+            can_produce("4d6kh3", target as f64),
+            "4d6kh3 can roll {}",
+            target
+        );
+    }
+    assert!(!can_produce("4d6kh3", 2.0));
+    assert!(!can_produce("4d6kh3", 19.0));
+}
+
+#[test]
+fn test_can_produce_is_conservative_for_explosions() {
+    assert!(!can_produce("1d6e6", 0.0));
+    assert!(!can_produce("2 + 1d6e6", 2.0));
+    // 1d6e6 never totals exactly 6, but only its bounds are known.
+    assert!(can_produce("1d6e6", 6.0));
+    assert!(can_produce("1d6e6", 100.0));
+}
+
+#[test]
+fn test_can_produce_reports_evaluation_errors() {
+    let expr = parse(&"1d6e6 + 1d0").unwrap();
+    assert!(expr.can_produce(3.0, EvalConfig::default()).is_err());
+}
+
+// ============================================================================
+// Analysis - Bounds
+// ============================================================================
+
+fn bounds_of(expr: &str) -> (f64, f64) {
+    bounds(&parse(&expr).unwrap()).unwrap()
+}
+
+#[test]
+fn test_bounds_of_plain_dice() {
+    assert_eq!(bounds_of("2d6"), (2.0, 12.0));
+    assert_eq!(bounds_of("3d%"), (0.0, 270.0));
+    assert_eq!(bounds_of("4dF"), (-4.0, 4.0));
+    assert_eq!(bounds_of("d6"), (1.0, 6.0));
+    assert_eq!(bounds_of("2d(1d4 + 2)"), (2.0, 12.0));
+}
+
+#[test]
+fn test_bounds_narrowed_by_keep_and_drop() {
+    assert_eq!(bounds_of("4d6kh3"), (3.0, 18.0));
+    assert_eq!(bounds_of("4d6pl1"), (3.0, 18.0));
+    assert_eq!(bounds_of("4d6k>4"), (0.0, 24.0));
+    assert_eq!(bounds_of("2d6mi3"), (6.0, 12.0));
+    // Chained keeps take the union, so this keeps two or three dice.
+    assert_eq!(bounds_of("4d6kh2kl1"), (2.0, 18.0));
+    // Parentheses end the chain, leaving one die.
+    assert_eq!(bounds_of("((10d6kh5)kl2)kh1"), (1.0, 6.0));
+}
+
+#[test]
+fn test_bounds_of_arithmetic() {
+    assert_eq!(bounds_of("2d6 - 1d4"), (-2.0, 11.0));
+    assert_eq!(bounds_of("-1d6 * 2"), (-12.0, -2.0));
+    assert_eq!(bounds_of("1d20 // 2"), (0.0, 10.0));
+    assert_eq!(bounds_of("1d20 >= 10"), (0.0, 1.0));
+    assert_eq!(bounds_of("2 + (1d8, 3)kh1"), (2.0, 13.0));
+}
+
+#[test]
+fn test_bounds_of_explosions_follow_max_rolls() {
+    let max_rolls = EvalConfig::default().max_rolls as f64;
+    assert_eq!(bounds_of("1d6e6"), (1.0, 6.0 * max_rolls));
+    assert_eq!(bounds_of("3d6e6kh1"), (1.0, 6.0));
+}
+
+#[test]
+fn test_bounds_contain_rolled_totals() {
+    for expr in ["4d6kh3", "2d6e6 - 1d4", "(1d8, 1d4)kl1", "5d10cs>=8cf1"] {
+        let (low, high) = bounds_of(expr);
+        for _ in 0..200 {
+            let total = roll(&expr).unwrap().total;
+            assert!(low <= total && total <= high, "{} rolled {}", expr, total);
+        }
+    }
+}
+
+#[test]
+fn test_bounds_reject_unbounded_operations() {
+    assert!(bounds(&parse(&"1d6stepe").unwrap()).is_err());
+    assert!(bounds(&parse(&"2d6 % 3").unwrap()).is_err());
+    assert!(bounds(&parse(&"1d6 / (1d4 - 2)").unwrap()).is_err());
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "binary")]
+
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{DieOrigin, EvalConfig, EvalResult, Value, eval_with_rng, parse};
+
+// ============================================================================
+// Binary Serialization
+// ============================================================================
+
+fn round_trip(result: &EvalResult) -> EvalResult {
+    let bytes = result.to_bytes().expect("result encodes");
+    EvalResult::from_bytes(&bytes).expect("result decodes")
+}
+
+#[test]
+fn test_complex_result_round_trips() {
+    let ast = parse(&"4d6kh3 [str] + 6d4e4 [x2 fire] + (1d8, 3, 2d6)kh2").unwrap();
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(11)).unwrap();
+    let decoded = round_trip(&result);
+
+    assert_eq!(decoded.total, result.total);
+    // Every field shows up in the debug form, so matching it checks the whole tree.
+    assert_eq!(format!("{:?}", decoded), format!("{:?}", result));
+    assert_eq!(decoded.to_ansi(), result.to_ansi());
+
+    let debug = format!("{:?}", decoded);
+    assert!(
+        debug.contains("dropped: true"),
+        "expected a dropped die or element"
+    );
+    assert!(debug.contains("repeat: 2"));
+}
+
+#[test]
+fn test_explosions_survive_round_trip() {
+    let ast = parse(&"10d4e4").unwrap();
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(3)).unwrap();
+    let Value::Dice(roll) = round_trip(&result).value else {
+        panic!("expected dice result");
+    };
+    assert!(roll.dice.len() > 10, "seed should trigger an explosion");
+    assert!(
+        roll.dice
+            .iter()
+            .any(|die| die.origin == DieOrigin::Explosion)
+    );
+}
+
+#[test]
+fn test_non_finite_totals_round_trip() {
+    let result = EvalResult {
+        total: f64::INFINITY,
+        value: Value::Literal(f64::NAN),
+    };
+    let decoded = round_trip(&result);
+    assert_eq!(decoded.total, f64::INFINITY);
+    match decoded.value {
+        Value::Literal(value) => assert!(value.is_nan()),
+        other => panic!("expected literal, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_truncated_bytes_error() {
+    let result = eval_with_rng(
+        &parse(&"3d6").unwrap(),
+        EvalConfig::default(),
+        StdRng::seed_from_u64(0),
+    )
+    .unwrap();
+    let bytes = result.to_bytes().unwrap();
+    let err = EvalResult::from_bytes(&bytes[..bytes.len() / 2]).unwrap_err();
+    assert!(err.to_string().starts_with("Serialization error"));
+}
@@ -0,0 +1,56 @@
+use rollatorium::parse;
+
+// ============================================================================
+// Canonical Hash
+// ============================================================================
+
+fn hash(expr: &str) -> u64 {
+    parse(&expr).unwrap().canonical_hash()
+}
+
+#[test]
+fn test_commutative_operands_share_a_hash() {
+    assert_eq!(hash("2 + 3"), hash("3 + 2"));
+    assert_eq!(hash("2 * 1d6"), hash("1d6 * 2"));
+    assert_eq!(hash("1 + 2 + 3"), hash("3 + (1 + 2)"));
+    assert_eq!(hash("1d20 == 20"), hash("20 == 1d20"));
+}
+
+#[test]
+fn test_non_commutative_operands_keep_their_order() {
+    assert_ne!(hash("2 - 3"), hash("3 - 2"));
+    assert_ne!(hash("6 / 2"), hash("2 / 6"));
+    assert_ne!(hash("1d20 > 10"), hash("10 > 1d20"));
+}
+
+#[test]
+fn test_distinct_expressions_differ() {
+    let exprs = [
+        "1d6",
+        "1d8",
+        "2d6",
+        "4d6kh3",
+        "4d6kl3",
+        "4d6kh2",
+        "1d6 + 1",
+        "1d6 * 1",
+        "d%",
+        "(1, 2)",
+        "(2, 1)kh1",
+        "3x1d6",
+    ];
+    for (i, a) in exprs.iter().enumerate() {
+        for b in &exprs[i + 1..] {
+            assert_ne!(hash(a), hash(b), "{} and {} hash equal", a, b);
+        }
+    }
+}
+
+#[test]
+fn test_hash_is_stable() {
+    // Changing this value invalidates every cache keyed on the hash.
+    assert_eq!(hash("4d6kh3 + 2"), 0x21a2_b06e_7504_d9ad);
+    assert_eq!(hash("d6"), hash("1d6"));
+    assert_eq!(hash("1d6 [fire]"), hash("1d6"));
+    assert_eq!(hash("0"), hash("0.0"));
+}
@@ -0,0 +1,62 @@
+use rollatorium::{parse, render_tree};
+
+// ============================================================================
+// Debug - AST Tree
+// ============================================================================
+
+fn tree(input: &str) -> String {
+    render_tree(&parse(&input).unwrap())
+}
+
+#[test]
+fn test_tree_shows_comparison_binds_loosest() {
+    assert_eq!(
+        tree("1 + 2 == 2"),
+        "\
+Binary(Equal)
+|-- left: Binary(Add)
+|   |-- left: Literal(1)
+|   `-- right: Literal(2)
+`-- right: Literal(2)
+"
+    );
+}
+
+#[test]
+fn test_tree_of_dice_with_operations() {
+    assert_eq!(
+        tree("max(4d6kh3 [str], -1d%) x2"),
+        "\
+Repeat(2)
+`-- expr: Call(max)
+    |-- arg: Annotated([str])
+    |   `-- expr: DiceWithOps
+    |       |-- dice: Dice
+    |       |   |-- count: Literal(4)
+    |       |   `-- sides: Literal(6)
+    |       `-- operation: Keep
+    |           `-- selector(Highest): Literal(3)
+    `-- arg: Unary(Minus)
+        `-- operand: Dice
+            |-- count: Literal(1)
+            `-- sides: Percent
+"
+    );
+}
+
+#[test]
+fn test_tree_of_sets_and_tables() {
+    assert_eq!(
+        tree("(1, d[2, 3])k>mean"),
+        "\
+Set
+|-- element: Literal(1)
+|-- element: Dice
+|   `-- sides: Table
+|       |-- entry: Literal(2)
+|       `-- entry: Literal(3)
+`-- operation: Keep
+    `-- selector(GreaterThan): Aggregate(mean)
+"
+    );
+}
@@ -162,3 +162,103 @@ fn test_annotations_do_not_change_totals() {
 
     assert_eq!(base_result.total, annotated_result.total);
 }
+
+#[test]
+fn test_roll_with_seed_is_reproducible() {
+    use rollatorium::roll_with_seed;
+
+    let expr = "2 * 4d6 + 1d20 - (1d8, 1d12)kh1";
+    for seed in 0..20 {
+        let first = roll_with_seed(&expr, seed).unwrap();
+        let second = roll_with_seed(&expr, seed).unwrap();
+        assert_eq!(first.total, second.total);
+        assert_eq!(first.to_string(), second.to_string());
+    }
+    let totals: Vec<f64> = (0..20)
+        .map(|seed| roll_with_seed(&expr, seed).unwrap().total)
+        .collect();
+    assert!(totals.iter().any(|&total| total != totals[0]));
+}
+
+#[test]
+fn test_roll_many_rolls_each_independently() {
+    use rollatorium::roll_many;
+
+    let results = roll_many(&"1d20 + 3", 50).unwrap();
+    assert_eq!(results.len(), 50);
+    assert!(results.iter().all(|result| result.total.is_finite()));
+    assert!(
+        results
+            .iter()
+            .all(|result| (4.0..=23.0).contains(&result.total))
+    );
+    assert!(
+        results
+            .iter()
+            .any(|result| result.total != results[0].total)
+    );
+    assert!(roll_many(&"1d20", 0).unwrap().is_empty());
+    assert!(roll_many(&"1d20 +", 3).is_err());
+}
+
+#[test]
+fn test_roll_iter_rolls_lazily_from_one_rng() {
+    use rand::{SeedableRng, rngs::StdRng};
+    use rollatorium::{EvalConfig, eval_with_rng, parse, roll_iter};
+
+    let expr = "1d20 + 3";
+    let ast = parse(&expr).unwrap();
+    let mut rng = StdRng::seed_from_u64(9);
+    let expected: Vec<f64> = (0..100)
+        .map(|_| {
+            eval_with_rng(&ast, EvalConfig::default(), &mut rng)
+                .unwrap()
+                .total
+        })
+        .collect();
+    let totals: Vec<f64> = roll_iter(&expr, StdRng::seed_from_u64(9))
+        .unwrap()
+        .take(100)
+        .map(|result| result.unwrap().total)
+        .collect();
+    assert_eq!(totals, expected);
+
+    let high = roll_iter(&"1d6", StdRng::seed_from_u64(1))
+        .unwrap()
+        .take(100_000)
+        .filter(|result| result.as_ref().unwrap().total == 6.0)
+        .count();
+    assert!((15_000..19_000).contains(&high), "{}", high);
+}
+
+#[test]
+fn test_roll_iter_errors() {
+    use rand::{SeedableRng, rngs::StdRng};
+    use rollatorium::roll_iter;
+
+    assert!(roll_iter(&"1d20 +", StdRng::seed_from_u64(0)).is_err());
+    let mut rolls = roll_iter(&"1d6e6 + 1d0", StdRng::seed_from_u64(0)).unwrap();
+    assert!(rolls.next().unwrap().is_err());
+    assert!(rolls.next().unwrap().is_err());
+}
+
+#[test]
+fn test_eval_with_boxed_rng() {
+    use rand::{RngCore, SeedableRng, rngs::StdRng};
+    use rollatorium::testing::SequenceRng;
+    use rollatorium::{EvalConfig, eval_with_boxed_rng, eval_with_rng, parse};
+
+    let ast = parse(&"2 + 4d6kh3").unwrap();
+    let rng: Box<dyn RngCore> = Box::new(SequenceRng::from_faces(6, &[3, 6, 1, 5]));
+    let result = eval_with_boxed_rng(&ast, EvalConfig::default(), rng).unwrap();
+    assert_eq!(result.total, 16.0);
+
+    let boxed = eval_with_boxed_rng(
+        &ast,
+        EvalConfig::default(),
+        Box::new(StdRng::seed_from_u64(4)),
+    )
+    .unwrap();
+    let plain = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(4)).unwrap();
+    assert_eq!(boxed.total, plain.total);
+}
@@ -81,3 +81,68 @@ fn test_annotations_with_set_operations() {
         );
     }
 }
+
+#[test]
+fn test_repeat_annotation_keeps_total_and_round_trips() {
+    let ast = parse(&"2 * 3 [x3 fire]").expect("parse repeat annotation");
+    assert_eq!(ast.to_string(), "2 * 3 [x3 fire]");
+    let result = eval(&ast).expect("evaluate repeat annotation");
+    assert_eq!(result.total, 6.0);
+
+    match result.value {
+        Value::Binary { right, .. } => match right.value {
+            Value::Annotated { annotations, .. } => {
+                assert_eq!(annotations[0].repeat, 3);
+                assert_eq!(annotations[0].text, "fire");
+            }
+            other => panic!("expected annotated operand, got {:?}", other),
+        },
+        other => panic!("expected binary value, got {:?}", other),
+    }
+}
+
+// =============================================================================
+// Tagged Totals
+// =============================================================================
+
+fn tagged(expr: &str, seed: u64) -> (f64, Vec<(String, f64)>) {
+    let ast = parse(&expr).unwrap();
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(seed)).unwrap();
+    (result.total, result.tagged_totals())
+}
+
+#[test]
+fn test_tagged_totals_split_damage_types() {
+    for seed in 0..20 {
+        let (total, tagged) = tagged("3d6[fire] + 2d6[cold]", seed);
+        assert_eq!(tagged.len(), 2);
+        assert_eq!(tagged[0].0, "fire");
+        assert_eq!(tagged[1].0, "cold");
+        assert!((3.0..=18.0).contains(&tagged[0].1));
+        assert!((2.0..=12.0).contains(&tagged[1].1));
+        assert_eq!(tagged[0].1 + tagged[1].1, total);
+    }
+}
+
+#[test]
+fn test_tagged_totals_sum_repeated_tags() {
+    let (total, tagged) = tagged("1d6[fire] + 1d4[cold] + 1d8[fire] + 3", 5);
+    assert_eq!(
+        tagged.iter().map(|(text, _)| text).collect::<Vec<_>>(),
+        ["fire", "cold"]
+    );
+    assert_eq!(tagged[0].1 + tagged[1].1 + 3.0, total);
+}
+
+#[test]
+fn test_tagged_totals_skip_dropped_elements() {
+    let (total, tagged) = tagged("(5[fire], 2[cold])kh1", 0);
+    assert_eq!(total, 5.0);
+    assert_eq!(tagged, [("fire".to_string(), 5.0)]);
+}
+
+#[test]
+fn test_tagged_totals_ignore_repeat_markers() {
+    let (_, tagged) = tagged("4[x2 fire] + 1[x3]", 0);
+    assert_eq!(tagged, [("fire".to_string(), 4.0)]);
+}
@@ -0,0 +1,32 @@
+mod common;
+use common::eval_faces;
+use rollatorium::{EvalConfig, roll};
+
+// ============================================================================
+// Breakdown - Plain Text
+// ============================================================================
+
+#[test]
+fn test_display_strikes_through_dropped_dice() {
+    let result = eval_faces("4d6kh3", 6, &[5, 3, 2, 6], EvalConfig::default());
+    assert_eq!(result.to_string(), "4d6kh3 (5, 3, ~~2~~, 6) = 14");
+}
+
+#[test]
+fn test_display_of_arithmetic_and_annotations() {
+    let result = eval_faces("2d6 [fire] + 3", 6, &[4, 1], EvalConfig::default());
+    assert_eq!(result.to_string(), "2d6 (4, 1) [fire] + 3 = 8");
+}
+
+#[test]
+fn test_display_of_sets() {
+    let result = eval_faces("(1d8, 3, 2)kh2", 8, &[1], EvalConfig::default());
+    assert_eq!(result.to_string(), "(~~1d8 (1)~~, 3, 2)kh2 = 5");
+}
+
+#[test]
+fn test_value_display_leaves_out_the_total() {
+    let result = roll(&"2 * (3 + 4)").unwrap();
+    assert_eq!(result.value.to_string(), "2 * (3 + 4)");
+    assert_eq!(result.to_string(), "2 * (3 + 4) = 14");
+}
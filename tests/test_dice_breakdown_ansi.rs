@@ -0,0 +1,144 @@
+mod common;
+use common::eval_faces;
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::testing::SequenceRng;
+use rollatorium::{EvalConfig, EvalResult, Value, eval_with_rng, parse};
+
+// ============================================================================
+// Breakdown - ANSI Colors
+// ============================================================================
+
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+#[test]
+fn test_ansi_dims_dropped_dice() {
+    let ast = parse(&"4d6kh3").unwrap();
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(0)).unwrap();
+    let dropped = match &result.value {
+        Value::Dice(roll) => roll.dice.iter().find(|die| die.dropped).unwrap().value,
+        other => panic!("expected dice result, got {:?}", other),
+    };
+    let rendered = result.to_ansi();
+    assert!(
+        rendered.contains(&format!("{}{}{}", DIM, dropped, RESET)),
+        "dropped die should be dimmed: {:?}",
+        rendered
+    );
+    assert!(rendered.starts_with("4d6kh3 ("));
+    assert!(rendered.ends_with(&format!(" = {}", result.total)));
+}
+
+#[test]
+fn test_ansi_colors_natural_max_and_one() {
+    let result = eval_faces("3d6", 6, &[6, 3, 1], EvalConfig::default());
+    assert_eq!(
+        result.to_ansi(),
+        "3d6 (\x1b[32m6\x1b[0m, 3, \x1b[31m1\x1b[0m) = 10"
+    );
+}
+
+#[test]
+fn test_ansi_bolds_exploded_dice() {
+    let result = eval_faces("1d2e2", 2, &[2, 1], EvalConfig::default());
+    assert_eq!(
+        result.to_ansi(),
+        "1d2e2 (\x1b[32m2\x1b[0m, \x1b[1m\x1b[31m1\x1b[0m) = 3"
+    );
+}
+
+#[test]
+fn test_ansi_plain_arithmetic_has_no_escapes() {
+    let result = rollatorium::roll(&"-(1 + 2) * 3").unwrap();
+    assert_eq!(result.to_ansi(), "-(1 + 2) * 3 = -9");
+}
+
+// ============================================================================
+// Breakdown - Pruned Operations
+// ============================================================================
+
+fn pruned(expr: &str, faces: &[u32]) -> EvalResult {
+    let config = EvalConfig {
+        prune_noop_operations: true,
+        ..EvalConfig::default()
+    };
+    let rng = SequenceRng::from_faces(6, faces);
+    eval_with_rng(&parse(&expr).unwrap(), config, rng).unwrap()
+}
+
+fn recorded_operations(result: &EvalResult) -> usize {
+    match &result.value {
+        Value::Dice(roll) => roll.operations.len(),
+        other => panic!("expected dice result, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_noop_keep_is_pruned() {
+    let result = pruned("4d6kh5", &[2, 3, 4, 5]);
+    assert_eq!(result.total, 14.0);
+    assert_eq!(recorded_operations(&result), 0);
+    assert_eq!(result.to_ansi(), "4d6 (2, 3, 4, 5) = 14");
+}
+
+#[test]
+fn test_effective_keep_is_retained() {
+    let result = pruned("4d6kh3", &[2, 3, 4, 5]);
+    assert_eq!(result.total, 12.0);
+    assert_eq!(recorded_operations(&result), 1);
+    assert!(result.to_ansi().starts_with("4d6kh3 ("));
+}
+
+#[test]
+fn test_only_noop_operations_are_pruned() {
+    let result = pruned("4d6rr1mi2kh3", &[2, 3, 4, 5]);
+    assert_eq!(result.total, 12.0);
+    assert_eq!(recorded_operations(&result), 1);
+    assert!(result.to_ansi().starts_with("4d6kh3 ("));
+}
+
+#[test]
+fn test_total_changing_operations_are_never_pruned() {
+    let result = pruned("4d6cs>5", &[2, 3, 4, 5]);
+    assert_eq!(result.total, 0.0);
+    assert_eq!(recorded_operations(&result), 1);
+}
+
+#[test]
+fn test_noop_operations_kept_by_default() {
+    let result = eval_faces("4d6kh5", 6, &[2, 3, 4, 5], EvalConfig::default());
+    assert_eq!(recorded_operations(&result), 1);
+}
+
+// ============================================================================
+// Breakdown - Counted Successes
+// ============================================================================
+
+#[test]
+fn test_ansi_marks_successes_and_failures() {
+    let result = eval_faces(
+        "5d10cs>=8cf<=1",
+        10,
+        &[9, 1, 5, 8, 10],
+        EvalConfig::default(),
+    );
+    assert_eq!(result.total, 2.0);
+    assert_eq!(
+        result.to_ansi(),
+        "5d10cs>=8cf<=1 (\x1b[4m9\x1b[0m, \x1b[7m\x1b[31m1\x1b[0m, 5, \x1b[4m8\x1b[0m, \
+         \x1b[4m\x1b[32m10\x1b[0m) = 2"
+    );
+}
+
+#[test]
+fn test_ansi_leaves_dropped_successes_unmarked() {
+    let result = eval_faces("3d10kh1cs>=8", 10, &[9, 8, 2], EvalConfig::default());
+    assert_eq!(result.total, 1.0);
+    assert_eq!(
+        result.to_ansi(),
+        format!(
+            "3d10kh1cs>=8 (\x1b[4m9\x1b[0m, {}8{}, {}2{}) = 1",
+            DIM, RESET, DIM, RESET
+        )
+    );
+}
@@ -1,5 +1,7 @@
 mod common;
-use common::r;
+use common::{eval_faces, r, roll_faces};
+
+use rollatorium::{CritType, EvalConfig, parse, roll};
 
 // ============================================================================
 // Chaining Operators
@@ -33,15 +35,88 @@ fn test_chaining_keep_on_literal_set() {
     assert_eq!(r("(1, 2, 3)k1k2"), 3.0);
 }
 
-// TODO: Implement CritType tracking
-// #[test]
-// fn test_crit() {
-//     // roll until we get a crit
-//     loop {
-//         let result = roll("1d20").unwrap();
-//         if result.total == 20.0 {
-//             assert_eq!(result.crit, CritType::Crit);
-//             break;
-//         }
-//     }
-// }
+#[test]
+fn test_chaining_keeps_take_union_on_dice_and_sets() {
+    // Chained keeps on a dice pool take the union of their selections, just
+    // as they do on a set.
+    let cases = [
+        ("4d6kh2kl1", 13.0),
+        ("4d6kh1kh2", 11.0),
+        ("4d6k2k3", 5.0),
+        ("4d6kh2ph1", 5.0),
+        ("4d6ph1kh1", 5.0),
+    ];
+    for (expr, total) in cases {
+        let result = eval_faces(expr, 6, &[5, 3, 2, 6], EvalConfig::default());
+        assert_eq!(result.total, total, "{}", expr);
+        let set = format!("(5, 3, 2, 6){}", &expr[3..]);
+        assert_eq!(r(&set), total, "{}", set);
+    }
+}
+
+#[test]
+fn test_parentheses_end_a_chain_of_keeps() {
+    // Each parenthesised keep chooses among the dice the one inside kept.
+    let cases = [
+        (
+            "(4d6kh2)kl1",
+            5.0,
+            "(4d6kh2)kl1 (5, ~~3~~, ~~2~~, ~~6~~) = 5",
+        ),
+        (
+            "((4d6kh3)kl2)kh1",
+            5.0,
+            "((4d6kh3)kl2)kh1 (5, ~~3~~, ~~2~~, ~~6~~) = 5",
+        ),
+    ];
+    for (expr, total, breakdown) in cases {
+        let result = eval_faces(expr, 6, &[5, 3, 2, 6], EvalConfig::default());
+        assert_eq!(result.total, total, "{}", expr);
+        assert_eq!(result.to_string(), breakdown);
+    }
+    assert_eq!(
+        parse(&"((10d6kh5)kl2)kh1").unwrap().to_string(),
+        "((10d6kh5)kl2)kh1"
+    );
+}
+
+#[test]
+fn test_selectors_in_one_keep_take_union() {
+    // One keep with both selectors keeps the highest and the lowest die, just
+    // as the chained `kh1kl1` does.
+    let (result, roll) = roll_faces("4d6kh1l1", 6, &[5, 3, 2, 6], EvalConfig::default());
+    assert_eq!(result.total, 8.0);
+    let kept: Vec<f64> = roll
+        .dice
+        .iter()
+        .filter(|d| d.kept)
+        .map(|d| d.value)
+        .collect();
+    assert_eq!(kept, [2.0, 6.0]);
+}
+
+#[test]
+fn test_crit() {
+    // roll until we get a crit
+    loop {
+        let result = roll(&"1d20").unwrap();
+        if result.total == 20.0 {
+            assert_eq!(result.crit(), CritType::Crit);
+            break;
+        }
+    }
+}
+
+#[test]
+fn test_crit_reads_the_natural_roll() {
+    let crit =
+        |expr: &str, face: u32| eval_faces(expr, 20, &[face, 5], EvalConfig::default()).crit();
+    assert_eq!(crit("1d20", 1), CritType::Fail);
+    assert_eq!(crit("1d20 + 5", 20), CritType::Crit);
+    assert_eq!(crit("1d20", 12), CritType::None);
+    // A rerolled natural one is still a fumble; more dice are never a crit.
+    assert_eq!(crit("1d20ro1", 1), CritType::Fail);
+    // An exploded natural 20 is still a crit despite the die it added.
+    assert_eq!(crit("1d20e20", 20), CritType::Crit);
+    assert_eq!(crit("2d20", 20), CritType::None);
+}
@@ -0,0 +1,54 @@
+mod common;
+use common::roll_faces;
+use rollatorium::{DiceRoll, EvalConfig};
+
+// ============================================================================
+// Dice Contributions
+// ============================================================================
+
+fn contributions_total(roll: &DiceRoll) -> f64 {
+    roll.contributions().iter().map(|&(_, share)| share).sum()
+}
+
+#[test]
+fn test_contributions_of_sum_pool() {
+    let (result, roll) = roll_faces("4d6kh3", 6, &[2, 5, 1, 4], EvalConfig::default());
+    assert_eq!(
+        roll.contributions(),
+        vec![(0, 2.0), (1, 5.0), (2, 0.0), (3, 4.0)]
+    );
+    assert_eq!(contributions_total(&roll), result.total);
+}
+
+#[test]
+fn test_contributions_of_count_pool() {
+    let (result, roll) = roll_faces("4d6cs>4cf1", 6, &[6, 3, 1, 5], EvalConfig::default());
+    assert_eq!(
+        roll.contributions(),
+        vec![(0, 1.0), (1, 0.0), (2, -1.0), (3, 1.0)]
+    );
+    assert_eq!(contributions_total(&roll), result.total);
+}
+
+#[test]
+fn test_contributions_of_spread_pool() {
+    let (result, roll) = roll_faces("4d6spread", 6, &[2, 5, 1, 4], EvalConfig::default());
+    assert_eq!(
+        roll.contributions(),
+        vec![(0, 0.0), (1, 5.0), (2, -1.0), (3, 0.0)]
+    );
+    assert_eq!(contributions_total(&roll), result.total);
+}
+
+#[test]
+fn test_contributions_of_single_die_spread() {
+    let (_, roll) = roll_faces("1d6spread", 6, &[4], EvalConfig::default());
+    assert_eq!(roll.contributions(), vec![(0, 0.0)]);
+}
+
+#[test]
+fn test_contributions_include_exploded_dice() {
+    let (result, roll) = roll_faces("1d6e6", 6, &[6, 3], EvalConfig::default());
+    assert_eq!(roll.contributions(), vec![(0, 6.0), (1, 3.0)]);
+    assert_eq!(contributions_total(&roll), result.total);
+}
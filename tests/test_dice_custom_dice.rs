@@ -0,0 +1,115 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{DiceRegistry, EvalConfig, Value, eval_with_config, eval_with_rng, luck, parse};
+
+// ============================================================================
+// Custom Dice
+// ============================================================================
+
+fn rune_config() -> EvalConfig {
+    let mut dice = DiceRegistry::new();
+    dice.register("Rune", [0.0, 1.0, 3.0]).unwrap();
+    dice.register_weighted("Coin", &[(1.0, 2), (0.0, 1)])
+        .unwrap();
+    EvalConfig {
+        dice,
+        ..EvalConfig::default()
+    }
+}
+
+#[test]
+fn test_custom_pool_rolls_registered_faces() {
+    let ast = parse(&"6dRune").unwrap();
+    assert_eq!(ast.to_string(), "6dRune");
+    for seed in 0..100 {
+        let result = eval_with_rng(&ast, rune_config(), StdRng::seed_from_u64(seed)).unwrap();
+        let Value::Dice(roll) = &result.value else {
+            panic!("expected dice result");
+        };
+        assert_eq!(roll.dice.len(), 6);
+        assert!(
+            roll.dice
+                .iter()
+                .all(|die| [0.0, 1.0, 3.0].contains(&die.value))
+        );
+        let sum: f64 = roll.dice.iter().map(|die| die.value).sum();
+        assert_eq!(result.total, sum);
+    }
+}
+
+#[test]
+fn test_custom_dice_with_operations() {
+    let ast = parse(&"dCoin + 4dRune kh2").unwrap();
+    assert_eq!(ast.to_string(), "dCoin + 4dRune kh2");
+    for _ in 0..100 {
+        let total = eval_with_config(&ast, rune_config()).unwrap().total;
+        assert!((0.0..=7.0).contains(&total), "unexpected total: {}", total);
+    }
+}
+
+#[test]
+fn test_custom_weighted_faces() {
+    let ast = parse(&"dCoin").unwrap();
+    assert!((luck(&ast, 0.0, rune_config()).unwrap() - 1.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_unregistered_custom_die_errors() {
+    let ast = parse(&"2dRune").unwrap();
+    let err = eval_with_config(&ast, EvalConfig::default()).unwrap_err();
+    assert!(
+        err.to_string().contains("Unknown custom die 'dRune'"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_invalid_custom_die_registrations() {
+    let mut dice = DiceRegistry::new();
+    assert!(dice.register("9Lives", [1.0]).is_err());
+    assert!(dice.register("Rune-2", [1.0]).is_err());
+    assert!(dice.register("Blank", Vec::new()).is_err());
+    assert!(dice.register("Void", [f64::NAN]).is_err());
+    assert!(dice.register_weighted("Unweighted", &[(1.0, 0)]).is_err());
+    assert!(dice.faces("Blank").is_none());
+}
+
+#[test]
+fn test_custom_possible_faces() {
+    for (expr, expected) in [("2dRune", vec![0.0, 1.0, 3.0]), ("dCoin", vec![0.0, 1.0])] {
+        let result = eval_with_config(&parse(&expr).unwrap(), rune_config()).unwrap();
+        let Value::Dice(roll) = &result.value else {
+            panic!("expected dice result");
+        };
+        assert_eq!(roll.possible_faces(), expected);
+    }
+}
+
+#[test]
+fn test_selector_epsilon_on_fractional_faces() {
+    let config = |selector_epsilon| {
+        let mut dice = DiceRegistry::new();
+        dice.register("Shard", [0.3 + 1e-7]).unwrap();
+        dice.register("Sum", [0.1 + 0.2]).unwrap();
+        EvalConfig {
+            dice,
+            selector_epsilon,
+            ..EvalConfig::default()
+        }
+    };
+    let total = |expr: &str, selector_epsilon| {
+        eval_with_config(&parse(&expr).unwrap(), config(selector_epsilon))
+            .unwrap()
+            .total
+    };
+    let tight = EvalConfig::default().selector_epsilon;
+    assert_eq!(total("3dShard cs==0.3", tight), 0.0);
+    assert_eq!(total("3dShard cs==0.3", 1e-6), 3.0);
+    assert_eq!(total("3dShard cs!=0.3", tight), 3.0);
+    assert_eq!(total("3dShard cs!=0.3", 1e-6), 0.0);
+    // Drift from summing fractions stays within the default tolerance.
+    assert_eq!(total("2dSum cs==0.3", tight), 2.0);
+    // Set selectors share the tolerance.
+    assert_eq!(total("(0.3000001, 0.5)k==0.3", tight), 0.0);
+    assert_eq!(total("(0.3000001, 0.5)k==0.3", 1e-6), 0.3000001);
+}
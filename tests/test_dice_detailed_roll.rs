@@ -0,0 +1,127 @@
+mod common;
+use common::{eval_faces, roll_faces};
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{
+    CritType, DetailedRoll, EvalConfig, SuccessTally, Value, eval_with_rng, parse, roll_detailed,
+};
+
+// ============================================================================
+// Detailed Roll
+// ============================================================================
+
+fn detailed(expr: &str, sides: u32, faces: &[u32]) -> DetailedRoll {
+    DetailedRoll::from_result(&eval_faces(expr, sides, faces, EvalConfig::default()))
+}
+
+#[test]
+fn test_detailed_keep_highest() {
+    let roll = detailed("4d6kh3", 6, &[5, 3, 2, 6]);
+    assert_eq!(roll.total, 14.0);
+    assert_eq!(
+        roll.dice,
+        [(5.0, true), (3.0, true), (2.0, false), (6.0, true)]
+    );
+    assert_eq!(roll.successes, None);
+    assert_eq!(roll.crit, CritType::None);
+}
+
+#[test]
+fn test_detailed_count_successes() {
+    let roll = detailed("6d10cs>=8", 10, &[8, 3, 10, 7, 9, 1]);
+    assert_eq!(roll.total, 3.0);
+    assert_eq!(roll.successes, Some(3));
+    assert_eq!(roll.dice.len(), 6);
+    assert!(roll.dice.iter().all(|&(_, kept)| kept));
+}
+
+#[test]
+fn test_detailed_crit_on_lone_die() {
+    assert_eq!(detailed("1d20 + 5", 20, &[20]).crit, CritType::Crit);
+    assert_eq!(detailed("1d20", 20, &[1]).crit, CritType::Fail);
+    assert_eq!(detailed("1d20", 20, &[12]).crit, CritType::None);
+    assert_eq!(detailed("2d20", 20, &[20, 20]).crit, CritType::None);
+}
+
+#[test]
+fn test_roll_detailed_matches_kept_dice() {
+    for _ in 0..100 {
+        let roll = roll_detailed(&"4d6kh3", EvalConfig::default()).unwrap();
+        assert_eq!(roll.dice.len(), 4);
+        assert_eq!(roll.dice.iter().filter(|&&(_, kept)| kept).count(), 3);
+        let kept: f64 = roll
+            .dice
+            .iter()
+            .filter(|&&(_, kept)| kept)
+            .map(|&(value, _)| value)
+            .sum();
+        assert_eq!(roll.total, kept);
+    }
+}
+
+// ============================================================================
+// Detailed Roll - Success Tally
+// ============================================================================
+
+#[test]
+fn test_success_tally_glitches_on_half_ones() {
+    let tally = roll_faces("6d6cs>=5", 6, &[5, 1, 6, 1, 3, 1], EvalConfig::default())
+        .1
+        .success_tally()
+        .unwrap();
+    assert_eq!(tally.successes, 2);
+    assert_eq!(tally.ones, 3);
+    assert!(tally.glitch);
+}
+
+#[test]
+fn test_success_tally_without_glitch() {
+    let tally = roll_faces("6d6cs>=5", 6, &[5, 1, 6, 1, 3, 2], EvalConfig::default())
+        .1
+        .success_tally()
+        .unwrap();
+    assert_eq!(
+        tally,
+        SuccessTally {
+            successes: 2,
+            ones: 2,
+            glitch: false
+        }
+    );
+}
+
+#[test]
+fn test_success_tally_counts_natural_ones() {
+    // The 1 raised by `mi2` still counts; the penetrating die rolled a 2.
+    let (_, roll) = roll_faces("2d6mi2cs>=5", 6, &[1, 5], EvalConfig::default());
+    assert_eq!(roll.success_tally().unwrap().ones, 1);
+    let (_, roll) = roll_faces("2d6!p6cs>=5", 6, &[6, 3, 2], EvalConfig::default());
+    assert_eq!(roll.dice.last().unwrap().value, 1.0);
+    assert_eq!(roll.success_tally().unwrap().ones, 0);
+}
+
+#[test]
+fn test_success_tally_needs_counted_successes() {
+    assert_eq!(
+        roll_faces("6d6", 6, &[1, 1, 1, 1, 1, 1], EvalConfig::default())
+            .1
+            .success_tally(),
+        None
+    );
+}
+
+#[test]
+fn test_success_tally_seeded() {
+    let ast = parse(&"8d6cs>=5").unwrap();
+    for seed in 0..100 {
+        let result =
+            eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(seed)).unwrap();
+        let Value::Dice(roll) = &result.value else {
+            panic!("expected dice result");
+        };
+        let tally = roll.success_tally().unwrap();
+        assert_eq!(tally.successes as f64, result.total);
+        let ones = roll.dice.iter().filter(|die| die.rolls[0] == 1.0).count();
+        assert_eq!(tally.ones, ones);
+        assert_eq!(tally.glitch, ones >= 4);
+    }
+}
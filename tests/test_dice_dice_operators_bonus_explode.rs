@@ -0,0 +1,60 @@
+mod common;
+use common::roll_faces;
+use rollatorium::testing::SequenceRng;
+use rollatorium::{DieOrigin, EvalConfig, eval_with_rng, parse};
+
+// ============================================================================
+// Dice Operators - Bonus Explode
+// ============================================================================
+
+#[test]
+fn test_bonus_pool_excluded_from_total() {
+    let (result, roll) = roll_faces("3d10eb10", 10, &[10, 3, 7, 4], EvalConfig::default());
+    assert_eq!(result.total, 20.0);
+    assert_eq!(roll.dice.len(), 3);
+    assert_eq!(roll.bonus.len(), 1);
+    assert_eq!(roll.bonus[0].value, 4.0);
+    assert_eq!(roll.bonus[0].origin, DieOrigin::Bonus);
+    assert_eq!(roll.bonus_total(), 4.0);
+}
+
+#[test]
+fn test_bonus_pool_chains_on_trigger() {
+    let (result, roll) = roll_faces("2d10eb>=9", 10, &[9, 10, 10, 2, 5], EvalConfig::default());
+    assert_eq!(result.total, 19.0);
+    let bonus: Vec<_> = roll.bonus.iter().map(|die| die.value).collect();
+    assert_eq!(bonus, [10.0, 2.0, 5.0]);
+    assert_eq!(roll.bonus_total(), 17.0);
+}
+
+#[test]
+fn test_bonus_pool_empty_without_trigger() {
+    let (result, roll) = roll_faces("3d6eb6", 6, &[1, 2, 3], EvalConfig::default());
+    assert_eq!(result.total, 6.0);
+    assert!(roll.bonus.is_empty());
+    assert_eq!(roll.bonus_total(), 0.0);
+}
+
+#[test]
+fn test_bonus_pool_after_keep() {
+    let (result, roll) = roll_faces("3d6kh2eb6", 6, &[6, 1, 4, 5], EvalConfig::default());
+    assert_eq!(result.total, 10.0);
+    assert_eq!(roll.bonus_total(), 5.0);
+}
+
+#[test]
+fn test_bonus_pool_breakdown() {
+    let (result, _) = roll_faces("2d6eb6", 6, &[6, 2, 3], EvalConfig::default());
+    assert_eq!(
+        result.to_ansi(),
+        "2d6eb6 (\x1b[32m6\x1b[0m, 2) bonus (3) = 8"
+    );
+    assert_eq!(parse(&"2d6eb6").unwrap().to_string(), "2d6eb6");
+}
+
+#[test]
+fn test_bonus_pool_bounded_by_max_rolls() {
+    let ast = parse(&"1d6eb>0").unwrap();
+    let rng = SequenceRng::new([SequenceRng::face(6, 3)]);
+    assert!(eval_with_rng(&ast, EvalConfig::default(), rng).is_err());
+}
@@ -0,0 +1,58 @@
+mod common;
+use common::{r, roll_faces};
+
+use rollatorium::{EvalConfig, parse};
+
+// ============================================================================
+// Dice Operators - Count Success
+// ============================================================================
+
+#[test]
+fn test_cs_op_totals_successes() {
+    let (result, roll) = roll_faces("5d10cs>=8", 10, &[9, 1, 5, 8, 10], EvalConfig::default());
+    assert_eq!(result.total, 3.0);
+    let successes: Vec<bool> = roll.dice.iter().map(|die| die.success).collect();
+    assert_eq!(successes, [true, false, false, true, true]);
+    assert!(roll.dice.iter().all(|die| !die.failure));
+}
+
+#[test]
+fn test_cs_op_ignores_pips() {
+    assert_eq!(r("3d1cs1"), 3.0);
+    assert_eq!(r("3d1cs>1"), 0.0);
+}
+
+#[test]
+fn test_cs_op_skips_dropped_dice() {
+    let (result, _) = roll_faces("4d10kh2cs>=8", 10, &[9, 8, 10, 2], EvalConfig::default());
+    assert_eq!(result.total, 2.0);
+}
+
+// ============================================================================
+// Dice Operators - Count Failure
+// ============================================================================
+
+#[test]
+fn test_cf_op_subtracts_failures() {
+    let (result, roll) = roll_faces(
+        "5d10cs>=8cf<=1",
+        10,
+        &[9, 1, 5, 8, 10],
+        EvalConfig::default(),
+    );
+    assert_eq!(result.total, 2.0);
+    let failures: Vec<bool> = roll.dice.iter().map(|die| die.failure).collect();
+    assert_eq!(failures, [false, true, false, false, false]);
+}
+
+#[test]
+fn test_cf_op_alone_can_go_negative() {
+    let (result, _) = roll_faces("4d10cf<=1", 10, &[1, 1, 5, 9], EvalConfig::default());
+    assert_eq!(result.total, -2.0);
+}
+
+#[test]
+fn test_cs_cf_op_display() {
+    let ast = parse(&"5d10cs>=8cf<=1").unwrap();
+    assert_eq!(ast.to_string(), "5d10cs>=8cf<=1");
+}
@@ -1,5 +1,7 @@
 mod common;
-use common::r;
+use common::{r, roll_faces};
+use rollatorium::testing::SequenceRng;
+use rollatorium::{EvalConfig, Value, eval_with_config, eval_with_rng, parse};
 
 // ============================================================================
 // Dice Operators - Explode
@@ -24,3 +26,140 @@ fn test_e_op_infinite_loop_under() {
 fn test_e_op_infinite_loop_all() {
     let _ = r("1d1e1");
 }
+
+// ============================================================================
+// Dice Operators - Explode - Truncation
+// ============================================================================
+
+fn truncating(max_rolls: usize) -> EvalConfig {
+    EvalConfig {
+        max_rolls,
+        truncate_on_limit: true,
+        ..EvalConfig::default()
+    }
+}
+
+#[test]
+fn test_e_op_truncates_at_max_rolls() {
+    let ast = parse(&"1d6e==6").unwrap();
+    let rng = SequenceRng::new([SequenceRng::face(6, 6)]);
+    let result = eval_with_rng(&ast, truncating(3), rng).unwrap();
+    assert!(result.truncated());
+    assert_eq!(result.total, 18.0);
+    let Value::Dice(roll) = &result.value else {
+        panic!("expected a dice roll, got {:?}", result.value);
+    };
+    assert!(roll.truncated);
+    assert_eq!(roll.dice.len(), 3);
+}
+
+#[test]
+fn test_e_op_truncation_keeps_later_operations() {
+    let result = eval_with_config(&parse(&"1d1e1kh2").unwrap(), truncating(5)).unwrap();
+    assert!(result.truncated());
+    assert_eq!(result.total, 2.0);
+}
+
+#[test]
+fn test_e_op_within_limit_not_truncated() {
+    let result = eval_with_config(&parse(&"2d6").unwrap(), truncating(3)).unwrap();
+    assert!(!result.truncated());
+}
+
+#[test]
+#[should_panic(expected = "Exceeded maximum number of rolls")]
+fn test_e_op_limit_errors_without_truncation() {
+    let config = EvalConfig {
+        max_rolls: 3,
+        ..EvalConfig::default()
+    };
+    eval_with_config(&parse(&"1d1e1").unwrap(), config).unwrap();
+}
+
+// ============================================================================
+// Dice Operators - Explode - Chain Depth
+// ============================================================================
+
+/// The total and die values of `expr` rolled on `faces`.
+fn exploded(expr: &str, sides: u32, faces: &[u32], config: EvalConfig) -> (f64, Vec<f64>) {
+    let (result, roll) = roll_faces(expr, sides, faces, config);
+    (
+        result.total,
+        roll.dice.iter().map(|die| die.value).collect(),
+    )
+}
+
+fn depth_capped(cap: usize) -> EvalConfig {
+    EvalConfig {
+        max_explosion_depth: Some(cap),
+        ..EvalConfig::default()
+    }
+}
+
+#[test]
+fn test_e_op_chain_stops_at_depth_cap() {
+    let chain = exploded("1d6e==6", 6, &[6, 6, 6, 6, 6], depth_capped(3));
+    assert_eq!(chain, (24.0, vec![6.0; 4]));
+    let chain = exploded("1d6e==6", 6, &[6], depth_capped(0));
+    assert_eq!(chain, (6.0, vec![6.0]));
+}
+
+#[test]
+fn test_e_op_depth_cap_is_per_chain() {
+    // Each of the two sixes gets its own single explosion.
+    let chains = exploded("2d6e==6", 6, &[6, 6, 6, 6], depth_capped(1));
+    assert_eq!(chains, (24.0, vec![6.0; 4]));
+}
+
+#[test]
+fn test_e_op_depth_cap_keeps_short_chains() {
+    let chain = exploded("1d6e==6", 6, &[6, 6, 2], depth_capped(5));
+    assert_eq!(chain, (14.0, vec![6.0, 6.0, 2.0]));
+}
+
+#[test]
+fn test_e_op_depth_cap_bounds_endless_chain() {
+    let config = EvalConfig {
+        max_explosion_depth: Some(20),
+        ..EvalConfig::default()
+    };
+    let result = eval_with_config(&parse(&"1d1e1").unwrap(), config).unwrap();
+    assert_eq!(result.total, 21.0);
+    assert!(!result.truncated());
+}
+
+// ============================================================================
+// Dice Operators - Explode - Ranges
+// ============================================================================
+
+#[test]
+fn test_e_op_explodes_on_a_range() {
+    let (total, dice) = exploded("1d10e>=8", 10, &[9, 8, 10, 3], EvalConfig::default());
+    assert_eq!(total, 30.0);
+    assert_eq!(dice, [9.0, 8.0, 10.0, 3.0]);
+}
+
+#[test]
+fn test_e_op_range_judges_each_new_die_alone() {
+    // The 5 and 6 explode; the 2 and 4 they roll stop, though earlier dice
+    // still match.
+    let (total, dice) = exploded("4d6e>=5", 6, &[5, 1, 6, 3, 2, 4], EvalConfig::default());
+    assert_eq!(total, 21.0);
+    assert_eq!(dice, [5.0, 1.0, 6.0, 3.0, 2.0, 4.0]);
+    let (total, dice) = exploded(
+        "4d6e>=5",
+        6,
+        &[5, 1, 6, 3, 6, 2, 5, 1],
+        EvalConfig::default(),
+    );
+    assert_eq!(total, 29.0);
+    assert_eq!(dice.len(), 8);
+}
+
+#[test]
+fn test_e_op_positional_selector_ranks_new_die_in_pool() {
+    // Each new die explodes again only while it is the single highest.
+    let (total, dice) = exploded("2d6eh1", 6, &[3, 5, 6, 2], EvalConfig::default());
+    assert_eq!(total, 16.0);
+    assert_eq!(dice, [3.0, 5.0, 6.0, 2.0]);
+}
@@ -0,0 +1,59 @@
+mod common;
+use common::{r, roll_faces};
+use rollatorium::{DiceRoll, EvalConfig, parse};
+
+// ============================================================================
+// Dice Operators - Explode Compound
+// ============================================================================
+
+fn compound(expr: &str, faces: &[u32]) -> DiceRoll {
+    roll_faces(expr, 6, faces, EvalConfig::default()).1
+}
+
+#[test]
+fn test_compound_adds_onto_die() {
+    let roll = compound("1d6!!", &[6, 4]);
+    assert_eq!(roll.dice.len(), 1);
+    assert_eq!(roll.dice[0].value, 10.0);
+    assert_eq!(roll.dice[0].rolls, [6.0, 4.0]);
+}
+
+#[test]
+fn test_compound_chains_while_matching() {
+    let roll = compound("2d6!!", &[6, 3, 6, 6, 2]);
+    assert_eq!(roll.dice.len(), 2);
+    assert_eq!(roll.dice[0].value, 20.0);
+    assert_eq!(roll.dice[0].rolls, [6.0, 6.0, 6.0, 2.0]);
+    assert_eq!(roll.dice[1].value, 3.0);
+}
+
+#[test]
+fn test_compound_with_selector() {
+    let roll = compound("2d6!!>=5", &[5, 2, 6, 1]);
+    assert_eq!(roll.dice[0].value, 12.0);
+    assert_eq!(roll.dice[0].rolls, [5.0, 6.0, 1.0]);
+    assert_eq!(roll.dice[1].value, 2.0);
+}
+
+#[test]
+fn test_compound_then_keep() {
+    let roll = compound("2d6!!kh1", &[6, 5, 1]);
+    assert_eq!(roll.dice[0].value, 7.0);
+    assert!(!roll.dice[0].dropped);
+    assert!(roll.dice[1].dropped);
+}
+
+#[test]
+fn test_compound_lexes_apart_from_explode() {
+    assert_eq!(parse(&"1d6!!").unwrap().to_string(), "1d6!!");
+    assert_eq!(parse(&"1d6!!6").unwrap().to_string(), "1d6!!6");
+    assert_eq!(parse(&"1d6!6").unwrap().to_string(), "1d6e6");
+    assert_eq!(parse(&"1d6!!=3").unwrap().to_string(), "1d6e!=3");
+    assert_ne!(parse(&"1d6!!6").unwrap(), parse(&"1d6!6").unwrap());
+}
+
+#[test]
+#[should_panic(expected = "Exceeded maximum number of rolls")]
+fn test_compound_respects_max_rolls() {
+    let _ = r("1d1!!");
+}
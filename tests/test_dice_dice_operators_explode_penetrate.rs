@@ -0,0 +1,50 @@
+mod common;
+use common::roll_faces;
+use rollatorium::{DiceRoll, EvalConfig, parse};
+
+// ============================================================================
+// Dice Operators - Explode Penetrate
+// ============================================================================
+
+fn explode_penetrate(expr: &str, faces: &[u32]) -> (f64, DiceRoll) {
+    let (result, roll) = roll_faces(expr, 6, faces, EvalConfig::default());
+    (result.total, roll)
+}
+
+#[test]
+fn test_explode_penetrate_compounds_one_less() {
+    let (total, roll) = explode_penetrate("1d6!!p", &[6, 6, 3]);
+    assert_eq!(total, 13.0);
+    assert_eq!(roll.dice.len(), 1);
+    assert_eq!(roll.dice[0].rolls, [6.0, 6.0, 3.0]);
+    assert_eq!(roll.dice[0].value, 13.0);
+}
+
+#[test]
+fn test_explode_penetrate_leaves_other_dice() {
+    let (total, roll) = explode_penetrate("2d6!!p", &[2, 6, 1]);
+    assert_eq!(total, 8.0);
+    let values: Vec<f64> = roll.dice.iter().map(|die| die.value).collect();
+    assert_eq!(values, [2.0, 6.0]);
+    assert_eq!(roll.dice[1].rolls, [6.0, 1.0]);
+}
+
+#[test]
+fn test_explode_penetrate_with_selector() {
+    let (total, roll) = explode_penetrate("1d6!!p>=5", &[5, 6, 2]);
+    assert_eq!(total, 11.0);
+    assert_eq!(roll.dice[0].rolls, [5.0, 6.0, 2.0]);
+}
+
+#[test]
+fn test_explode_penetrate_display() {
+    for input in ["1d6!!p", "4d6!!p>=5", "1 + 1d6!!p"] {
+        assert_eq!(parse(&input).unwrap().to_string(), input);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Exceeded maximum number of rolls")]
+fn test_explode_penetrate_bounded_by_max_rolls() {
+    let _ = rollatorium::roll(&"1d1!!p").unwrap();
+}
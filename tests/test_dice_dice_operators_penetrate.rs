@@ -0,0 +1,49 @@
+mod common;
+use common::{r, roll_faces};
+use rollatorium::{DiceRoll, DieOrigin, EvalConfig, parse};
+
+// ============================================================================
+// Dice Operators - Penetrate
+// ============================================================================
+
+fn penetrate(expr: &str, faces: &[u32]) -> (f64, DiceRoll) {
+    let (result, roll) = roll_faces(expr, 6, faces, EvalConfig::default());
+    (result.total, roll)
+}
+
+#[test]
+fn test_penetrate_subtracts_one_from_explosions() {
+    let (total, roll) = penetrate("1d6!p", &[6, 4]);
+    assert_eq!(total, 9.0);
+    assert_eq!(roll.dice.len(), 2);
+    assert_eq!(roll.dice[1].value, 3.0);
+    assert_eq!(roll.dice[1].rolls, [4.0]);
+    assert!(matches!(roll.dice[1].origin, DieOrigin::Explosion));
+}
+
+#[test]
+fn test_penetrate_chains_on_raw_roll() {
+    let (total, roll) = penetrate("1d6!p", &[6, 6, 1]);
+    assert_eq!(total, 11.0);
+    let values: Vec<f64> = roll.dice.iter().map(|die| die.value).collect();
+    assert_eq!(values, [6.0, 5.0, 0.0]);
+}
+
+#[test]
+fn test_penetrate_with_selector() {
+    let (total, roll) = penetrate("2d6!p>=5", &[5, 2, 3]);
+    assert_eq!(total, 9.0);
+    assert_eq!(roll.dice.len(), 3);
+}
+
+#[test]
+fn test_penetrate_display() {
+    assert_eq!(parse(&"1d6!p").unwrap().to_string(), "1d6!p");
+    assert_eq!(parse(&"1d6!p>=5").unwrap().to_string(), "1d6!p>=5");
+}
+
+#[test]
+#[should_panic(expected = "Exceeded maximum number of rolls")]
+fn test_penetrate_respects_max_rolls() {
+    let _ = r("1d1!p");
+}
@@ -1,5 +1,7 @@
 mod common;
-use common::r;
+use common::{r, roll_faces};
+
+use rollatorium::{DiceRoll, EvalConfig, EvalResult, eval_with_rng, parse};
 
 // ============================================================================
 // Dice Operators - Reroll
@@ -26,3 +28,46 @@ fn test_rr_op_infinite_loop_under() {
 fn test_rr_op_infinite_loop_all() {
     let _ = r("1d1rr1");
 }
+
+// ============================================================================
+// Dice Operators - Reroll - Per-Die Cap
+// ============================================================================
+
+fn roll_capped(expr: &str, cap: Option<usize>, faces: &[u32]) -> (EvalResult, DiceRoll) {
+    let config = EvalConfig {
+        max_rerolls_per_die: cap,
+        ..EvalConfig::default()
+    };
+    roll_faces(expr, 20, faces, config)
+}
+
+#[test]
+fn test_rr_cap_keeps_last_value() {
+    let (result, roll) = roll_capped("1d20rr<20", Some(2), &[3, 5, 7, 20]);
+    assert_eq!(result.total, 7.0);
+    assert_eq!(roll.dice[0].rolls, [3.0, 5.0, 7.0]);
+}
+
+#[test]
+fn test_rr_cap_counts_each_die_apart() {
+    let (result, _) = roll_capped("2d20rr<10", Some(1), &[2, 15, 4, 8]);
+    assert_eq!(result.total, 19.0);
+}
+
+#[test]
+fn test_rr_cap_none_rerolls_until_no_match() {
+    let (result, _) = roll_capped("1d20rr<20", None, &[3, 5, 7, 20]);
+    assert_eq!(result.total, 20.0);
+}
+
+#[test]
+fn test_rr_cap_stops_an_endless_reroll() {
+    let config = EvalConfig {
+        max_rerolls_per_die: Some(10),
+        ..EvalConfig::default()
+    };
+    let ast = parse(&"50d1rr1").unwrap();
+    let result = eval_with_rng(&ast, config, rand::rng()).unwrap();
+    assert_eq!(result.total, 50.0);
+    assert!(eval_with_rng(&ast, EvalConfig::default(), rand::rng()).is_err());
+}
@@ -0,0 +1,49 @@
+mod common;
+use common::roll_faces;
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{EvalConfig, Value, eval_with_rng, parse};
+
+// ============================================================================
+// Dice Operators - Reroll Better and Worse
+// ============================================================================
+
+#[test]
+fn test_reroll_better_keeps_higher_roll() {
+    let ast = parse(&"1d20rb").unwrap();
+    for seed in 0..50 {
+        let result =
+            eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(seed)).unwrap();
+        let Value::Dice(roll) = &result.value else {
+            panic!("expected dice result");
+        };
+        let die = &roll.dice[0];
+        assert_eq!(die.rolls.len(), 2);
+        assert_eq!(die.value, die.rolls[0].max(die.rolls[1]));
+        assert_eq!(result.total, die.value);
+    }
+}
+
+#[test]
+fn test_reroll_worse_keeps_lower_roll() {
+    let (result, roll) = roll_faces("1d20rw", 20, &[15, 4], EvalConfig::default());
+    assert_eq!(result.total, 4.0);
+    assert_eq!(roll.dice[0].rolls, [15.0, 4.0]);
+    let (result, _) = roll_faces("1d20rw", 20, &[3, 18], EvalConfig::default());
+    assert_eq!(result.total, 3.0);
+}
+
+#[test]
+fn test_reroll_better_with_selector() {
+    // Only the 2 rerolls, and the 1 it rolls leaves it at 2.
+    let (result, roll) = roll_faces("3d6rb<3", 6, &[5, 2, 4, 1], EvalConfig::default());
+    assert_eq!(result.total, 11.0);
+    let rolls: Vec<usize> = roll.dice.iter().map(|die| die.rolls.len()).collect();
+    assert_eq!(rolls, [1, 2, 1]);
+}
+
+#[test]
+fn test_reroll_better_display() {
+    for input in ["1d20rb", "1d20rw", "4d6rb<3", "1 + 2d20rw"] {
+        assert_eq!(parse(&input).unwrap().to_string(), input);
+    }
+}
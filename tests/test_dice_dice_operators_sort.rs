@@ -0,0 +1,48 @@
+mod common;
+use common::roll_faces;
+use rollatorium::{DiceRoll, EvalConfig};
+
+// ============================================================================
+// Dice Operators - Sort
+// ============================================================================
+
+fn values(roll: &DiceRoll) -> Vec<f64> {
+    roll.dice.iter().map(|die| die.value).collect()
+}
+
+#[test]
+fn test_sort_ascending_keeps_total() {
+    let faces = [4, 1, 6, 3, 1];
+    let (unsorted, _) = roll_faces("5d6", 6, &faces, EvalConfig::default());
+    let (sorted, roll) = roll_faces("5d6sa", 6, &faces, EvalConfig::default());
+    assert_eq!(sorted.total, unsorted.total);
+    assert_eq!(values(&roll), [1.0, 1.0, 3.0, 4.0, 6.0]);
+}
+
+#[test]
+fn test_sort_descending() {
+    let (result, roll) = roll_faces("5d6sd", 6, &[4, 1, 6, 3, 1], EvalConfig::default());
+    assert_eq!(result.total, 15.0);
+    assert_eq!(values(&roll), [6.0, 4.0, 3.0, 1.0, 1.0]);
+}
+
+#[test]
+fn test_sort_keeps_kept_flags_with_their_dice() {
+    let (result, roll) = roll_faces("4d6kh3sa", 6, &[5, 2, 6, 3], EvalConfig::default());
+    assert_eq!(result.total, 14.0);
+    assert_eq!(values(&roll), [2.0, 3.0, 5.0, 6.0]);
+    let kept: Vec<bool> = roll.dice.iter().map(|die| die.kept).collect();
+    assert_eq!(kept, [false, true, true, true]);
+    assert!(roll.dice[0].dropped);
+}
+
+#[test]
+fn test_sort_displays_and_follows_selector_matches() {
+    let config = EvalConfig {
+        record_selector_matches: true,
+        ..EvalConfig::default()
+    };
+    let (result, roll) = roll_faces("3d6kh1sd", 6, &[2, 6, 4], config);
+    assert_eq!(result.value.to_string(), "3d6kh1sd (6, ~~4~~, ~~2~~)");
+    assert_eq!(roll.selector_matches[0].dice, [(0, vec![0])]);
+}
@@ -0,0 +1,56 @@
+mod common;
+use common::roll_faces;
+use rollatorium::{EvalConfig, parse, roll};
+
+// ============================================================================
+// Dice Operators - Spread
+// ============================================================================
+
+#[test]
+fn test_spread_of_equal_dice_is_zero() {
+    let (result, roll) = roll_faces("4d6spread", 6, &[3, 3, 3, 3], EvalConfig::default());
+    assert_eq!(result.total, 0.0);
+    assert_eq!(roll.spread(), Some(0.0));
+}
+
+#[test]
+fn test_spread_of_mixed_pool() {
+    let (result, roll) = roll_faces("4d6spread", 6, &[2, 5, 1, 4], EvalConfig::default());
+    assert_eq!(result.total, 4.0);
+    assert_eq!(roll.spread(), Some(4.0));
+}
+
+#[test]
+fn test_spread_uses_kept_dice() {
+    let (result, roll) = roll_faces("4d6kh3spread", 6, &[2, 5, 1, 4], EvalConfig::default());
+    assert_eq!(result.total, 3.0);
+    assert_eq!(roll.spread(), Some(3.0));
+    assert_eq!(parse(&"4d6kh3spread").unwrap().to_string(), "4d6kh3spread");
+}
+
+#[test]
+fn test_spread_accessor_without_operator() {
+    let (result, roll) = roll_faces("3d6", 6, &[6, 1, 4], EvalConfig::default());
+    assert_eq!(result.total, 11.0);
+    assert_eq!(roll.spread(), Some(5.0));
+    let (_, roll) = roll_faces("3d6p>0", 6, &[6, 1, 4], EvalConfig::default());
+    assert_eq!(roll.spread(), None);
+}
+
+#[test]
+fn test_spread_stays_in_range() {
+    for _ in 0..100 {
+        let val = roll(&"6d10spread").unwrap().total;
+        assert!(
+            (0.0..=9.0).contains(&val),
+            "6d10spread out of range: {}",
+            val
+        );
+    }
+}
+
+#[test]
+fn test_spread_rejects_success_counts_and_sets() {
+    assert!(roll(&"4d6spreadcs>3").is_err());
+    assert!(roll(&"(1, 2, 3)spread").is_err());
+}
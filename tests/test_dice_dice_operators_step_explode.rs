@@ -0,0 +1,74 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{DieOrigin, EvalConfig, Value, eval_with_rng, parse};
+
+// ============================================================================
+// Dice Operators - Step Explode
+// ============================================================================
+
+fn dice_for(expr: &str, config: EvalConfig, seed: u64) -> rollatorium::DiceRoll {
+    let ast = parse(&expr).unwrap();
+    let result = eval_with_rng(&ast, config, StdRng::seed_from_u64(seed)).unwrap();
+    match result.value {
+        Value::Dice(roll) => roll,
+        other => panic!("expected dice result, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_stepe_exceeds_original_die() {
+    let dice = dice_for("1d6stepe", EvalConfig::default(), 16);
+    assert_eq!(dice.dice.len(), 2);
+    assert_eq!(dice.dice[0].value, 6.0);
+    assert_eq!(dice.dice[1].origin, DieOrigin::StepExplosion { size: 8 });
+    assert!(dice.dice[1].value > 6.0, "step-up die should beat a d6");
+}
+
+#[test]
+fn test_stepe_climbs_ladder() {
+    let dice = dice_for("1d6stepe", EvalConfig::default(), 65);
+    let sizes: Vec<_> = dice.dice.iter().map(|die| die.origin).collect();
+    assert_eq!(
+        sizes,
+        [
+            DieOrigin::Original,
+            DieOrigin::StepExplosion { size: 8 },
+            DieOrigin::StepExplosion { size: 10 },
+        ]
+    );
+}
+
+#[test]
+fn test_stepe_custom_ladder() {
+    let config = EvalConfig {
+        explode_ladder: vec![6, 100],
+        ..EvalConfig::default()
+    };
+    let dice = dice_for("1d6stepe", config, 16);
+    assert_eq!(dice.dice[1].origin, DieOrigin::StepExplosion { size: 100 });
+}
+
+#[test]
+fn test_stepe_range() {
+    for seed in 0..200 {
+        let dice = dice_for("1d6stepe", EvalConfig::default(), seed);
+        for die in &dice.dice {
+            match die.origin {
+                DieOrigin::Original => assert!((1.0..=6.0).contains(&die.value)),
+                DieOrigin::StepExplosion { size } => {
+                    assert!((1.0..=size as f64).contains(&die.value))
+                }
+                other => panic!("unexpected origin {:?}", other),
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "Exceeded maximum number of rolls")]
+fn test_stepe_bounded_by_max_rolls() {
+    let config = EvalConfig {
+        explode_ladder: vec![1],
+        ..EvalConfig::default()
+    };
+    let _ = dice_for("1d1stepe", config, 0);
+}
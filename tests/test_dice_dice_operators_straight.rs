@@ -0,0 +1,59 @@
+mod common;
+use common::roll_faces;
+use rollatorium::{EvalConfig, parse, roll};
+
+// ============================================================================
+// Dice Operators - Straight
+// ============================================================================
+
+#[test]
+fn test_straight_with_gap() {
+    let (result, roll) = roll_faces("4d6straight", 6, &[6, 3, 2, 4], EvalConfig::default());
+    assert_eq!(result.total, 3.0);
+    assert_eq!(roll.straight(), 3);
+}
+
+#[test]
+fn test_straight_of_full_run() {
+    let (result, _) = roll_faces("5d6 straight", 6, &[5, 1, 3, 2, 4], EvalConfig::default());
+    assert_eq!(result.total, 5.0);
+}
+
+#[test]
+fn test_straight_ignores_repeated_faces() {
+    let (result, roll) = roll_faces("6d6straight", 6, &[2, 2, 3, 3, 5, 5], EvalConfig::default());
+    assert_eq!(result.total, 2.0);
+    assert_eq!(
+        roll.contributions(),
+        vec![(0, 1.0), (1, 0.0), (2, 1.0), (3, 0.0), (4, 0.0), (5, 0.0)]
+    );
+}
+
+#[test]
+fn test_straight_uses_kept_dice() {
+    let (result, _) = roll_faces("4d6kh3straight", 6, &[1, 2, 3, 5], EvalConfig::default());
+    assert_eq!(result.total, 2.0);
+    assert_eq!(
+        parse(&"4d6kh3straight").unwrap().to_string(),
+        "4d6kh3straight"
+    );
+}
+
+#[test]
+fn test_straight_stays_in_range() {
+    for _ in 0..100 {
+        let val = roll(&"6d6straight").unwrap().total;
+        assert!(
+            (1.0..=6.0).contains(&val),
+            "6d6straight out of range: {}",
+            val
+        );
+    }
+}
+
+#[test]
+fn test_straight_rejects_other_totals_and_sets() {
+    assert!(roll(&"4d6straightcs>3").is_err());
+    assert!(roll(&"4d6straightspread").is_err());
+    assert!(roll(&"(1, 2, 3)straight").is_err());
+}
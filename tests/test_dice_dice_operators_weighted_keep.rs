@@ -0,0 +1,69 @@
+mod common;
+use common::roll_faces;
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{EvalConfig, Value, eval_with_rng, parse, roll};
+
+// ============================================================================
+// Dice Operators - Weighted Keep
+// ============================================================================
+
+#[test]
+fn test_wkeep_keeps_dice_at_or_under_their_value() {
+    // Four dice, then one keep draw for each.
+    let (result, roll) = roll_faces(
+        "4d6wkeep",
+        6,
+        &[6, 1, 4, 3, 6, 2, 5, 3],
+        EvalConfig::default(),
+    );
+    let kept: Vec<bool> = roll.dice.iter().map(|die| die.kept).collect();
+    assert_eq!(kept, vec![true, false, false, true]);
+    assert!(roll.dice[1].dropped);
+    assert_eq!(result.total, 9.0);
+    assert_eq!(parse(&"4d6wkeep").unwrap().to_string(), "4d6wkeep");
+}
+
+#[test]
+fn test_wkeep_favours_high_dice() {
+    let ast = parse(&"6d6 wkeep").unwrap();
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut rolled = [0usize; 7];
+    let mut kept = [0usize; 7];
+    for _ in 0..2000 {
+        let result = eval_with_rng(&ast, EvalConfig::default(), &mut rng).unwrap();
+        let Value::Dice(roll) = result.value else {
+            panic!("expected dice result");
+        };
+        for die in &roll.dice {
+            rolled[die.value as usize] += 1;
+            kept[die.value as usize] += die.kept as usize;
+        }
+    }
+    for face in 1..=6 {
+        let rate = kept[face] as f64 / rolled[face] as f64;
+        let expected = face as f64 / 6.0;
+        assert!(
+            (rate - expected).abs() < 0.05,
+            "face {} kept {:.3} of the time, expected {:.3}",
+            face,
+            rate,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_wkeep_respects_max_rolls() {
+    let config = EvalConfig {
+        max_rolls: 5,
+        ..EvalConfig::default()
+    };
+    let ast = parse(&"4d6wkeep").unwrap();
+    assert!(eval_with_rng(&ast, config, StdRng::seed_from_u64(0)).is_err());
+}
+
+#[test]
+fn test_wkeep_requires_numbered_dice() {
+    assert!(roll(&"4d%wkeep").is_err());
+    assert!(roll(&"(1, 2, 3)wkeep").is_err());
+}
@@ -0,0 +1,526 @@
+mod common;
+use common::{eval_faces, r};
+
+use rand::distr::{Distribution, Uniform};
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::testing::SequenceRng;
+use rollatorium::{EvalConfig, Value, eval_with_rng, parse, roll};
+
+// ============================================================================
+// Functions - until
+// ============================================================================
+
+fn until_with_seed(expr: &str, seed: u64) -> rollatorium::Result<rollatorium::EvalResult> {
+    let ast = parse(&expr).unwrap();
+    eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(seed))
+}
+
+/// Replays the d20 stream for `seed` to find the first attempt reaching `target`.
+fn expected_attempt(seed: u64, target: u32) -> usize {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let d20 = Uniform::new_inclusive(1, 20).unwrap();
+    (1..).find(|_| d20.sample(&mut rng) >= target).unwrap()
+}
+
+#[test]
+fn test_until_counts_attempts() {
+    for seed in 0..20 {
+        let result = until_with_seed("until(1d20 + 5 >= 23, 100)", seed).unwrap();
+        assert_eq!(result.total, expected_attempt(seed, 18) as f64);
+    }
+}
+
+#[test]
+fn test_until_keeps_final_roll() {
+    let result = until_with_seed("until(1d20 >= 15, 100)", 7).unwrap();
+    let Value::Call { name, args } = result.value else {
+        panic!("expected call result");
+    };
+    assert_eq!(name, "until");
+    assert_eq!(args.len(), 2);
+    assert_eq!(args[0].total, 1.0);
+    assert_eq!(args[1].total, 100.0);
+    match &args[0].value {
+        Value::Binary { left, .. } => assert!(left.total >= 15.0),
+        other => panic!("expected comparison, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_until_immediate_success() {
+    assert_eq!(roll(&"until(1d20 >= 1, 5)").unwrap().total, 1.0);
+}
+
+#[test]
+fn test_until_exhausts_tries() {
+    let err = roll(&"until(1d20 > 20, 50)").unwrap_err();
+    assert!(err.to_string().contains("within 50 tries"));
+}
+
+#[test]
+fn test_until_constant_false_stops_early() {
+    let err = roll(&"until(1 > 2, 1000000)").unwrap_err();
+    assert!(err.to_string().contains("tries"));
+}
+
+#[test]
+fn test_until_bounded_by_max_rolls() {
+    let ast = parse(&"until(1d20 > 20, 1000000)").unwrap();
+    let err = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(0)).unwrap_err();
+    assert!(err.to_string().contains("rolls"));
+}
+
+#[test]
+fn test_until_argument_count() {
+    assert!(roll(&"until(1d20 >= 10)").is_err());
+    assert!(roll(&"until(1d20 >= 10, 5, 2)").is_err());
+}
+
+#[test]
+fn test_until_requires_parentheses() {
+    assert!(parse(&"until 3").is_err());
+}
+
+#[test]
+fn test_until_zero_tries() {
+    let err = roll(&"until(1, 0)").unwrap_err();
+    assert!(err.to_string().contains("0 tries"));
+}
+
+#[test]
+fn test_until_display() {
+    let ast = parse(&"until(1d20+5>=15,10)").unwrap();
+    assert_eq!(ast.to_string(), "until(1d20 + 5 >= 15, 10)");
+}
+
+// ============================================================================
+// Functions - clamp
+// ============================================================================
+
+#[test]
+fn test_clamp_limits_total() {
+    assert_eq!(roll(&"clamp(25, 1..20)").unwrap().total, 20.0);
+    assert_eq!(roll(&"clamp(-3, 1..20)").unwrap().total, 1.0);
+    assert_eq!(roll(&"clamp(7, 1..20)").unwrap().total, 7.0);
+}
+
+#[test]
+fn test_clamp_dice_within_range() {
+    for _ in 0..100 {
+        let total = roll(&"clamp(3d6, 5..15)").unwrap().total;
+        assert!((5.0..=15.0).contains(&total), "{}", total);
+    }
+}
+
+#[test]
+fn test_clamp_expression_bounds() {
+    assert_eq!(roll(&"clamp(100, 1 + 1..2 * 5)").unwrap().total, 10.0);
+    assert_eq!(roll(&"clamp(0.25, .5..1.5)").unwrap().total, 0.5);
+}
+
+#[test]
+fn test_clamp_records_range() {
+    let result = roll(&"clamp(25, 1..20)").unwrap();
+    let Value::Call { args, .. } = result.value else {
+        panic!("expected call result");
+    };
+    assert_eq!(args[0].total, 25.0);
+    match &args[1].value {
+        Value::Range { low, high } => {
+            assert_eq!(low.total, 1.0);
+            assert_eq!(high.total, 20.0);
+        }
+        other => panic!("expected range, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_clamp_errors() {
+    assert!(roll(&"clamp(5, 20..1)").is_err());
+    assert!(roll(&"clamp(5, 20)").is_err());
+    assert!(roll(&"clamp(5)").is_err());
+}
+
+#[test]
+fn test_range_outside_function_errors() {
+    let err = parse(&"1..20").unwrap_err();
+    assert!(err.to_string().contains("function arguments"), "{}", err);
+    assert!(parse(&"(1..20)").is_err());
+    assert!(parse(&"1d20 + 1..20").is_err());
+}
+
+#[test]
+fn test_range_display() {
+    let ast = parse(&"clamp(3d6,1..2+4)").unwrap();
+    assert_eq!(ast.to_string(), "clamp(3d6, 1..2 + 4)");
+}
+
+// ============================================================================
+// Functions - margin
+// ============================================================================
+
+fn margin_with(expr: &str, face: u32) -> rollatorium::EvalResult {
+    eval_faces(expr, 20, &[face], EvalConfig::default())
+}
+
+#[test]
+fn test_margin_on_hit_and_miss() {
+    assert_eq!(margin_with("margin(1d20 + 5, 15)", 17).total, 7.0);
+    assert_eq!(margin_with("margin(1d20 + 5, 15)", 4).total, -6.0);
+    assert_eq!(margin_with("margin(1d20 + 5, 15)", 10).total, 0.0);
+}
+
+#[test]
+fn test_margin_differs_from_comparison() {
+    assert_eq!(margin_with("1d20 + 5 >= 15", 17).total, 1.0);
+    assert_eq!(margin_with("margin(1d20 + 5, 15) * 2", 17).total, 14.0);
+}
+
+#[test]
+fn test_margin_keeps_roll() {
+    let result = margin_with("margin(1d20 + 5, 10 + 5)", 12);
+    assert_eq!(result.total, 2.0);
+    let Value::Call { name, args } = &result.value else {
+        panic!("expected call result, got {:?}", result.value);
+    };
+    assert_eq!(name, "margin");
+    assert_eq!(args[0].total, 17.0);
+    assert_eq!(args[1].total, 15.0);
+    assert!(matches!(args[0].value, Value::Binary { .. }));
+}
+
+#[test]
+fn test_margin_errors() {
+    assert!(roll(&"margin(1d20)").is_err());
+    assert!(roll(&"margin(1d20, 10, 5)").is_err());
+}
+
+// ============================================================================
+// Functions - if
+// ============================================================================
+
+/// Counts the words drawn from the wrapped RNG.
+struct CountingRng {
+    inner: SequenceRng,
+    draws: usize,
+}
+
+impl rand::RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.draws += 1;
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.draws += 1;
+        self.inner.fill_bytes(dst)
+    }
+}
+
+/// Rolls `if(1d20 >= 10, 1d6, 1d8)` with the d20 on `check`, the d6 on 4 and
+/// the d8 on 2, returning the total and the RNG draws taken.
+fn if_with(check: u32, lazy_branches: bool) -> (f64, usize) {
+    let ast = parse(&"if(1d20 >= 10, 1d6, 1d8)").unwrap();
+    let faces = if lazy_branches && check < 10 {
+        vec![SequenceRng::face(20, check), SequenceRng::face(8, 2)]
+    } else if lazy_branches {
+        vec![SequenceRng::face(20, check), SequenceRng::face(6, 4)]
+    } else {
+        vec![
+            SequenceRng::face(20, check),
+            SequenceRng::face(6, 4),
+            SequenceRng::face(8, 2),
+        ]
+    };
+    let mut rng = CountingRng {
+        inner: SequenceRng::once(faces),
+        draws: 0,
+    };
+    let config = EvalConfig {
+        lazy_branches,
+        ..EvalConfig::default()
+    };
+    let total = eval_with_rng(&ast, config, &mut rng).unwrap().total;
+    (total, rng.draws)
+}
+
+#[test]
+fn test_if_eager_rolls_both_branches() {
+    assert_eq!(if_with(15, false), (4.0, 3));
+    assert_eq!(if_with(5, false), (2.0, 3));
+}
+
+#[test]
+fn test_if_lazy_rolls_taken_branch() {
+    assert_eq!(if_with(15, true), (4.0, 2));
+    assert_eq!(if_with(5, true), (2.0, 2));
+}
+
+#[test]
+fn test_if_records_rolled_branches() {
+    let ast = parse(&"if(1 > 2, 1d6, 10)").unwrap();
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(0)).unwrap();
+    assert_eq!(result.total, 10.0);
+    let Value::Call { name, args } = &result.value else {
+        panic!("expected call result, got {:?}", result.value);
+    };
+    assert_eq!(name, "if");
+    assert_eq!(args.len(), 3);
+    assert_eq!(ast.to_string(), "if(1 > 2, 1d6, 10)");
+}
+
+#[test]
+fn test_if_errors() {
+    assert!(roll(&"if(1, 2)").is_err());
+    assert!(roll(&"if(1, 2, 3, 4)").is_err());
+}
+
+// ============================================================================
+// Functions - chance
+// ============================================================================
+
+fn chance_with(expr: &str, face: u32) -> rollatorium::EvalResult {
+    eval_faces(expr, 100, &[face], EvalConfig::default())
+}
+
+#[test]
+fn test_chance_inside_and_outside_band() {
+    assert_eq!(chance_with("chance(30)", 1).total, 1.0);
+    assert_eq!(chance_with("chance(30)", 30).total, 1.0);
+    assert_eq!(chance_with("chance(30)", 31).total, 0.0);
+    assert_eq!(chance_with("chance(30)", 100).total, 0.0);
+}
+
+#[test]
+fn test_chance_extremes() {
+    assert_eq!(chance_with("chance(0)", 1).total, 0.0);
+    assert_eq!(chance_with("chance(100)", 100).total, 1.0);
+    assert_eq!(chance_with("chance(10 * 5)", 50).total, 1.0);
+}
+
+#[test]
+fn test_chance_shows_roll_in_breakdown() {
+    let result = chance_with("chance(30)", 57);
+    let Value::Call { name, args } = &result.value else {
+        panic!("expected call result, got {:?}", result.value);
+    };
+    assert_eq!(name, "chance");
+    assert_eq!(args[1].total, 57.0);
+    assert_eq!(result.to_ansi(), "chance(30, 1d100 (57)) = 0");
+}
+
+#[test]
+fn test_chance_errors() {
+    assert!(roll(&"chance()").is_err());
+    assert!(roll(&"chance(30, 40)").is_err());
+}
+
+// ============================================================================
+// Functions - needs
+// ============================================================================
+
+fn needs_with(faces: &[u32]) -> rollatorium::EvalResult {
+    eval_faces("needs(6d10cs>=8, 3)", 10, faces, EvalConfig::default())
+}
+
+#[test]
+fn test_needs_exactly_at_threshold() {
+    assert_eq!(needs_with(&[8, 9, 10, 1, 2, 3]).total, 1.0);
+}
+
+#[test]
+fn test_needs_one_below_threshold() {
+    assert_eq!(needs_with(&[8, 9, 7, 1, 2, 3]).total, 0.0);
+}
+
+#[test]
+fn test_needs_above_threshold() {
+    assert_eq!(needs_with(&[8, 9, 10, 10, 2, 3]).total, 1.0);
+}
+
+#[test]
+fn test_needs_keeps_pool_in_breakdown() {
+    let result = needs_with(&[8, 9, 7, 1, 2, 3]);
+    let Value::Call { name, args } = &result.value else {
+        panic!("expected call result, got {:?}", result.value);
+    };
+    assert_eq!(name, "needs");
+    assert_eq!(args[0].total, 2.0);
+    let Value::Dice(pool) = &args[0].value else {
+        panic!("expected dice result, got {:?}", args[0].value);
+    };
+    assert_eq!(pool.dice.len(), 6);
+    assert_eq!(args[1].total, 3.0);
+}
+
+#[test]
+fn test_needs_errors() {
+    assert!(roll(&"needs(6d10cs>=8)").is_err());
+    assert!(roll(&"needs(6d10cs>=8, 3, 4)").is_err());
+}
+
+// ============================================================================
+// Functions - reroll_pool
+// ============================================================================
+
+fn reroll_pool_with(expr: &str, faces: &[u32]) -> rollatorium::EvalResult {
+    eval_faces(expr, 6, faces, EvalConfig::default())
+}
+
+fn attempts(result: &rollatorium::EvalResult) -> Vec<f64> {
+    let Value::Call { name, args } = &result.value else {
+        panic!("expected call result, got {:?}", result.value);
+    };
+    assert_eq!(name, "reroll_pool");
+    args[..args.len() - 1].iter().map(|arg| arg.total).collect()
+}
+
+#[test]
+fn test_reroll_pool_keeps_passing_first_roll() {
+    let result = reroll_pool_with("reroll_pool(2d6, <7, 3)", &[3, 4]);
+    assert_eq!(result.total, 7.0);
+    assert_eq!(attempts(&result), [7.0]);
+}
+
+#[test]
+fn test_reroll_pool_rerolls_until_condition_fails() {
+    let result = reroll_pool_with("reroll_pool(2d6, <7, 3)", &[1, 2, 2, 2, 6, 5]);
+    assert_eq!(result.total, 11.0);
+    assert_eq!(attempts(&result), [3.0, 4.0, 11.0]);
+}
+
+#[test]
+fn test_reroll_pool_returns_last_attempt_when_out_of_rerolls() {
+    let result = reroll_pool_with("reroll_pool(2d6, <7, 2)", &[3, 3, 1, 1, 2, 2]);
+    assert_eq!(result.total, 4.0);
+    assert_eq!(attempts(&result), [6.0, 2.0, 4.0]);
+}
+
+#[test]
+fn test_reroll_pool_with_no_rerolls_rolls_once() {
+    let result = reroll_pool_with("reroll_pool(2d6, <=12, 0)", &[1, 1]);
+    assert_eq!(result.total, 2.0);
+}
+
+#[test]
+fn test_reroll_pool_display() {
+    let ast = parse(&"reroll_pool(4d6, >=(10 + 2), 3)").unwrap();
+    assert_eq!(ast.to_string(), "reroll_pool(4d6, >=(10 + 2), 3)");
+    let ast = parse(&"reroll_pool(4d6, <10, 3)").unwrap();
+    assert_eq!(ast.to_string(), "reroll_pool(4d6, <10, 3)");
+}
+
+#[test]
+fn test_reroll_pool_errors() {
+    assert!(roll(&"reroll_pool(4d6, 10, 3)").is_err());
+    assert!(roll(&"reroll_pool(4d6, <10)").is_err());
+    assert!(roll(&"<10").is_err());
+    assert!(roll(&"clamp(<10, 1..2)").is_err());
+}
+
+// ============================================================================
+// Functions - gate
+// ============================================================================
+
+/// Rolls `gate((1d20 >= 10), 2d6)` with the d20 on `check` and the d6s on 3
+/// and 5, returning the result and the RNG draws taken.
+fn gate_with(check: u32, lazy_branches: bool) -> (rollatorium::EvalResult, usize) {
+    let ast = parse(&"gate((1d20 >= 10), 2d6)").unwrap();
+    let mut faces = vec![SequenceRng::face(20, check)];
+    if check >= 10 || !lazy_branches {
+        faces.extend([SequenceRng::face(6, 3), SequenceRng::face(6, 5)]);
+    }
+    let mut rng = CountingRng {
+        inner: SequenceRng::once(faces),
+        draws: 0,
+    };
+    let config = EvalConfig {
+        lazy_branches,
+        ..EvalConfig::default()
+    };
+    let result = eval_with_rng(&ast, config, &mut rng).unwrap();
+    (result, rng.draws)
+}
+
+#[test]
+fn test_gate_passes_value_on_success() {
+    let (result, draws) = gate_with(14, false);
+    assert_eq!(result.total, 8.0);
+    assert_eq!(draws, 3);
+}
+
+#[test]
+fn test_gate_zeroes_value_on_failure() {
+    let (result, draws) = gate_with(4, false);
+    assert_eq!(result.total, 0.0);
+    assert_eq!(draws, 3);
+    let Value::Call { name, args } = &result.value else {
+        panic!("expected call result, got {:?}", result.value);
+    };
+    assert_eq!(name, "gate");
+    assert_eq!(args[0].total, 0.0);
+    assert_eq!(args[1].total, 8.0);
+}
+
+#[test]
+fn test_gate_lazy_skips_value_on_failure() {
+    let (result, draws) = gate_with(4, true);
+    assert_eq!(result.total, 0.0);
+    assert_eq!(draws, 1);
+    let (result, draws) = gate_with(10, true);
+    assert_eq!(result.total, 8.0);
+    assert_eq!(draws, 3);
+}
+
+#[test]
+fn test_gate_errors() {
+    assert!(roll(&"gate(1)").is_err());
+    assert!(roll(&"gate(1, 2, 3)").is_err());
+}
+
+// ============================================================================
+// Functions - min, max, abs and rounding
+// ============================================================================
+
+#[test]
+fn test_max_takes_the_better_roll() {
+    for (face, expected) in [(2, 10.0), (12, 17.0)] {
+        let result = eval_faces("max(1d20 + 5, 10)", 20, &[face], EvalConfig::default());
+        assert_eq!(result.total, expected);
+        let Value::Call { name, args } = &result.value else {
+            panic!("expected call result, got {:?}", result.value);
+        };
+        assert_eq!(name, "max");
+        assert_eq!(args.len(), 2);
+    }
+}
+
+#[test]
+fn test_min_and_max_take_any_number_of_arguments() {
+    assert_eq!(r("min(4)"), 4.0);
+    assert_eq!(r("min(3, -2, 7)"), -2.0);
+    assert_eq!(r("max(3, -2, 7)"), 7.0);
+}
+
+#[test]
+fn test_abs_and_rounding() {
+    assert_eq!(r("abs(2 - 5)"), 3.0);
+    assert_eq!(r("floor(7 / 2)"), 3.0);
+    assert_eq!(r("ceil(7 / 2)"), 4.0);
+    assert_eq!(r("round(7 / 2)"), 4.0);
+    assert_eq!(r("round(-7 / 2)"), -4.0);
+    assert_eq!(r("floor(-7 / 2)"), -4.0);
+}
+
+#[test]
+fn test_min_max_and_rounding_errors() {
+    for input in ["min()", "max()", "abs()", "floor(1, 2)", "round(1, 2)"] {
+        assert!(roll(&input).is_err(), "{}", input);
+    }
+    assert!(roll(&"sqrt(4)").is_err());
+}
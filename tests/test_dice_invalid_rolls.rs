@@ -22,3 +22,147 @@ fn test_zero_sided_die() {
 fn test_invalid_minimum() {
     let _ = r("10d6mil1");
 }
+
+#[test]
+#[should_panic(expected = "Missing operator between expressions")]
+fn test_adjacent_dice_pools() {
+    let _ = r("1d6 2d6");
+}
+
+#[test]
+#[should_panic(expected = "Missing operator between expressions")]
+fn test_adjacent_numbers() {
+    let _ = r("2 3");
+}
+
+#[test]
+#[should_panic(expected = "Unexpected trailing input")]
+fn test_trailing_close_paren() {
+    let _ = r("2 + 3)");
+}
+
+#[test]
+#[should_panic(expected = "Expected a digit after '.' at position 5")]
+fn test_bare_dot_selector_target() {
+    let _ = r("1d6k<.");
+}
+
+#[test]
+#[should_panic(expected = "Expected a digit after '.' at position 0")]
+fn test_bare_dot() {
+    let _ = r(".");
+}
+
+// ============================================================================
+// Invalid Rolls - Error Spans
+// ============================================================================
+
+#[test]
+fn test_parser_error_points_at_token() {
+    let err = rollatorium::parse(&"1d6 + * 2").unwrap_err();
+    assert_eq!(err.span(), Some(6..7));
+    assert_eq!(
+        err.caret_diagnostic("1d6 + * 2").unwrap(),
+        "1d6 + * 2\n      ^"
+    );
+
+    let err = rollatorium::parse(&"1d6 2d6").unwrap_err();
+    assert_eq!(err.span(), Some(4..5));
+    let err = rollatorium::parse(&"max(1, 1d6 2").unwrap_err();
+    assert_eq!(err.span(), Some(11..12));
+}
+
+#[test]
+fn test_parser_error_at_end_of_input() {
+    let err = rollatorium::parse(&"1d6 +").unwrap_err();
+    assert_eq!(err.span(), Some(5..5));
+    assert_eq!(err.caret_diagnostic("1d6 +").unwrap(), "1d6 +\n     ^");
+}
+
+#[test]
+fn test_lexer_error_points_at_text() {
+    let err = rollatorium::parse(&"1d6 = 3").unwrap_err();
+    assert!(matches!(err, rollatorium::RollatoriumError::Lexer { .. }));
+    assert_eq!(err.span(), Some(4..5));
+    let err = rollatorium::parse(&"2 + 1.").unwrap_err();
+    assert_eq!(err.span(), Some(4..6));
+    let err = rollatorium::parse(&"1d6 [fire").unwrap_err();
+    assert_eq!(err.span(), Some(5..9));
+}
+
+#[test]
+fn test_eval_errors_have_no_span() {
+    let err = rollatorium::roll(&"6d0").unwrap_err();
+    assert_eq!(err.span(), None);
+    assert_eq!(err.caret_diagnostic("6d0"), None);
+}
+
+// ============================================================================
+// Invalid Rolls - Nesting Depth
+// ============================================================================
+
+fn nesting_error(input: &str) -> String {
+    match rollatorium::parse(&input).unwrap_err() {
+        rollatorium::RollatoriumError::Parser { message, .. } => message,
+        other => panic!("expected a parser error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_deep_parentheses_error_cleanly() {
+    let input = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+    assert!(nesting_error(&input).contains("too deeply nested"));
+}
+
+#[test]
+fn test_long_operator_chains_error_cleanly() {
+    for operator in ["+", "*", "=="] {
+        let input = format!("{}1", format!("1{}", operator).repeat(100_000));
+        assert!(nesting_error(&input).contains("too deeply nested"));
+    }
+    assert_eq!(r(&format!("{}1", "1+".repeat(128))), 129.0);
+}
+
+#[test]
+fn test_long_sign_and_power_chains_error_cleanly() {
+    assert!(nesting_error(&format!("{}1", "-".repeat(10_000))).contains("too deeply nested"));
+    assert!(nesting_error(&"2^".repeat(10_000)).contains("too deeply nested"));
+    let selector = format!("4d6k>{}1", "-".repeat(10_000));
+    assert!(nesting_error(&selector).contains("too deeply nested"));
+}
+
+#[test]
+fn test_deep_calls_and_brackets_error_cleanly() {
+    let inputs = [
+        format!("{}1{}", "max(".repeat(10_000), ")".repeat(10_000)),
+        format!("{}6{}", "1d(".repeat(10_000), ")".repeat(10_000)),
+        format!("{}6{}", "1d[".repeat(10_000), "]".repeat(10_000)),
+        format!("4d6k>{}3{}", "(".repeat(10_000), ")".repeat(10_000)),
+    ];
+    for input in inputs {
+        assert!(nesting_error(&input).contains("too deeply nested"));
+    }
+}
+
+#[test]
+fn test_nesting_up_to_the_limit_still_parses() {
+    let parens = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    assert_eq!(r(&parens(128)), 1.0);
+    assert!(nesting_error(&parens(129)).contains("too deeply nested"));
+    assert_eq!(r(&format!("{}1", "-".repeat(128))), 1.0);
+    assert!(nesting_error(&format!("{}1", "-".repeat(129))).contains("too deeply nested"));
+    assert_eq!(r(&format!("{}1", "1^".repeat(128))), 1.0);
+    assert_eq!(
+        r(&format!("{}1{}", "max(".repeat(128), ")".repeat(128))),
+        1.0
+    );
+}
+
+#[test]
+fn test_signs_and_parentheses_each_count_once() {
+    // `-(` is two levels, so 64 of them reach the limit.
+    let input = format!("{}1{}", "-(".repeat(64), ")".repeat(64));
+    assert_eq!(r(&input), 1.0);
+    let input = format!("{}1{}", "-(".repeat(65), ")".repeat(65));
+    assert!(nesting_error(&input).contains("too deeply nested"));
+}
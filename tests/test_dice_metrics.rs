@@ -0,0 +1,65 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::testing::SequenceRng;
+use rollatorium::{EvalConfig, EvalMetrics, eval_with_metrics, eval_with_rng, parse};
+
+// ============================================================================
+// Evaluation Metrics
+// ============================================================================
+
+fn metrics_of(expr: &str) -> EvalMetrics {
+    let ast = parse(&expr).unwrap();
+    eval_with_metrics(&ast, EvalConfig::default(), StdRng::seed_from_u64(1))
+        .unwrap()
+        .1
+}
+
+#[test]
+fn test_metrics_of_keep_highest() {
+    let metrics = metrics_of("4d6kh3");
+    // The pool, its quantity, its size and the selector target.
+    assert_eq!(metrics.eval_calls, 4);
+    assert_eq!(metrics.dice_rolled, 4);
+    assert_eq!(metrics.selections, 1);
+}
+
+#[test]
+fn test_metrics_of_arithmetic() {
+    let metrics = metrics_of("1 + 2 * 3");
+    assert_eq!(metrics.eval_calls, 5);
+    assert_eq!(metrics.dice_rolled, 0);
+    assert_eq!(metrics.selections, 0);
+}
+
+#[test]
+fn test_metrics_count_every_selection_pass() {
+    let metrics = metrics_of("(1, 2, 3)kh1 + 4d6kh3kl2");
+    assert_eq!(metrics.selections, 3);
+}
+
+#[test]
+fn test_metrics_leave_result_unchanged() {
+    let ast = parse(&"8d10e10kh4").unwrap();
+    let (result, metrics) =
+        eval_with_metrics(&ast, EvalConfig::default(), StdRng::seed_from_u64(9)).unwrap();
+    let plain = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(9)).unwrap();
+    assert_eq!(result.total, plain.total);
+    assert!(metrics.dice_rolled >= 8);
+}
+
+#[test]
+fn test_metrics_count_plain_dice_rolled() {
+    assert_eq!(metrics_of("3d6").dice_rolled, 3);
+}
+
+#[test]
+fn test_metrics_count_explosion_rolls() {
+    let ast = parse(&"1d6e==6").unwrap();
+    let (result, metrics) = eval_with_metrics(
+        &ast,
+        EvalConfig::default(),
+        SequenceRng::from_faces(6, &[6, 6, 2]),
+    )
+    .unwrap();
+    assert_eq!(result.total, 14.0);
+    assert_eq!(metrics.dice_rolled, 3);
+}
@@ -0,0 +1,40 @@
+use rollatorium::{EvalConfig, Value, eval_with_namespace, parse};
+
+// ============================================================================
+// Namespaced RNG Streams
+// ============================================================================
+
+fn faces(seed: u64, namespace: &str) -> Vec<f64> {
+    let ast = parse(&"20d20").unwrap();
+    let result = eval_with_namespace(&ast, EvalConfig::default(), seed, namespace).unwrap();
+    let Value::Dice(roll) = result.value else {
+        panic!("expected a dice roll");
+    };
+    roll.dice.iter().map(|die| die.value).collect()
+}
+
+#[test]
+fn test_same_seed_and_namespace_reproduce() {
+    assert_eq!(faces(7, "Alice"), faces(7, "Alice"));
+}
+
+#[test]
+fn test_namespaces_diverge() {
+    assert_ne!(faces(7, "Alice"), faces(7, "Bob"));
+    assert_ne!(faces(7, "Alice"), faces(7, ""));
+}
+
+#[test]
+fn test_seeds_diverge() {
+    assert_ne!(faces(7, "Alice"), faces(8, "Alice"));
+}
+
+#[test]
+fn test_namespace_respects_config() {
+    let config = EvalConfig {
+        max_rolls: 2,
+        ..EvalConfig::default()
+    };
+    let ast = parse(&"3d6").unwrap();
+    assert!(eval_with_namespace(&ast, config, 7, "Alice").is_err());
+}
@@ -1,7 +1,7 @@
 mod common;
 use common::r;
 
-use rollatorium::roll;
+use rollatorium::{BinaryOperator, RollatoriumError, parse, roll};
 
 // ============================================================================
 // Node Tests - Binary Operations
@@ -103,14 +103,173 @@ fn test_div_zero_slash() {
 #[test]
 fn test_div_zero_double_slash() {
     // Integer division by zero should error
-    // TODO: Check if this should panic or return an error
     let result = roll(&"10 // 0");
-    assert!(result.is_err() || result.unwrap().total.is_infinite());
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_div_zero_double_slash_message() {
+    let error = roll(&"10 // (2 - 2)").unwrap_err();
+    assert!(matches!(
+        error,
+        RollatoriumError::DivideByZero {
+            operator: BinaryOperator::IntDivide,
+            ..
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Evaluation error: Integer division by zero in '10 // (2 - 2)'"
+    );
+}
+
+#[test]
+fn test_ceil_divide() {
+    assert_eq!(r("7 /^ 2"), 4.0);
+    assert_eq!(r("8 /^ 2"), 4.0);
+    assert_eq!(r("-7 /^ 2"), -3.0);
+    assert_eq!(r("1 + 7 /^ 2 * 3"), 13.0);
+    assert_eq!(parse(&"7/^2").unwrap().to_string(), "7 /^ 2");
+    for _ in 0..100 {
+        let val = r("1d20 /^ 2");
+        assert!((1.0..=10.0).contains(&val) && val.fract() == 0.0, "{}", val);
+    }
+}
+
+#[test]
+fn test_div_zero_ceil_divide() {
+    let error = roll(&"10 /^ 0").unwrap_err();
+    assert!(matches!(
+        error,
+        RollatoriumError::DivideByZero {
+            operator: BinaryOperator::CeilDivide,
+            ..
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Evaluation error: Ceiling division by zero in '10 /^ 0'"
+    );
 }
 
 #[test]
 fn test_div_zero_modulo() {
-    // Modulo by zero should be NaN
-    let result = r("10 % 0");
-    assert!(result.is_nan());
+    let error = roll(&"10 % 0").unwrap_err();
+    assert!(matches!(
+        error,
+        RollatoriumError::DivideByZero {
+            operator: BinaryOperator::Modulo,
+            ..
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Evaluation error: Modulo by zero in '10 % 0'"
+    );
+}
+
+// ============================================================================
+// Precision Loss Diagnostics
+// ============================================================================
+
+fn precision_diagnostics(expr: &str, warn: bool) -> Vec<rollatorium::Diagnostic> {
+    use rand::{SeedableRng, rngs::StdRng};
+    use rollatorium::{EvalConfig, eval_outcome, parse};
+
+    let config = EvalConfig {
+        warn_precision_loss: warn,
+        ..EvalConfig::default()
+    };
+    let ast = parse(&expr).unwrap();
+    eval_outcome(&ast, config, StdRng::seed_from_u64(0))
+        .unwrap()
+        .diagnostics
+}
+
+#[test]
+fn test_huge_product_warns_precision_loss() {
+    let diagnostics = precision_diagnostics("99999999999 * 99999999999", true);
+    assert!(matches!(
+        diagnostics.as_slice(),
+        [rollatorium::Diagnostic::PrecisionLoss { total, .. }] if *total > 9.0e21
+    ));
+}
+
+#[test]
+fn test_huge_product_silent_without_flag() {
+    assert!(precision_diagnostics("99999999999 * 99999999999", false).is_empty());
+}
+
+#[test]
+fn test_safe_arithmetic_does_not_warn() {
+    assert!(precision_diagnostics("1000 * 1000 + 4d6", true).is_empty());
+}
+
+// ============================================================================
+// Total Rounding
+// ============================================================================
+
+fn rounded(expr: &str, rule: rollatorium::RoundingRule) -> rollatorium::EvalResult {
+    use rand::{SeedableRng, rngs::StdRng};
+    use rollatorium::{EvalConfig, eval_with_rng, parse};
+
+    let config = EvalConfig {
+        total_rounding: rule,
+        ..EvalConfig::default()
+    };
+    eval_with_rng(&parse(&expr).unwrap(), config, StdRng::seed_from_u64(0)).unwrap()
+}
+
+#[test]
+fn test_total_rounding_rules() {
+    use rollatorium::RoundingRule::*;
+
+    for (rule, expected) in [
+        (None, 3.5),
+        (Down, 3.0),
+        (Up, 4.0),
+        (Nearest, 4.0),
+        (HalfEven, 4.0),
+    ] {
+        assert_eq!(rounded("7/2", rule).total, expected, "{:?}", rule);
+    }
+    assert_eq!(rounded("5/2", HalfEven).total, 2.0);
+    assert_eq!(rounded("5/2", Nearest).total, 3.0);
+    assert_eq!(rounded("-7/2", Down).total, -4.0);
+}
+
+#[test]
+fn test_total_rounding_leaves_breakdown() {
+    use rollatorium::Value;
+
+    let result = rounded("7/2", rollatorium::RoundingRule::Down);
+    let Value::Binary { left, right, .. } = &result.value else {
+        panic!("expected binary result, got {:?}", result.value);
+    };
+    assert_eq!(left.total / right.total, 3.5);
+}
+
+#[test]
+fn test_div_zero_by_zero() {
+    let error = roll(&"0 / 0").unwrap_err();
+    assert!(matches!(
+        error,
+        RollatoriumError::DivideByZero {
+            operator: BinaryOperator::Divide,
+            ..
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Evaluation error: Division by zero in '0 / 0'"
+    );
+}
+
+#[test]
+fn test_nan_totals_error() {
+    assert!(roll(&"10 / 0 - 10 / 0").is_err());
+    assert!(roll(&"(0 - 8) ^ 0.5").is_err());
+    assert!(roll(&"(1/0, -1/0)").is_err());
+    assert!(roll(&"(1/0, 0-1/0)kh2").is_err());
+    assert!(roll(&"margin(1/0, 1/0)").is_err());
 }
@@ -1,5 +1,9 @@
 mod common;
-use common::r;
+use common::{eval_faces, r, roll_faces};
+
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::testing::SequenceRng;
+use rollatorium::{EvalConfig, EvalResult, Value, eval_with_rng, parse};
 // ============================================================================
 // Node Tests - Dice
 // ============================================================================
@@ -65,3 +69,327 @@ fn test_two_d_percent() {
         assert!((0.0..=180.0).contains(&val), "2d% out of range: {}", val);
     }
 }
+
+// ============================================================================
+// Node Tests - Percentile Dice
+// ============================================================================
+
+#[test]
+fn test_d100_and_percentile_roll_one_to_a_hundred() {
+    for expr in ["d100", "d%%"] {
+        let mut seen = [false; 100];
+        for seed in 0..2000 {
+            let val = rollatorium::roll_with_seed(&expr, seed).unwrap().total;
+            assert!(
+                (1.0..=100.0).contains(&val),
+                "{} out of range: {}",
+                expr,
+                val
+            );
+            seen[val as usize - 1] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen), "{} missed a face", expr);
+    }
+}
+
+#[test]
+fn test_percent_stays_a_tens_die() {
+    for seed in 0..200 {
+        let val = rollatorium::roll_with_seed(&"d%", seed).unwrap().total;
+        assert!((0.0..=90.0).contains(&val), "d% out of range: {}", val);
+        assert_eq!(val % 10.0, 0.0, "d% not a multiple of 10: {}", val);
+    }
+}
+
+#[test]
+fn test_percentile_faces_and_display() {
+    let ast = parse(&"2d%%kh1").unwrap();
+    assert_eq!(ast.to_string(), "2d%%kh1");
+    let rng = SequenceRng::from_faces(100, &[37, 100]);
+    assert_eq!(
+        eval_with_rng(&ast, EvalConfig::default(), rng)
+            .unwrap()
+            .total,
+        100.0
+    );
+    let expected: Vec<f64> = (1..=100).map(f64::from).collect();
+    assert_eq!(faces_of("1d%%"), expected);
+    assert_eq!(parse(&"1d% % 10").unwrap().to_string(), "1d% % 10");
+}
+
+// ============================================================================
+// Node Tests - Expression-Sized Dice
+// ============================================================================
+
+#[test]
+fn test_expression_sized_die() {
+    for _ in 0..1000 {
+        let val = r("1d(1d6)");
+        assert!((1.0..=6.0).contains(&val), "1d(1d6) out of range: {}", val);
+    }
+}
+
+#[test]
+fn test_expression_sized_die_display() {
+    let ast = rollatorium::parse(&"2d(1d4+2)kh1").unwrap();
+    assert_eq!(ast.to_string(), "2d(1d4 + 2)kh1");
+}
+
+#[test]
+fn test_expression_sized_die_rolls_its_faces() {
+    let result = eval_faces("2d(2*3)", 6, &[6, 4], EvalConfig::default());
+    assert_eq!(result.total, 10.0);
+}
+
+#[test]
+fn test_expression_quantity() {
+    let (result, roll) = roll_faces("(1+1)d6", 6, &[2, 5], EvalConfig::default());
+    assert_eq!(result.total, 7.0);
+    assert_eq!(roll.quantity, 2);
+}
+
+#[test]
+fn test_expression_quantity_display() {
+    for input in ["(1 + 1)d6kh1", "(1d4)d6", "(2 * 2)d(1 + 5) + 1"] {
+        let ast = parse(&input).unwrap();
+        assert_eq!(ast.to_string(), input);
+    }
+    assert_eq!(parse(&"(3)d6").unwrap().to_string(), "3d6");
+    assert!(parse(&"(1, 2)d6").is_err());
+}
+
+// ============================================================================
+// Node Tests - Table Dice
+// ============================================================================
+
+#[test]
+fn test_table_die_within_entry_ranges() {
+    for _ in 0..1000 {
+        let val = r("1d[1d4, 2d6, 1d8]");
+        assert!(
+            (1.0..=12.0).contains(&val),
+            "table die out of range: {}",
+            val
+        );
+    }
+}
+
+#[test]
+fn test_table_die_disjoint_entries() {
+    for _ in 0..1000 {
+        let val = r("2d[1, 10 + 1d4, 100]");
+        let singles = [1.0, 11.0, 12.0, 13.0, 14.0, 100.0];
+        let possible = singles
+            .iter()
+            .any(|a| singles.iter().any(|b| (a + b - val).abs() < 1e-9));
+        assert!(
+            possible,
+            "table dice total not in union of entries: {}",
+            val
+        );
+    }
+}
+
+#[test]
+fn test_table_die_keeps_highest_entry() {
+    for _ in 0..1000 {
+        assert_eq!(r("3d[5, 5, 5]kh1"), 5.0);
+        let val = r("4d[1, 20]kh1");
+        assert!(val == 1.0 || val == 20.0);
+    }
+}
+
+#[test]
+fn test_table_die_nested() {
+    for _ in 0..1000 {
+        let val = r("1d[1d[1, 2], 3]");
+        assert!(
+            (1.0..=3.0).contains(&val),
+            "nested table out of range: {}",
+            val
+        );
+    }
+}
+
+#[test]
+fn test_table_die_records_chosen_entry() {
+    let ast = parse(&"3d[1d4, 2d6] [loot]").unwrap();
+    assert_eq!(ast.to_string(), "3d[1d4, 2d6] [loot]");
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(5)).unwrap();
+    let Value::Annotated { expr, .. } = &result.value else {
+        panic!("expected annotated result");
+    };
+    let Value::Dice(roll) = &expr.value else {
+        panic!("expected dice result");
+    };
+    assert_eq!(roll.size, 2);
+    for die in &roll.dice {
+        let entry = die.entry.as_ref().expect("table dice record their entry");
+        assert_eq!(entry.total, die.value);
+        assert!(matches!(entry.value, Value::Dice(_)));
+    }
+    let total: f64 = roll.dice.iter().map(|die| die.value).sum();
+    assert_eq!(result.total, total);
+}
+
+#[test]
+fn test_table_die_breakdown() {
+    let result = rollatorium::roll(&"1d[7]").unwrap();
+    assert_eq!(result.to_ansi(), "1d[7] (7) = 7");
+    let result = rollatorium::roll(&"2d[3]kh1").unwrap();
+    assert_eq!(result.to_ansi(), "2d[3]kh1 (3, \x1b[2m3\x1b[0m) = 3");
+}
+
+#[test]
+fn test_table_die_errors() {
+    assert!(rollatorium::parse(&"1d[]").is_err());
+    assert!(rollatorium::roll(&"1d[1, 2]e2").is_err());
+    assert!(rollatorium::roll(&"1d[1, 2]rr1").is_err());
+}
+
+#[test]
+fn test_table_die_bracket_must_touch_d() {
+    for _ in 0..100 {
+        let val = r("d[1, 2, 3]");
+        assert!(
+            (1.0..=3.0).contains(&val),
+            "table die out of range: {}",
+            val
+        );
+    }
+    assert!(rollatorium::parse(&"1d [1, 2, 3]").is_err());
+    for input in ["1d6[fire]", "1d6 [fire]"] {
+        let result = rollatorium::roll(&input).unwrap();
+        let Value::Annotated { expr, annotations } = &result.value else {
+            panic!("expected {} to be annotated, got {:?}", input, result.value);
+        };
+        assert_eq!(annotations[0].text, "fire");
+        assert!(matches!(expr.value, Value::Dice(_)));
+    }
+}
+
+#[test]
+fn test_table_die_bounded_by_max_rolls() {
+    assert!(rollatorium::roll(&"600d[1d6, 2d6]").is_err());
+}
+
+// ============================================================================
+// Node Tests - Percent Dice Operations
+// ============================================================================
+
+/// Rolls `expr` with percent dice landing on `values`, each a multiple of 10.
+fn roll_percent(expr: &str, values: &[u32]) -> EvalResult {
+    let faces: Vec<_> = values.iter().map(|value| value / 10 + 1).collect();
+    eval_faces(expr, 10, &faces, EvalConfig::default())
+}
+
+#[test]
+fn test_percent_keep_highest() {
+    assert_eq!(roll_percent("3d%kh1", &[40, 90, 0]).total, 90.0);
+}
+
+#[test]
+fn test_percent_selectors_compare_scaled_values() {
+    // Thresholds apply to the 0-90 values, not the raw 0-9 draw.
+    assert_eq!(roll_percent("3d%k>30", &[40, 30, 90]).total, 130.0);
+    assert_eq!(roll_percent("3d%k>3", &[40, 0, 90]).total, 130.0);
+    assert_eq!(roll_percent("3d%p==90", &[90, 10, 90]).total, 10.0);
+}
+
+#[test]
+fn test_percent_reroll_below_threshold() {
+    assert_eq!(roll_percent("2d%rr<20", &[10, 50, 0, 20]).total, 70.0);
+}
+
+#[test]
+fn test_percent_explode_on_ninety() {
+    assert_eq!(roll_percent("d%e==90", &[90, 90, 30]).total, 210.0);
+}
+
+#[test]
+fn test_percent_faces_are_tens_from_zero_to_ninety() {
+    let mut seen: Vec<f64> = (1..=10)
+        .map(|face| eval_faces("1d%", 10, &[face], EvalConfig::default()).total)
+        .collect();
+    seen.dedup();
+    let expected: Vec<f64> = (0..10).map(|ten| ten as f64 * 10.0).collect();
+    assert_eq!(seen, expected);
+    assert_eq!(faces_of("1d%"), expected);
+}
+
+#[test]
+fn test_percent_operations_stay_scaled() {
+    for _ in 0..1000 {
+        for expr in ["3d%kh1", "2d%rr<20", "d%e==90"] {
+            let val = r(expr);
+            assert_eq!(val % 10.0, 0.0, "{} not multiple of 10: {}", expr, val);
+        }
+    }
+}
+
+// ============================================================================
+// Node Tests - Possible Faces
+// ============================================================================
+
+fn faces_of(expr: &str) -> Vec<f64> {
+    match rollatorium::roll(&expr).unwrap().value {
+        Value::Dice(roll) => roll.possible_faces(),
+        other => panic!("expected dice result, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_possible_faces_of_standard_dice() {
+    assert_eq!(faces_of("2d6"), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    assert_eq!(faces_of("1d1"), vec![1.0]);
+    assert_eq!(faces_of("1d(2 + 2)kh1"), vec![1.0, 2.0, 3.0, 4.0]);
+}
+
+#[test]
+fn test_possible_faces_of_percent_dice() {
+    let expected: Vec<f64> = (0..10).map(|n| n as f64 * 10.0).collect();
+    assert_eq!(faces_of("d%"), expected);
+}
+
+#[test]
+fn test_table_dice_have_no_fixed_faces() {
+    assert!(faces_of("1d[1, 2d6]").is_empty());
+}
+
+// ============================================================================
+// Node Tests - Fudge Dice
+// ============================================================================
+
+#[test]
+fn test_sane_total_4d_fudge() {
+    for _ in 0..1000 {
+        let val = r("4dF");
+        assert!((-4.0..=4.0).contains(&val), "4dF out of range: {}", val);
+        assert_eq!(val.fract(), 0.0);
+    }
+}
+
+#[test]
+fn test_fudge_faces() {
+    assert_eq!(faces_of("4dF"), vec![-1.0, 0.0, 1.0]);
+    let (result, roll) = roll_faces("4dF", 3, &[1, 2, 3, 3], EvalConfig::default());
+    assert_eq!(result.total, 1.0);
+    let values: Vec<f64> = roll.dice.iter().map(|die| die.value).collect();
+    assert_eq!(values, [-1.0, 0.0, 1.0, 1.0]);
+}
+
+#[test]
+fn test_fudge_display_and_operations() {
+    assert_eq!(parse(&"dF").unwrap().to_string(), "dF");
+    assert_eq!(parse(&"4dF kh2").unwrap().to_string(), "4dF kh2");
+    let result = eval_faces("4dF kh2", 3, &[1, 3, 2, 3], EvalConfig::default());
+    assert_eq!(result.total, 2.0);
+}
+
+#[test]
+fn test_fudge_is_not_a_custom_name() {
+    assert!(parse(&"4dFate").is_ok());
+    assert!(rollatorium::roll(&"4dFate").is_err());
+    let mut dice = rollatorium::DiceRegistry::new();
+    assert!(dice.register("F", [1.0, 2.0]).is_err());
+}
@@ -28,3 +28,32 @@ fn test_literal_float_zero_point_five() {
 fn test_literal_float_dot_five() {
     assert_eq!(r(".5"), 0.5);
 }
+
+// ============================================================================
+// Node Tests - Literals - Scientific Notation
+// ============================================================================
+
+#[test]
+fn test_literal_exponent() {
+    assert_eq!(r("1e3"), 1000.0);
+    assert_eq!(r("2.5e-1"), 0.25);
+    assert_eq!(r("1.5E+2"), 150.0);
+    assert_eq!(r("2 * 1e2 + 1"), 201.0);
+    assert_eq!(r("1e1d1"), 10.0);
+}
+
+#[test]
+fn test_e_after_dice_still_explodes() {
+    for input in ["1d6e6", "4d6kh3rr<2e6", "1d1e1kh2"] {
+        let ast = rollatorium::parse(&input).unwrap();
+        assert_eq!(ast.to_string(), input);
+    }
+    let result = rollatorium::roll(&"1d6e6").unwrap();
+    assert!(result.total >= 1.0);
+}
+
+#[test]
+fn test_literal_exponent_needs_digits() {
+    assert!(rollatorium::parse(&"2e+").is_err());
+    assert!(rollatorium::parse(&"3e400").is_err());
+}
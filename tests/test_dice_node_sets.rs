@@ -1,5 +1,7 @@
 mod common;
-use common::r;
+use common::{eval_faces, r};
+use rollatorium::{EvalConfig, SetRoll, Value};
+
 // ============================================================================
 // Node Tests - Sets
 // ============================================================================
@@ -18,3 +20,38 @@ fn test_single_element_set_with_comma() {
 fn test_two_element_set() {
     assert_eq!(r("(1, 1)"), 2.0);
 }
+
+// ============================================================================
+// Node Tests - Sets of Checks
+// ============================================================================
+
+fn checks(expr: &str, faces: &[u32]) -> (f64, SetRoll) {
+    let result = eval_faces(expr, 20, faces, EvalConfig::default());
+    match result.value {
+        Value::Set(set) => (result.total, set),
+        other => panic!("expected set result, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_set_of_checks_counts_successes() {
+    let (total, set) = checks("(1d20 >= 10, 1d20 >= 15, 1d20 >= 5)", &[12, 14, 5]);
+    assert_eq!(total, 2.0);
+    assert_eq!(set.success_count(), 2);
+}
+
+#[test]
+fn test_set_of_checks_keeps_successes() {
+    let (total, set) = checks("(1d20>=10, 1d20>=15)k>0", &[3, 18]);
+    assert_eq!(total, 1.0);
+    assert_eq!(set.success_count(), 1);
+    assert!(!set.elements[0].kept);
+    assert!(set.elements[1].kept);
+}
+
+#[test]
+fn test_success_count_ignores_dropped_elements() {
+    let (total, set) = checks("(1d20 >= 10, 1d20 >= 10)p>0", &[11, 19]);
+    assert_eq!(total, 0.0);
+    assert_eq!(set.success_count(), 0);
+}
@@ -44,3 +44,50 @@ fn comparison_without_parentheses() {
 fn comparison_with_parentheses_affects_sum() {
     assert_eq!(r("1 + (2 == 2)"), 2.0);
 }
+
+// ============================================================================
+// PEMDAS - Exponentiation
+// ============================================================================
+
+#[test]
+fn power_result() {
+    assert_eq!(r("2 ^ 3"), 8.0);
+    assert_eq!(r("2 ** 3"), 8.0);
+    assert_eq!(r("4 ^ 0.5"), 2.0);
+}
+
+#[test]
+fn power_right_associative() {
+    assert_eq!(r("2 ^ 3 ^ 2"), 512.0);
+    assert_eq!(r("2 ^ 3 ^ 2"), r("2 ^ (3 ^ 2)"));
+    assert_eq!(r("(2 ^ 3) ^ 2"), 64.0);
+}
+
+#[test]
+fn power_binds_tighter_than_multiplication() {
+    assert_eq!(r("3 * 2 ^ 2"), 12.0);
+    assert_eq!(r("2 ^ 2 * 3"), r("(2 ^ 2) * 3"));
+    assert_eq!(r("1 + 2 ^ 3"), 9.0);
+    assert_eq!(r("16 / 2 ^ 2"), 4.0);
+}
+
+#[test]
+fn power_applies_after_unary() {
+    assert_eq!(r("-2 ^ 2"), 4.0);
+    assert_eq!(r("2 ^ -1"), 0.5);
+}
+
+#[test]
+fn power_display_round_trips() {
+    for (input, shown) in [
+        ("2 ^ 3 ^ 2", "2 ^ 3 ^ 2"),
+        ("(2 ^ 3) ^ 2", "(2 ^ 3) ^ 2"),
+        ("2 ** 3", "2 ^ 3"),
+        ("-(2 ^ 2)", "-(2 ^ 2)"),
+        ("(3 * 2) ^ 2", "(3 * 2) ^ 2"),
+    ] {
+        let ast = rollatorium::parse(&input).unwrap();
+        assert_eq!(ast.to_string(), shown);
+        assert_eq!(rollatorium::parse(&shown).unwrap(), ast);
+    }
+}
@@ -0,0 +1,105 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{Diagnostic, EvalConfig, Value, eval_outcome, eval_with_rng, parse};
+
+// ============================================================================
+// Dice Quantity Limits
+// ============================================================================
+
+fn capped_config(clamp: bool) -> EvalConfig {
+    EvalConfig {
+        max_dice: 10,
+        clamp_quantity_to_max: clamp,
+        ..EvalConfig::default()
+    }
+}
+
+#[test]
+fn test_over_cap_quantity_errors_by_default() {
+    let ast = parse(&"25d6").unwrap();
+    let err = eval_with_rng(&ast, capped_config(false), StdRng::seed_from_u64(1)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Evaluation error: Dice quantity 25 exceeds the maximum of 10"
+    );
+}
+
+#[test]
+fn test_over_cap_quantity_clamps_with_diagnostic() {
+    let ast = parse(&"25d6").unwrap();
+    let outcome = eval_outcome(&ast, capped_config(true), StdRng::seed_from_u64(1)).unwrap();
+    match &outcome.result.value {
+        Value::Dice(roll) => {
+            assert_eq!(roll.quantity, 10);
+            assert_eq!(roll.dice.len(), 10);
+        }
+        other => panic!("expected dice result, got {:?}", other),
+    }
+    assert_eq!(
+        outcome.diagnostics,
+        [Diagnostic::QuantityClamped {
+            requested: 25,
+            clamped: 10,
+        }]
+    );
+}
+
+#[test]
+fn test_quantity_at_cap_is_untouched() {
+    let ast = parse(&"10d6").unwrap();
+    let outcome = eval_outcome(&ast, capped_config(true), StdRng::seed_from_u64(1)).unwrap();
+    assert!(outcome.diagnostics.is_empty());
+    assert!((10.0..=60.0).contains(&outcome.result.total));
+}
+
+#[test]
+fn test_huge_quantity_errors_before_rolling() {
+    let ast = parse(&"1000000000d1").unwrap();
+    let err = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(1)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Evaluation error: Dice quantity 1000000000 exceeds the maximum of 10000"
+    );
+}
+
+// ============================================================================
+// Die Size Limits
+// ============================================================================
+
+#[test]
+fn test_over_cap_die_size_errors() {
+    let config = EvalConfig {
+        max_die_size: 100,
+        ..EvalConfig::default()
+    };
+    let ast = parse(&"1d101").unwrap();
+    let err = eval_with_rng(&ast, config.clone(), StdRng::seed_from_u64(1)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Evaluation error: Die size 101 exceeds the maximum of 100"
+    );
+
+    let ast = parse(&"1d100").unwrap();
+    assert!(eval_with_rng(&ast, config, StdRng::seed_from_u64(1)).is_ok());
+}
+
+#[test]
+fn test_default_die_size_limit_is_finite() {
+    let ast = parse(&"1d4000000000").unwrap();
+    let err = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(1)).unwrap_err();
+    assert!(err.to_string().contains("exceeds the maximum"), "{}", err);
+}
+
+#[test]
+fn test_unlimited_quantity_stops_at_the_roll_limit() {
+    let config = EvalConfig {
+        max_dice: usize::MAX,
+        ..EvalConfig::default()
+    };
+    let ast = parse(&"1000000000000000d6").unwrap();
+    let err = eval_with_rng(&ast, config, StdRng::seed_from_u64(1)).unwrap_err();
+    assert!(
+        err.to_string().contains("Exceeded maximum number of rolls"),
+        "{}",
+        err
+    );
+}
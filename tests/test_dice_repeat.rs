@@ -0,0 +1,67 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{EvalConfig, Value, eval_with_rng, parse, roll};
+
+// ============================================================================
+// Repetition
+// ============================================================================
+
+#[test]
+fn test_prefix_and_postfix_repeat_are_equivalent() {
+    for (prefix, postfix) in [
+        ("3x1d6", "1d6 x3"),
+        ("3x1d6", "1d6x3"),
+        ("2x4d6kh3", "4d6kh3 × 2"),
+    ] {
+        let prefix_ast = parse(&prefix).unwrap();
+        let postfix_ast = parse(&postfix).unwrap();
+        assert_eq!(prefix_ast, postfix_ast);
+        assert_eq!(postfix_ast.to_string(), prefix);
+        for seed in 0..20 {
+            let rng = || StdRng::seed_from_u64(seed);
+            let left = eval_with_rng(&prefix_ast, EvalConfig::default(), rng()).unwrap();
+            let right = eval_with_rng(&postfix_ast, EvalConfig::default(), rng()).unwrap();
+            assert_eq!(left.total, right.total, "{} vs {}", prefix, postfix);
+        }
+    }
+}
+
+#[test]
+fn test_repeat_rolls_whole_pool() {
+    let result = roll(&"2d6 x3").unwrap();
+    let Value::Repeat { results } = &result.value else {
+        panic!("expected repeat result, got {:?}", result.value);
+    };
+    assert_eq!(results.len(), 3);
+    for repetition in results {
+        let Value::Dice(roll) = &repetition.value else {
+            panic!("expected dice result");
+        };
+        assert_eq!(roll.dice.len(), 2);
+    }
+    let sum: f64 = results.iter().map(|repetition| repetition.total).sum();
+    assert_eq!(result.total, sum);
+}
+
+#[test]
+fn test_repeat_binds_loosest() {
+    assert_eq!(parse(&"1d6 + 2 x3").unwrap().to_string(), "3x1d6 + 2");
+    for _ in 0..100 {
+        let val = roll(&"1d6 + 2 x3").unwrap().total;
+        assert!(
+            (9.0..=24.0).contains(&val),
+            "1d6 + 2 x3 out of range: {}",
+            val
+        );
+    }
+    assert_eq!(roll(&"(2 x3) * 2").unwrap().total, 12.0);
+}
+
+#[test]
+fn test_invalid_repeats() {
+    assert!(parse(&"3x1d6 x2").is_err());
+    assert!(parse(&"1d6 x0").is_err());
+    assert!(parse(&"1d6 x1.5").is_err());
+    assert!(parse(&"1d6 x").is_err());
+    assert!(parse(&"x3").is_err());
+    assert!(roll(&"20000x1").is_err());
+}
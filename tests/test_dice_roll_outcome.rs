@@ -0,0 +1,39 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{EvalConfig, eval_outcome, eval_with_rng, parse, roll_outcome};
+
+// ============================================================================
+// Roll Outcome
+// ============================================================================
+
+#[test]
+fn test_outcome_carries_config() {
+    let config = EvalConfig {
+        max_rolls: 42,
+        ..EvalConfig::default()
+    };
+    let outcome = roll_outcome(&"4d6kh3", config.clone()).unwrap();
+    assert_eq!(outcome.config.max_rolls, config.max_rolls);
+    assert!((3.0..=18.0).contains(&outcome.result.total));
+}
+
+#[test]
+fn test_outcome_result_matches_plain_evaluation() {
+    let ast = parse(&"3d6e==6 + 2").unwrap();
+    let config = EvalConfig {
+        max_rolls: 64,
+        ..EvalConfig::default()
+    };
+    let outcome = eval_outcome(&ast, config.clone(), StdRng::seed_from_u64(7)).unwrap();
+    let plain = eval_with_rng(&ast, config, StdRng::seed_from_u64(7)).unwrap();
+    assert_eq!(outcome.result.total, plain.total);
+    assert_eq!(outcome.config.max_rolls, 64);
+}
+
+#[test]
+fn test_outcome_propagates_errors() {
+    let config = EvalConfig {
+        max_rolls: 2,
+        ..EvalConfig::default()
+    };
+    assert!(roll_outcome(&"3d6", config).is_err());
+}
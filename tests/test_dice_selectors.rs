@@ -1,5 +1,9 @@
 mod common;
-use common::r;
+use common::{eval_faces, r, roll_faces};
+
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::testing::SequenceRng;
+use rollatorium::{EvalConfig, EvalResult, SelectorMatches, Value, eval_with_rng, parse};
 
 // ============================================================================
 // Selectors
@@ -64,3 +68,285 @@ fn test_selector_k_less_equal_expression() {
 fn test_selector_kh_dynamic_count_expression() {
     assert_eq!(r("(1, 2, 3, 4)kh(1+1)"), 7.0);
 }
+
+// ============================================================================
+// Selectors - Set Aggregates
+// ============================================================================
+
+#[test]
+fn test_selector_k_greater_than_mean() {
+    // mean is 3, so only 4 and 5 are strictly above it
+    assert_eq!(r("(1, 2, 3, 4, 5)k>mean"), 9.0);
+}
+
+#[test]
+fn test_selector_k_greater_equal_mean_keeps_ties() {
+    assert_eq!(r("(1, 2, 3, 4, 5)k>=mean"), 12.0);
+}
+
+#[test]
+fn test_selector_k_less_than_median_even_pool() {
+    // median of (1, 2, 10, 20) is 6
+    assert_eq!(r("(1, 2, 10, 20)k<median"), 3.0);
+}
+
+#[test]
+fn test_selector_drop_below_mean() {
+    assert_eq!(r("(2, 4, 6, 8)p<mean"), 14.0);
+}
+
+#[test]
+fn test_selector_k_less_than_sum() {
+    assert_eq!(r("(1, 1, 8)k<sum"), 10.0);
+}
+
+#[test]
+fn test_selector_mean_with_rolled_elements() {
+    for _ in 0..100 {
+        let val = r("(1d6, 1d6, 1d6)k>=mean");
+        assert!((1.0..=18.0).contains(&val), "unexpected total: {}", val);
+    }
+}
+
+#[test]
+fn test_aggregate_outside_selector_errors() {
+    assert!(rollatorium::roll(&"mean + 1").is_err());
+}
+
+#[test]
+fn test_selector_min_max_and_avg_on_sets() {
+    assert_eq!(r("(3, 1, 4, 1)p==min"), 7.0);
+    assert_eq!(r("(3, 1, 4, 1)k<max"), 5.0);
+    assert_eq!(r("(2, 4, 6, 8)k>avg"), 14.0);
+}
+
+// ============================================================================
+// Selectors - Dice Pool Aggregates
+// ============================================================================
+
+fn roll_sequence(expr: &str, rng: SequenceRng) -> EvalResult {
+    let ast = parse(&expr).unwrap();
+    eval_with_rng(&ast, EvalConfig::default(), rng).unwrap()
+}
+
+#[test]
+fn test_reroll_below_pool_average() {
+    // The pool averages 3.5, so the 1 and 2 reroll into 6 and 5.
+    let (result, roll) = roll_faces("4d6rr<avg", 6, &[1, 6, 2, 5, 6, 5], EvalConfig::default());
+    assert_eq!(result.total, 22.0);
+    let rolls: Vec<&[f64]> = roll.dice.iter().map(|die| die.rolls.as_slice()).collect();
+    assert_eq!(rolls, [&[1.0, 6.0][..], &[6.0], &[2.0, 5.0], &[5.0]]);
+}
+
+#[test]
+fn test_reroll_against_average_resolves_once() {
+    // The average stays 2.5 while the low dice reroll, so a rerolled 2 goes
+    // again though the new pool would average higher.
+    assert_eq!(
+        eval_faces("3d6rr<avg", 6, &[1, 4, 2, 6, 3], EvalConfig::default()).total,
+        13.0
+    );
+}
+
+#[test]
+fn test_dice_pool_aggregates() {
+    let roll_with = |expr: &str| eval_faces(expr, 6, &[2, 5, 3, 5], EvalConfig::default()).total;
+    assert_eq!(roll_with("4d6k>=mean"), 10.0);
+    assert_eq!(roll_with("4d6p==max"), 5.0);
+    assert_eq!(roll_with("4d6k>min"), 13.0);
+    assert_eq!(roll_with("4d6k<median"), 5.0);
+    assert_eq!(roll_with("4d6kh3p==min"), 10.0);
+}
+
+// ============================================================================
+// Selectors - Middle
+// ============================================================================
+
+#[test]
+fn test_keep_middle_one_keeps_the_median_die() {
+    let (result, roll) = roll_faces("5d6km1", 6, &[6, 2, 4, 1, 5], EvalConfig::default());
+    assert_eq!(result.total, 4.0);
+    let kept: Vec<usize> = (0..roll.dice.len())
+        .filter(|&idx| roll.dice[idx].kept)
+        .collect();
+    assert_eq!(kept, [2]);
+}
+
+#[test]
+fn test_keep_middle_uneven_split_drops_extra_high() {
+    assert_eq!(
+        eval_faces("4d6km1", 6, &[6, 2, 4, 1], EvalConfig::default()).total,
+        2.0
+    );
+    assert_eq!(
+        eval_faces("4d6km2", 6, &[6, 2, 4, 1], EvalConfig::default()).total,
+        6.0
+    );
+    assert_eq!(
+        eval_faces("4d6pm2", 6, &[6, 2, 4, 1], EvalConfig::default()).total,
+        7.0
+    );
+}
+
+#[test]
+fn test_keep_middle_on_sets() {
+    assert_eq!(r("(9, 1, 5, 7, 3)km3"), 15.0);
+    assert_eq!(r("(9, 1, 5)km5"), 15.0);
+    assert_eq!(r("(9, 1, 5)km0"), 0.0);
+}
+
+#[test]
+fn test_keep_middle_display() {
+    for input in ["5d6km1", "(1, 2, 3)pm1", "6d6km(1 + 1)"] {
+        assert_eq!(parse(&input).unwrap().to_string(), input);
+    }
+}
+
+// ============================================================================
+// Selectors - Ties
+// ============================================================================
+
+fn kept_indices(result: &EvalResult) -> Vec<usize> {
+    let Value::Dice(roll) = &result.value else {
+        panic!("expected dice, got {:?}", result.value);
+    };
+    (0..roll.dice.len())
+        .filter(|&idx| roll.dice[idx].kept)
+        .collect()
+}
+
+#[test]
+fn test_tied_dice_keep_earliest_rolled() {
+    let ast = parse(&"4d1kh2").unwrap();
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(7)).unwrap();
+    assert_eq!(kept_indices(&result), [0, 1]);
+    let tied =
+        |expr: &str| kept_indices(&eval_faces(expr, 6, &[3, 5, 3, 5], EvalConfig::default()));
+    assert_eq!(tied("4d6kl1"), [0]);
+    assert_eq!(tied("4d6kh1"), [1]);
+    assert_eq!(tied("4d6ph1"), [0, 2, 3]);
+}
+
+#[test]
+fn test_tied_set_elements_keep_earliest() {
+    let ast = parse(&"(2, 7, 7, 2)kh1").unwrap();
+    let result = eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(1)).unwrap();
+    let Value::Set(set) = &result.value else {
+        panic!("expected set, got {:?}", result.value);
+    };
+    let kept: Vec<bool> = set.elements.iter().map(|element| element.kept).collect();
+    assert_eq!(kept, [false, true, false, false]);
+}
+
+// ============================================================================
+// Rolled Selector Targets
+// ============================================================================
+
+#[test]
+fn test_rolled_target_keeps_against_one_threshold() {
+    let mut words: Vec<_> = [8, 3, 6, 9, 5, 7]
+        .into_iter()
+        .map(|face| SequenceRng::face(10, face))
+        .collect();
+    words.push(SequenceRng::face(4, 2));
+    let result = roll_sequence("6d10k>=(5 + 1d4)", SequenceRng::once(words));
+    assert_eq!(result.total, 24.0);
+}
+
+#[test]
+fn test_rolled_target_evaluated_once_per_reroll_loop() {
+    // A target rolled again on each pass would run the sequence dry.
+    let rng = SequenceRng::once([
+        SequenceRng::face(10, 1),
+        SequenceRng::face(10, 7),
+        SequenceRng::face(10, 2),
+        SequenceRng::face(4, 2),
+        SequenceRng::face(10, 5),
+        SequenceRng::face(10, 9),
+    ]);
+    assert_eq!(roll_sequence("3d10rr<(1d4 + 1)", rng).total, 21.0);
+}
+
+#[test]
+fn test_rolled_target_applies_consistently() {
+    let ast = parse(&"6d10k>=(5 + 1d4)").unwrap();
+    for seed in 0..200 {
+        let result =
+            eval_with_rng(&ast, EvalConfig::default(), StdRng::seed_from_u64(seed)).unwrap();
+        let Value::Dice(roll) = &result.value else {
+            panic!("expected dice result");
+        };
+        assert_eq!(roll.dice.len(), 6);
+        let consistent = (6..=9).any(|threshold| {
+            roll.dice
+                .iter()
+                .all(|die| die.dropped == (die.value < threshold as f64))
+        });
+        assert!(consistent, "seed {}: {:?}", seed, roll.dice);
+    }
+}
+
+// ============================================================================
+// Leading-Dot Targets
+// ============================================================================
+
+#[test]
+fn test_selector_leading_dot_target() {
+    assert_eq!(parse(&"1d6k<.5").unwrap().to_string(), "1d6k<0.5");
+    assert_eq!(r("1d6k<.5"), 0.0);
+    assert_eq!(r("(1, 2, 3)k>.5"), 6.0);
+}
+
+#[test]
+fn test_parenthesized_literal_die_size() {
+    assert_eq!(parse(&"1d(6)").unwrap().to_string(), "1d6");
+    for _ in 0..100 {
+        let value = r("1d(6)");
+        assert!((1.0..=6.0).contains(&value), "unexpected total: {}", value);
+    }
+}
+
+// ============================================================================
+// Selector Matches
+// ============================================================================
+
+fn matches_for(expr: &str, faces: &[u32], record: bool) -> Vec<SelectorMatches> {
+    let config = EvalConfig {
+        record_selector_matches: record,
+        ..EvalConfig::default()
+    };
+    roll_faces(expr, 10, faces, config).1.selector_matches
+}
+
+#[test]
+fn test_selector_matches_name_each_selector() {
+    let matches = matches_for("4d10k>5h1", &[7, 2, 9, 4], true);
+    assert_eq!(
+        matches,
+        [SelectorMatches {
+            operation: 0,
+            dice: vec![(0, vec![0]), (2, vec![0, 1])],
+        }]
+    );
+}
+
+#[test]
+fn test_selector_matches_for_die_only_highest_picks() {
+    let matches = matches_for("4d10k>8h1", &[7, 2, 3, 4], true);
+    assert_eq!(matches[0].dice, [(0, vec![1])]);
+}
+
+#[test]
+fn test_selector_matches_follow_recorded_operations() {
+    let matches = matches_for("4d10rr1p<3k>5h1", &[7, 2, 9, 4], true);
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].operation, 1);
+    assert_eq!(matches[0].dice, [(1, vec![0])]);
+    assert_eq!(matches[1].operation, 2);
+    assert_eq!(matches[1].dice, [(0, vec![0]), (2, vec![0, 1])]);
+}
+
+#[test]
+fn test_selector_matches_off_by_default() {
+    assert!(matches_for("4d10k>5h1", &[7, 2, 9, 4], false).is_empty());
+}
@@ -1,5 +1,6 @@
 mod common;
-use common::r;
+use common::{eval_faces, r, roll_faces};
+use rollatorium::{EvalConfig, parse};
 
 // ============================================================================
 // Set Operators - Keep
@@ -19,3 +20,119 @@ fn test_chained_keep_lowest() {
 fn test_keep_highest_and_lowest() {
     assert_eq!(r("(1, 2, 3, 4, 5)kh1kl1"), 6.0);
 }
+
+#[test]
+fn test_chained_keeps_take_union() {
+    assert_eq!(r("(1, 2, 3, 4, 5)kh2kl1"), 10.0);
+    assert_eq!(r("(1, 2, 3, 4, 5)kh1kh2"), 9.0);
+    assert_eq!(r("(5, 1, 2, 1)k1k2"), 4.0);
+}
+
+// ============================================================================
+// Set Operators - Keep - Advantage
+// ============================================================================
+
+#[test]
+fn test_elven_accuracy_desugars_to_three_dice() {
+    for input in ["d20eacc", "1d20eacc", "1d20 eacc"] {
+        let ast = rollatorium::parse(&input).unwrap();
+        assert_eq!(ast, rollatorium::parse(&"3d20kh1").unwrap(), "{}", input);
+        assert_eq!(ast.to_string(), "3d20kh1");
+    }
+}
+
+#[test]
+fn test_elven_accuracy_needs_a_single_numbered_die() {
+    for input in [
+        "2d20eacc",
+        "d%eacc",
+        "d(1d20)eacc",
+        "(1, 2)eacc",
+        "d20kh1eacc",
+    ] {
+        assert!(
+            rollatorium::parse(&input).is_err(),
+            "{} should not parse",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_advantage_and_disadvantage_shorthand() {
+    let eval = |input: &str| eval_faces(input, 20, &[7, 15], EvalConfig::default()).total;
+    assert_eq!(eval("d20adv"), 15.0);
+    assert_eq!(eval("d20dis"), 7.0);
+    assert_eq!(parse(&"d20adv").unwrap().to_string(), "2d20kh1");
+    assert_eq!(parse(&"d20dis").unwrap().to_string(), "2d20kl1");
+}
+
+#[test]
+fn test_advantage_needs_a_single_numbered_die() {
+    for input in ["2d20adv", "2d20dis", "d%adv", "(1, 2)adv", "d20kh1adv"] {
+        assert!(
+            rollatorium::parse(&input).is_err(),
+            "{} should not parse",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_keep_helpers_build_advantage_pools() {
+    let advantage = rollatorium::keep_highest(2, 20, 1);
+    assert_eq!(advantage, rollatorium::parse(&"2d20kh1").unwrap());
+    assert_eq!(
+        rollatorium::keep_highest(3, 20, 1),
+        rollatorium::parse(&"d20eacc").unwrap()
+    );
+    let disadvantage = rollatorium::keep_lowest(2, 20, 1);
+    assert_eq!(disadvantage.to_string(), "2d20kl1");
+    let val = rollatorium::eval(&rollatorium::keep_highest(4, 6, 3))
+        .unwrap()
+        .total;
+    assert!((3.0..=18.0).contains(&val));
+}
+
+#[test]
+fn test_elven_accuracy_range_skews_high() {
+    let mut sum = 0.0;
+    for _ in 0..2000 {
+        let val = r("d20eacc");
+        assert!((1.0..=20.0).contains(&val), "d20eacc out of range: {}", val);
+        sum += val;
+    }
+    // The highest of three d20s averages about 15.5.
+    let mean = sum / 2000.0;
+    assert!(mean > 14.5 && mean < 16.5, "mean {}", mean);
+}
+
+fn advantage_pair(expr: &str, faces: &[u32]) -> Option<(f64, f64, f64)> {
+    roll_faces(expr, 20, faces, EvalConfig::default())
+        .1
+        .advantage_pair()
+}
+
+#[test]
+fn test_advantage_pair() {
+    assert_eq!(advantage_pair("2d20kh1", &[7, 15]), Some((7.0, 15.0, 15.0)));
+    assert_eq!(advantage_pair("2d20kh1", &[18, 3]), Some((18.0, 3.0, 18.0)));
+}
+
+#[test]
+fn test_disadvantage_pair() {
+    assert_eq!(advantage_pair("2d20kl1", &[7, 15]), Some((7.0, 15.0, 7.0)));
+    assert_eq!(
+        advantage_pair("2d20kl1", &[12, 12]),
+        Some((12.0, 12.0, 12.0))
+    );
+}
+
+#[test]
+fn test_advantage_pair_needs_two_dice_and_one_keep() {
+    assert_eq!(advantage_pair("2d20", &[7, 15]), None);
+    assert_eq!(advantage_pair("3d20kh1", &[7, 15, 2]), None);
+    assert_eq!(advantage_pair("2d20kh2", &[7, 15]), None);
+    assert_eq!(advantage_pair("2d20p1", &[7, 15]), None);
+    assert_eq!(advantage_pair("2d20rr1kh1", &[7, 15]), None);
+}
@@ -0,0 +1,44 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{EvalConfig, ExprCache, eval_with_rng, parse};
+
+// ============================================================================
+// Expression Cache
+// ============================================================================
+
+#[test]
+fn test_cache_matches_uncached_rolls() {
+    let mut cache = ExprCache::new();
+    let mut cached_rng = StdRng::seed_from_u64(5);
+    let mut rng = StdRng::seed_from_u64(5);
+    for input in ["4d6kh3", "1d20 + 5", "4d6kh3", "4d6kh3"] {
+        let cached = cache
+            .roll(&input, EvalConfig::default(), &mut cached_rng)
+            .unwrap();
+        let expected =
+            eval_with_rng(&parse(&input).unwrap(), EvalConfig::default(), &mut rng).unwrap();
+        assert_eq!(cached.total, expected.total);
+    }
+}
+
+#[test]
+fn test_cache_grows_once_per_expression() {
+    let mut cache = ExprCache::new();
+    let mut rng = StdRng::seed_from_u64(5);
+    assert!(cache.is_empty());
+    cache.roll(&"2d6", EvalConfig::default(), &mut rng).unwrap();
+    assert_eq!(cache.len(), 1);
+    cache.roll(&"2d6", EvalConfig::default(), &mut rng).unwrap();
+    assert_eq!(cache.len(), 1);
+    cache.roll(&"1d8", EvalConfig::default(), &mut rng).unwrap();
+    assert_eq!(cache.len(), 2);
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_cache_skips_invalid_expressions() {
+    let mut cache = ExprCache::new();
+    let mut rng = StdRng::seed_from_u64(5);
+    assert!(cache.roll(&"2d", EvalConfig::default(), &mut rng).is_err());
+    assert!(cache.is_empty());
+}
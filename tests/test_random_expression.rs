@@ -0,0 +1,27 @@
+#![cfg(feature = "test-util")]
+
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::parse;
+use rollatorium::testing::random_expression;
+
+// ============================================================================
+// Random Expressions
+// ============================================================================
+
+#[test]
+fn test_random_expressions_parse() {
+    for depth in 0..=3 {
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let expr = random_expression(&mut rng, depth);
+            assert!(parse(&expr).is_ok(), "failed to parse {:?}", expr);
+        }
+    }
+}
+
+#[test]
+fn test_random_expressions_are_reproducible() {
+    let first = random_expression(&mut StdRng::seed_from_u64(9), 2);
+    let second = random_expression(&mut StdRng::seed_from_u64(9), 2);
+    assert_eq!(first, second);
+}
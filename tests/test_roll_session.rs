@@ -0,0 +1,54 @@
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{EvalConfig, RollSession, eval_with_rng, parse};
+
+// ============================================================================
+// Roll Session
+// ============================================================================
+
+const ROLLS: [&str; 4] = ["1d20 + 3", "2d6", "4d6kh3", "1d8 - 1"];
+
+#[test]
+fn test_session_total_is_sum_of_rolls() {
+    let mut session = RollSession::seeded(11, EvalConfig::default());
+    let mut sum = 0.0;
+    for input in ROLLS {
+        sum += session.roll(&input).unwrap();
+    }
+    assert_eq!(session.total(), sum);
+}
+
+#[test]
+fn test_session_shares_one_rng() {
+    let mut session = RollSession::seeded(11, EvalConfig::default());
+    let mut rng = StdRng::seed_from_u64(11);
+    for input in ROLLS {
+        let expected = eval_with_rng(&parse(&input).unwrap(), EvalConfig::default(), &mut rng)
+            .unwrap()
+            .total;
+        assert_eq!(session.roll(&input).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_session_reproducible_from_seed() {
+    let totals = |seed| {
+        let mut session = RollSession::seeded(seed, EvalConfig::default());
+        for input in ROLLS {
+            session.roll(&input).unwrap();
+        }
+        session.total()
+    };
+    assert_eq!(totals(3), totals(3));
+}
+
+#[test]
+fn test_session_reset_and_errors() {
+    let mut session = RollSession::seeded(0, EvalConfig::default());
+    session.roll(&"10").unwrap();
+    assert!(session.roll(&"1d").is_err());
+    assert_eq!(session.total(), 10.0);
+    session.reset();
+    assert_eq!(session.total(), 0.0);
+    assert_eq!(session.roll(&"2 + 3").unwrap(), 5.0);
+    assert_eq!(session.total(), 5.0);
+}
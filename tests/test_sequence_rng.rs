@@ -0,0 +1,69 @@
+use rand::RngCore;
+use rollatorium::testing::SequenceRng;
+use rollatorium::{EvalConfig, Value, eval_with_rng, parse};
+
+// ============================================================================
+// Sequence RNG
+// ============================================================================
+
+fn roll_with(expr: &str, rng: SequenceRng) -> rollatorium::EvalResult {
+    let ast = parse(&expr).unwrap();
+    eval_with_rng(&ast, EvalConfig::default(), rng).unwrap()
+}
+
+#[test]
+fn test_sequence_rng_keep_highest_breakdown() {
+    let result = roll_with("4d6kh3", SequenceRng::from_faces(6, &[5, 3, 2, 6]));
+    assert_eq!(result.total, 14.0);
+    let Value::Dice(roll) = &result.value else {
+        panic!("expected dice result");
+    };
+    let values: Vec<_> = roll.dice.iter().map(|die| die.value).collect();
+    let dropped: Vec<_> = roll.dice.iter().map(|die| die.dropped).collect();
+    assert_eq!(values, [5.0, 3.0, 2.0, 6.0]);
+    assert_eq!(dropped, [false, false, true, false]);
+    assert_eq!(
+        result.to_ansi(),
+        "4d6kh3 (5, 3, \x1b[2m2\x1b[0m, \x1b[32m6\x1b[0m) = 14"
+    );
+}
+
+#[test]
+fn test_sequence_rng_every_face() {
+    for sides in [2, 4, 6, 8, 10, 12, 20, 100, 1000] {
+        let faces: Vec<u32> = (1..=sides).collect();
+        let expr = format!("{}d{}", sides, sides);
+        let result = roll_with(&expr, SequenceRng::from_faces(sides, &faces));
+        let Value::Dice(roll) = &result.value else {
+            panic!("expected dice result");
+        };
+        let values: Vec<_> = roll.dice.iter().map(|die| die.value as u32).collect();
+        assert_eq!(values, faces, "d{}", sides);
+    }
+}
+
+#[test]
+fn test_sequence_rng_mixed_sizes() {
+    let rng = SequenceRng::once([SequenceRng::face(20, 17), SequenceRng::face(4, 3)]);
+    assert_eq!(roll_with("1d20 + 1d4", rng).total, 20.0);
+}
+
+#[test]
+fn test_sequence_rng_cycles() {
+    let mut rng = SequenceRng::new([1, 2]);
+    let words: Vec<_> = (0..5).map(|_| rng.next_u32()).collect();
+    assert_eq!(words, [1, 2, 1, 2, 1]);
+    assert_eq!(rng.remaining(), 1);
+}
+
+#[test]
+#[should_panic(expected = "ran out of values")]
+fn test_sequence_rng_once_exhausts() {
+    roll_with("3d6", SequenceRng::from_faces(6, &[1, 2]));
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn test_sequence_rng_rejects_bad_face() {
+    SequenceRng::face(6, 7);
+}
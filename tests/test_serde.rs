@@ -0,0 +1,57 @@
+#![cfg(feature = "serde")]
+
+use rand::{SeedableRng, rngs::StdRng};
+use rollatorium::{DieOrigin, EvalConfig, EvalResult, Value, eval_with_rng, parse};
+
+// ============================================================================
+// JSON Serialization
+// ============================================================================
+
+fn rolled(expr: &str, seed: u64) -> EvalResult {
+    eval_with_rng(
+        &parse(&expr).unwrap(),
+        EvalConfig::default(),
+        StdRng::seed_from_u64(seed),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_result_round_trips_through_json() {
+    let result = rolled("-1d6mi3 + 4d6kh3 [str] + 6d4e4 [x2] + (1d8, 3, 2d6)kh2", 11);
+    let json = serde_json::to_string(&result).unwrap();
+    let decoded: EvalResult = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(decoded.total, result.total);
+    // Every field shows up in the debug form, so matching it checks the whole tree.
+    assert_eq!(format!("{:?}", decoded), format!("{:?}", result));
+}
+
+#[test]
+fn test_values_are_tagged_with_their_type() {
+    let json = serde_json::to_value(rolled("1d6 + 2", 0)).unwrap();
+    let value = &json["value"];
+    assert_eq!(value["type"], "Binary");
+    assert_eq!(value["value"]["operator"], "Add");
+    assert_eq!(value["value"]["left"]["value"]["type"], "Dice");
+    assert_eq!(value["value"]["right"]["value"]["type"], "Literal");
+    assert_eq!(value["value"]["right"]["value"]["value"], 2.0);
+}
+
+#[test]
+fn test_die_origins_are_tagged() {
+    let result = rolled("10d4e4", 3);
+    let Value::Dice(roll) = &result.value else {
+        panic!("expected dice result");
+    };
+    let exploded = roll
+        .dice
+        .iter()
+        .position(|die| die.origin == DieOrigin::Explosion)
+        .expect("seed should trigger an explosion");
+
+    let json = serde_json::to_value(&result).unwrap();
+    let dice = &json["value"]["value"]["dice"];
+    assert_eq!(dice[0]["origin"]["type"], "Original");
+    assert_eq!(dice[exploded]["origin"]["type"], "Explosion");
+}
@@ -0,0 +1,108 @@
+use rollatorium::{EvalConfig, expected_value, parse, profile, sample_distribution};
+
+// ============================================================================
+// Stats - Expected Value
+// ============================================================================
+
+fn mean_of(expr: &str) -> f64 {
+    expected_value(&parse(&expr).unwrap()).unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn test_expected_value_of_plain_dice() {
+    assert_close(mean_of("1d6"), 3.5);
+    assert_close(mean_of("3d6"), 10.5);
+    assert_close(mean_of("d20"), 10.5);
+    assert_close(mean_of("2d%"), 90.0);
+    assert_close(mean_of("4dF"), 0.0);
+}
+
+#[test]
+fn test_expected_value_of_huge_dice() {
+    // Far too many faces to list, but the mean of a plain die is exact.
+    assert_close(mean_of("1d100000000000"), 50_000_000_000.5);
+    assert_close(mean_of("3d100000000000"), 150_000_000_001.5);
+    let kept = expected_value(&parse(&"2d100000000000kh1").unwrap());
+    assert!(kept.is_err());
+}
+
+#[test]
+fn test_expected_value_of_arithmetic() {
+    assert_close(mean_of("2d6 + 3"), 10.0);
+    assert_close(mean_of("1d8 - 1d4"), 2.0);
+    assert_close(mean_of("-1d6 * 2"), -7.0);
+    assert_close(mean_of("(1d4, 2, 1d6)"), 8.0);
+}
+
+#[test]
+fn test_expected_value_of_keep_and_drop() {
+    // Advantage and disadvantage on a d20.
+    assert_close(mean_of("2d20kh1"), 13.825);
+    assert_close(mean_of("2d20kl1"), 7.175);
+    assert_close(mean_of("4d6pl1"), mean_of("4d6kh3"));
+    assert_close(mean_of("4d6kh4"), 14.0);
+}
+
+#[test]
+fn test_expected_value_matches_exact_distribution() {
+    for expr in ["4d6kh3", "5d10kl2", "3d%kh1", "4dF ph1", "2d6 * 1d4"] {
+        let exact = profile(&parse(&expr).unwrap(), EvalConfig::default()).unwrap();
+        assert!(exact.exact);
+        assert_close(mean_of(expr), exact.mean);
+    }
+}
+
+#[test]
+fn test_expected_value_rejects_open_ended_operations() {
+    for expr in ["1d6e6", "2d6rr1", "1d6 / 2", "4d6k>3"] {
+        assert!(
+            expected_value(&parse(&expr).unwrap()).is_err(),
+            "{} has no closed form",
+            expr
+        );
+    }
+}
+
+// ============================================================================
+// Stats - Sampled Distribution
+// ============================================================================
+
+#[test]
+fn test_sample_distribution_of_2d6() {
+    let counts = sample_distribution(&"2d6", 100_000, 7).unwrap();
+    assert_eq!(
+        counts.keys().copied().collect::<Vec<_>>(),
+        (2..=12).collect::<Vec<_>>()
+    );
+    assert_eq!(counts.values().sum::<u64>(), 100_000);
+    let mode = counts.iter().max_by_key(|(_, count)| **count).unwrap().0;
+    assert_eq!(*mode, 7);
+}
+
+#[test]
+fn test_sample_distribution_is_reproducible() {
+    let first = sample_distribution(&"4d6kh3", 500, 3).unwrap();
+    assert_eq!(sample_distribution(&"4d6kh3", 500, 3).unwrap(), first);
+}
+
+#[test]
+fn test_sample_distribution_rounds_totals() {
+    let counts = sample_distribution(&"1d2 / 2", 1000, 0).unwrap();
+    // 0.5 rounds away from zero, so both faces land in bucket 1.
+    assert_eq!(counts.get(&1), Some(&1000));
+}
+
+#[test]
+fn test_sample_distribution_reports_errors() {
+    assert!(sample_distribution(&"2d6 +", 10, 0).is_err());
+    assert!(sample_distribution(&"1d0", 10, 0).is_err());
+}
@@ -0,0 +1,79 @@
+use rollatorium::{RollatoriumError, validate};
+
+// ============================================================================
+// Validate
+// ============================================================================
+
+fn validation_error(input: &str) -> String {
+    match validate(&input).unwrap_err() {
+        RollatoriumError::Eval(message) => message,
+        other => panic!("expected an evaluation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_validate_accepts_valid_expressions() {
+    for input in [
+        "4d6kh3 + 2",
+        "1d20adv",
+        "(1d6, 2d8)kh1",
+        "max(1d6, 3)",
+        "1d(1d6)",
+        "3x1d6",
+        "10d1000000",
+    ] {
+        assert!(validate(&input).is_ok(), "{} should validate", input);
+    }
+}
+
+#[test]
+fn test_validate_reports_parse_errors() {
+    assert!(matches!(
+        validate(&"1d6 +").unwrap_err(),
+        RollatoriumError::Parser { .. }
+    ));
+}
+
+#[test]
+fn test_validate_rejects_literal_limits() {
+    assert_eq!(
+        validation_error("1d2000000"),
+        "Die size 2000000 exceeds the maximum of 1000000"
+    );
+    assert_eq!(
+        validation_error("1 + 20000d6"),
+        "Dice quantity 20000 exceeds the maximum of 10000"
+    );
+    assert_eq!(validation_error("2d0"), "die size must be positive");
+    assert_eq!(
+        validation_error("1d6.5"),
+        "die size must be an integer, found 6.5"
+    );
+}
+
+#[test]
+fn test_validate_rejects_negative_selector_counts() {
+    assert_eq!(
+        validation_error("4d6kh(-1)"),
+        "selector must be non-negative"
+    );
+    assert_eq!(
+        validation_error("max(1, (1, 2)kl(-2))"),
+        "selector must be non-negative"
+    );
+}
+
+#[test]
+fn test_validate_matches_roll_errors() {
+    for input in ["1d2000000", "2d0", "4d6kh(-1)", "20000x1d6"] {
+        let validated = validate(&input).unwrap_err().to_string();
+        let rolled = rollatorium::roll(&input).unwrap_err().to_string();
+        assert_eq!(validated, rolled, "{}", input);
+    }
+}
+
+#[test]
+fn test_validate_leaves_rolled_values_to_eval() {
+    assert!(validate(&"1d(1d6 - 6)").is_ok());
+    assert!(validate(&"4d6kh(1d3 - 3)").is_ok());
+}